@@ -0,0 +1,392 @@
+//! Renders recorded scenes into images and animations.
+//!
+//! Run with `--help` for all options.
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process;
+
+use gymnarium_base::math::{Position2D, Size2D};
+
+use gymnarium_visualisers_base::image::{self, AnimationFormat, ImageFormat};
+use gymnarium_visualisers_base::rasterizer::{
+    AntiAliasing, Rasterizer2D, MAXIMUM_SAMPLES_PER_AXIS,
+};
+use gymnarium_visualisers_base::recorder::DEFAULT_FRAMES_PER_SECOND;
+use gymnarium_visualisers_base::scene::Scene;
+use gymnarium_visualisers_base::scene_stream::SceneStreamReader;
+use gymnarium_visualisers_base::svg::SvgExporter;
+use gymnarium_visualisers_base::{Color, Viewport2D, Viewport2DModification};
+
+const USAGE: &str = "\
+Usage: gymnarium_render [OPTIONS] <INPUT> <OUTPUT>
+
+Renders a scene stream or a JSON scene (or JSON array of scenes) into an image or animation.
+INPUT may be `-` to read from the standard input.
+
+The format is chosen by the extension of OUTPUT:
+  .png .ppm .pgm .svg   one file per frame; with several frames OUTPUT has to contain `{}`,
+                        which is replaced by the frame index
+  .gif .apng            one animation of all frames
+
+Options:
+  -s, --size <WIDTHxHEIGHT>          size of the output in pixels [default: 640x480]
+  -v, --viewport <X,Y,WIDTH,HEIGHT>  shown area around the center X,Y in world coordinates,
+                                     replaces the viewport of the scenes
+      --flip-x                       flips the x axis of the viewport given with --viewport
+      --flip-y                       flips the y axis of the viewport given with --viewport
+  -m, --modification <MODE>          how the viewport fits the output: loose, keep or scissor
+                                     [default: keep for --viewport, otherwise as recorded]
+  -f, --frames <RANGE>               frames to render as START:END with END exclusive, or a
+                                     single index; negative indices count from the end and
+                                     both bounds may be omitted [default: all frames]
+  -b, --background <COLOR>           replaces the background color of the scenes, given as
+                                     #RRGGBB[AA] or CSS color name
+  -r, --fps <NUMBER>                 frames per second of animations [default: 30]
+  -a, --anti-aliasing <MODE>         none, analytic or the samples per axis up to 16 [default: analytic]
+  -h, --help                         prints this help
+";
+
+fn main() {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::parse(&arguments) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(error) = run(&options) {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+/* --- --- --- Options --- --- --- */
+
+#[derive(Debug)]
+struct Options {
+    input: String,
+    output: String,
+    width: usize,
+    height: usize,
+    viewport: Option<Viewport2D>,
+    modification: Option<Viewport2DModification>,
+    frames: (Option<isize>, Option<isize>),
+    background_color: Option<Color>,
+    frames_per_second: f64,
+    anti_aliasing: AntiAliasing,
+}
+
+impl Options {
+    /// Returns `None` if the help was requested.
+    fn parse(arguments: &[String]) -> Result<Option<Self>, String> {
+        let mut positional = Vec::new();
+        let mut options = Self {
+            input: String::new(),
+            output: String::new(),
+            width: 640,
+            height: 480,
+            viewport: None,
+            modification: None,
+            frames: (None, None),
+            background_color: None,
+            frames_per_second: DEFAULT_FRAMES_PER_SECOND,
+            anti_aliasing: AntiAliasing::Analytic,
+        };
+        let (mut flip_x, mut flip_y) = (false, false);
+
+        let mut arguments = arguments.iter();
+        while let Some(argument) = arguments.next() {
+            let mut value = || {
+                arguments
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", argument))
+            };
+            match argument.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-s" | "--size" => {
+                    let (width, height) = parse_size(value()?)?;
+                    options.width = width;
+                    options.height = height;
+                }
+                "-v" | "--viewport" => options.viewport = Some(parse_viewport(value()?)?),
+                "--flip-x" => flip_x = true,
+                "--flip-y" => flip_y = true,
+                "-m" | "--modification" => {
+                    options.modification = Some(parse_modification(value()?)?)
+                }
+                "-f" | "--frames" => options.frames = parse_frames(value()?)?,
+                "-b" | "--background" => {
+                    options.background_color = Some(
+                        value()?
+                            .parse::<Color>()
+                            .map_err(|error| error.to_string())?,
+                    )
+                }
+                "-r" | "--fps" => {
+                    options.frames_per_second = value()?
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|fps| *fps > 0f64 && fps.is_finite())
+                        .ok_or("frames per second have to be a positive number")?
+                }
+                "-a" | "--anti-aliasing" => options.anti_aliasing = parse_anti_aliasing(value()?)?,
+                "-" => positional.push(argument.clone()),
+                unknown if unknown.starts_with('-') => {
+                    return Err(format!("unknown option {}", unknown))
+                }
+                _ => positional.push(argument.clone()),
+            }
+        }
+
+        if positional.len() != 2 {
+            return Err("expected exactly one INPUT and one OUTPUT".to_string());
+        }
+        options.output = positional.pop().unwrap();
+        options.input = positional.pop().unwrap();
+        match &mut options.viewport {
+            Some(viewport) => {
+                viewport.flipped_x_axis = flip_x;
+                viewport.flipped_y_axis = flip_y;
+            }
+            None if flip_x || flip_y => {
+                return Err("--flip-x and --flip-y require --viewport".to_string())
+            }
+            None => {}
+        }
+        Ok(Some(options))
+    }
+}
+
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("invalid size {}, expected WIDTHxHEIGHT", value);
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    match (width.parse::<usize>(), height.parse::<usize>()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_viewport(value: &str) -> Result<Viewport2D, String> {
+    let numbers: Vec<f64> = value
+        .split(',')
+        .map(|number| number.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("invalid viewport {}, expected X,Y,WIDTH,HEIGHT", value))?;
+    match numbers[..] {
+        [x, y, width, height] if width > 0f64 && height > 0f64 => Ok(Viewport2D::with(
+            Position2D::with(x, y),
+            Size2D::with(width, height),
+        )),
+        _ => Err(format!(
+            "invalid viewport {}, expected X,Y,WIDTH,HEIGHT with positive size",
+            value
+        )),
+    }
+}
+
+fn parse_modification(value: &str) -> Result<Viewport2DModification, String> {
+    match value {
+        "loose" => Ok(Viewport2DModification::LooseAspectRatio),
+        "keep" => Ok(Viewport2DModification::KeepAspectRatio),
+        "scissor" => Ok(Viewport2DModification::KeepAspectRatioAndScissorRemains),
+        _ => Err(format!(
+            "invalid modification {}, expected loose, keep or scissor",
+            value
+        )),
+    }
+}
+
+fn parse_frames(value: &str) -> Result<(Option<isize>, Option<isize>), String> {
+    let invalid = || format!("invalid frames {}, expected START:END or INDEX", value);
+    let bound = |bound: &str| -> Result<Option<isize>, String> {
+        if bound.is_empty() {
+            Ok(None)
+        } else {
+            bound.parse::<isize>().map(Some).map_err(|_| invalid())
+        }
+    };
+    match value.split_once(':') {
+        Some((start, end)) => Ok((bound(start)?, bound(end)?)),
+        None => {
+            let index = bound(value)?.ok_or_else(invalid)?;
+            // a single negative index must not end at zero, `-1` selects the last frame
+            let end = index.checked_add(1).filter(|end| *end != 0);
+            Ok((Some(index), end))
+        }
+    }
+}
+
+fn parse_anti_aliasing(value: &str) -> Result<AntiAliasing, String> {
+    match value {
+        "none" => Ok(AntiAliasing::None),
+        "analytic" => Ok(AntiAliasing::Analytic),
+        samples => match samples.parse::<u32>() {
+            Ok(samples_per_axis) if (1..=MAXIMUM_SAMPLES_PER_AXIS).contains(&samples_per_axis) => {
+                Ok(AntiAliasing::Supersampling(samples_per_axis))
+            }
+            _ => Err(format!(
+                "invalid anti-aliasing {}, expected none, analytic or a number from 1 to {}",
+                value, MAXIMUM_SAMPLES_PER_AXIS
+            )),
+        },
+    }
+}
+
+/* --- --- --- Rendering --- --- --- */
+
+#[derive(Debug, Copy, Clone)]
+enum OutputFormat {
+    Svg,
+    Image,
+    Animation,
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let output_format = output_format(&options.output)?;
+    let scenes = load_scenes(&options.input)?;
+    let (first_frame, end_frame) = frame_range(options.frames, scenes.len())?;
+    let scenes = &scenes[first_frame..end_frame];
+
+    let rasterizer =
+        Rasterizer2D::with(options.width, options.height).anti_aliasing(options.anti_aliasing);
+    let rasterize = |scene: &Scene| {
+        let mut rasterizer = rasterizer.clone();
+        rasterizer.viewport = view(scene, options);
+        rasterizer.background_color = background_color(scene, options);
+        rasterizer.rasterize(&scene.geometries)
+    };
+
+    match output_format {
+        OutputFormat::Animation => {
+            let frames: Vec<_> = scenes.iter().map(rasterize).collect();
+            image::save_animation(&frames, options.frames_per_second, &options.output)
+                .map_err(|error| error.to_string())
+        }
+        OutputFormat::Svg | OutputFormat::Image => {
+            if scenes.len() > 1 && !options.output.contains("{}") {
+                return Err(format!(
+                    "{} frames selected, but {} holds a single frame; add `{{}}` to its name, \
+                     select one frame with --frames or use .gif or .apng",
+                    scenes.len(),
+                    options.output
+                ));
+            }
+            let index_width = (end_frame - 1).to_string().len();
+            for (index, scene) in (first_frame..).zip(scenes) {
+                let path = options
+                    .output
+                    .replace("{}", &format!("{:0width$}", index, width = index_width));
+                match output_format {
+                    OutputFormat::Svg => {
+                        let mut exporter = SvgExporter::with(options.width, options.height)
+                            .background_color(background_color(scene, options));
+                        exporter.viewport = view(scene, options);
+                        fs::write(&path, exporter.export(&scene.geometries))
+                            .map_err(|error| format!("could not write {}: {}", path, error))?;
+                    }
+                    _ => {
+                        image::save(&rasterize(scene), &path).map_err(|error| error.to_string())?
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn output_format(output: &str) -> Result<OutputFormat, String> {
+    let path = Path::new(output);
+    let is_svg = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+    if is_svg {
+        Ok(OutputFormat::Svg)
+    } else if ImageFormat::from_path(path).is_some() {
+        Ok(OutputFormat::Image)
+    } else if AnimationFormat::from_path(path).is_some() {
+        Ok(OutputFormat::Animation)
+    } else {
+        Err(format!(
+            "unknown output format of {}, expected .png, .ppm, .pgm, .svg, .gif or .apng",
+            output
+        ))
+    }
+}
+
+/// Reads a scene stream, a JSON scene or a JSON array of scenes.
+fn load_scenes(input: &str) -> Result<Vec<Scene>, String> {
+    let mut data = Vec::new();
+    let read = if input == "-" {
+        std::io::stdin().read_to_end(&mut data).map(|_| ())
+    } else {
+        fs::read(input).map(|content| data = content)
+    };
+    read.map_err(|error| format!("could not read {}: {}", input, error))?;
+
+    if data.starts_with(b"GYMSCENE") {
+        return SceneStreamReader::new(&data[..])
+            .and_then(|mut reader| reader.read_all())
+            .map_err(|error| format!("could not read {}: {}", input, error));
+    }
+    let json = |error: serde_json::Error| format!("could not read {} as scene: {}", input, error);
+    match data.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'[') => serde_json::from_slice(&data).map_err(json),
+        _ => serde_json::from_slice(&data)
+            .map(|scene| vec![scene])
+            .map_err(json),
+    }
+}
+
+/// Resolves the selected frames to a non-empty range of indices.
+fn frame_range(
+    (start, end): (Option<isize>, Option<isize>),
+    frame_count: usize,
+) -> Result<(usize, usize), String> {
+    let resolve = |index: isize| {
+        if index < 0 {
+            frame_count.saturating_sub(index.unsigned_abs())
+        } else {
+            (index as usize).min(frame_count)
+        }
+    };
+    let start = start.map_or(0, resolve);
+    let end = end.map_or(frame_count, resolve);
+    if start >= end {
+        Err(format!(
+            "no frames selected, the input contains {} frames",
+            frame_count
+        ))
+    } else {
+        Ok((start, end))
+    }
+}
+
+fn view(scene: &Scene, options: &Options) -> Option<(Viewport2D, Viewport2DModification)> {
+    match (options.viewport, scene.viewport) {
+        (Some(viewport), _) => Some((
+            viewport,
+            options
+                .modification
+                .unwrap_or(Viewport2DModification::KeepAspectRatio),
+        )),
+        (None, Some((viewport, modification))) => {
+            Some((viewport, options.modification.unwrap_or(modification)))
+        }
+        (None, None) => None,
+    }
+}
+
+fn background_color(scene: &Scene, options: &Options) -> Color {
+    options
+        .background_color
+        .or(scene.background_color)
+        .unwrap_or_else(Color::white)
+}
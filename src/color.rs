@@ -0,0 +1,527 @@
+//! Conversions of `Color` between color spaces, hex strings and CSS color names.
+//!
+//! Hues are given in degrees, saturation, value and lightness in `[0, 1]`. Colors created from
+//! HSV or HSL are opaque, the alpha channel is ignored when converting into them.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::{Color, Pixel};
+
+/// The named colors of CSS, sorted by their lowercase name, as `0xRRGGBB`.
+///
+/// The keyword `transparent` is not contained, `Color::named` handles it separately.
+pub const CSS_NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+/* --- --- --- ParseColorError --- --- --- */
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseColorError(pub String);
+
+impl Display for ParseColorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid color {}, expected #RGB, #RGBA, #RRGGBB, #RRGGBBAA or a CSS color name",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseColorError {}
+
+/* --- --- --- Color --- --- --- */
+
+impl Color {
+    /// Creates an opaque color from hue, saturation and value.
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> Self {
+        let (saturation, value) = (saturation.clamp(0f64, 1f64), value.clamp(0f64, 1f64));
+        let chroma = value * saturation;
+        from_hue_and_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Returns hue, saturation and value, hue and saturation are zero for grays.
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let (hue, maximum, minimum) = self.hue_and_extrema();
+        let saturation = if maximum > 0f64 {
+            (maximum - minimum) / maximum
+        } else {
+            0f64
+        };
+        (hue, saturation, maximum)
+    }
+
+    /// Creates an opaque color from hue, saturation and lightness.
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        let (saturation, lightness) = (saturation.clamp(0f64, 1f64), lightness.clamp(0f64, 1f64));
+        let chroma = (1f64 - (2f64 * lightness - 1f64).abs()) * saturation;
+        from_hue_and_chroma(hue, chroma, lightness - chroma / 2f64)
+    }
+
+    /// Returns hue, saturation and lightness, hue and saturation are zero for grays.
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let (hue, maximum, minimum) = self.hue_and_extrema();
+        let lightness = (maximum + minimum) / 2f64;
+        let saturation = if maximum > minimum {
+            (maximum - minimum) / (1f64 - (2f64 * lightness - 1f64).abs())
+        } else {
+            0f64
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// Parses `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA` with or without the leading `#`.
+    ///
+    /// Colors without alpha are opaque.
+    pub fn from_hex(hex: &str) -> Result<Self, ParseColorError> {
+        let invalid = || ParseColorError(hex.to_string());
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let digits_per_channel = match digits.len() {
+            3 | 4 => 1,
+            6 | 8 => 2,
+            _ => return Err(invalid()),
+        };
+        let channel = |index: usize| {
+            let start = index * digits_per_channel;
+            match digits.get(start..start + digits_per_channel) {
+                // a single digit is repeated, `#f80` is `#ff8800`
+                Some(digit) if digits_per_channel == 1 => {
+                    u8::from_str_radix(digit, 16).map(|value| value * 17)
+                }
+                Some(digits) => u8::from_str_radix(digits, 16),
+                None => Ok(255),
+            }
+            .map_err(|_| invalid())
+        };
+        Ok(Self::with(
+            channel(0)?,
+            channel(1)?,
+            channel(2)?,
+            channel(3)?,
+        ))
+    }
+
+    /// Returns `#rrggbb` for opaque colors and `#rrggbbaa` otherwise.
+    pub fn to_hex(&self) -> String {
+        if self.alpha == 255 {
+            format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+        } else {
+            format!(
+                "#{:02x}{:02x}{:02x}{:02x}",
+                self.red, self.green, self.blue, self.alpha
+            )
+        }
+    }
+
+    /// Returns the opaque CSS color with the given case-insensitive name, or
+    /// `Color::transparent()` for `transparent`.
+    pub fn named(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name == "transparent" {
+            return Some(Self::transparent());
+        }
+        CSS_NAMED_COLORS
+            .binary_search_by(|(candidate, _)| candidate.cmp(&name.as_str()))
+            .ok()
+            .map(|index| {
+                let rgb = CSS_NAMED_COLORS[index].1;
+                Self::with((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+            })
+    }
+
+    /// Interpolates linearly between this color at `0` and the other one at `1`, for every
+    /// channel including alpha.
+    ///
+    /// The factor is limited to `[0, 1]`.
+    pub fn lerp(&self, other: &Color, factor: f64) -> Self {
+        let factor = factor.clamp(0f64, 1f64);
+        let channel =
+            |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * factor).round() as u8;
+        Self::with(
+            channel(self.red, other.red),
+            channel(self.green, other.green),
+            channel(self.blue, other.blue),
+            channel(self.alpha, other.alpha),
+        )
+    }
+
+    /// Returns the hue together with the largest and smallest channel in `[0, 1]`.
+    fn hue_and_extrema(&self) -> (f64, f64, f64) {
+        let [red, green, blue] = [self.red, self.green, self.blue].map(|c| c as f64 / 255f64);
+        let maximum = red.max(green).max(blue);
+        let minimum = red.min(green).min(blue);
+        let chroma = maximum - minimum;
+        let hue = if chroma == 0f64 {
+            0f64
+        } else if maximum == red {
+            60f64 * ((green - blue) / chroma).rem_euclid(6f64)
+        } else if maximum == green {
+            60f64 * ((blue - red) / chroma + 2f64)
+        } else {
+            60f64 * ((red - green) / chroma + 4f64)
+        };
+        (hue, maximum, minimum)
+    }
+}
+
+/// Creates an opaque color from the hue and chroma with all channels raised by the offset.
+fn from_hue_and_chroma(hue: f64, chroma: f64, offset: f64) -> Color {
+    let sector = hue.rem_euclid(360f64) / 60f64;
+    let second = chroma * (1f64 - (sector.rem_euclid(2f64) - 1f64).abs());
+    let (red, green, blue) = match sector as u32 {
+        0 => (chroma, second, 0f64),
+        1 => (second, chroma, 0f64),
+        2 => (0f64, chroma, second),
+        3 => (0f64, second, chroma),
+        4 => (second, 0f64, chroma),
+        _ => (chroma, 0f64, second),
+    };
+    let channel = |value: f64| ((value + offset).clamp(0f64, 1f64) * 255f64).round() as u8;
+    Color::with(channel(red), channel(green), channel(blue), 255)
+}
+
+/// Parses hex strings like `Color::from_hex` and CSS color names like `Color::named`.
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        match Self::named(value) {
+            Some(color) => Ok(color),
+            None => Self::from_hex(value).map_err(|_| ParseColorError(value.to_string())),
+        }
+    }
+}
+
+/// Pixels are opaque colors.
+impl From<Pixel> for Color {
+    fn from(pixel: Pixel) -> Self {
+        Self::with(pixel.red, pixel.green, pixel.blue, 255)
+    }
+}
+
+/// Drops the alpha channel, use `Pixel::blend` to composite translucent colors instead.
+impl From<Color> for Pixel {
+    fn from(color: Color) -> Self {
+        Self::with(color.red, color.green, color.blue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Colors with every channel in steps of 17, including all grays.
+    fn colors() -> impl Iterator<Item = Color> {
+        (0..16u8).flat_map(|red| {
+            (0..16u8).flat_map(move |green| {
+                (0..16u8).map(move |blue| Color::with(red * 17, green * 17, blue * 17, 255))
+            })
+        })
+    }
+
+    fn assert_close(actual: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let difference = (actual.0 - expected.0)
+            .abs()
+            .max((actual.1 - expected.1).abs())
+            .max((actual.2 - expected.2).abs());
+        assert!(difference < 1e-9, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn hsv_round_trips() {
+        for color in colors() {
+            let (hue, saturation, value) = color.to_hsv();
+            assert_eq!(Color::from_hsv(hue, saturation, value), color);
+        }
+        assert_eq!(
+            Color::from_hsv(0f64, 1f64, 1f64),
+            Color::with(255, 0, 0, 255)
+        );
+        assert_eq!(
+            Color::from_hsv(120f64, 1f64, 1f64),
+            Color::with(0, 255, 0, 255)
+        );
+        assert_eq!(
+            Color::from_hsv(-120f64, 1f64, 1f64),
+            Color::with(0, 0, 255, 255)
+        );
+        assert_eq!(
+            Color::from_hsv(420f64, 2f64, 0.5f64),
+            Color::with(128, 128, 0, 255)
+        );
+        assert_close(Color::with(0, 0, 255, 255).to_hsv(), (240f64, 1f64, 1f64));
+        assert_close(
+            Color::with(255, 128, 0, 0).to_hsv(),
+            (60f64 * 128f64 / 255f64, 1f64, 1f64),
+        );
+        assert_close(Color::with(51, 51, 51, 255).to_hsv(), (0f64, 0f64, 0.2f64));
+    }
+
+    #[test]
+    fn hsl_round_trips() {
+        for color in colors() {
+            let (hue, saturation, lightness) = color.to_hsl();
+            assert_eq!(Color::from_hsl(hue, saturation, lightness), color);
+        }
+        assert_eq!(
+            Color::from_hsl(0f64, 1f64, 0.5f64),
+            Color::with(255, 0, 0, 255)
+        );
+        assert_eq!(
+            Color::from_hsl(240f64, 1f64, 0.75f64),
+            Color::with(128, 128, 255, 255)
+        );
+        assert_eq!(Color::from_hsl(180f64, 0.5f64, 1f64), Color::white());
+        assert_close(Color::with(0, 255, 0, 255).to_hsl(), (120f64, 1f64, 0.5f64));
+        assert_close(Color::white().to_hsl(), (0f64, 0f64, 1f64));
+        assert_close(Color::black().to_hsl(), (0f64, 0f64, 0f64));
+    }
+
+    #[test]
+    fn hex_strings_are_parsed() {
+        assert_eq!(Color::from_hex("#f80"), Ok(Color::with(255, 136, 0, 255)));
+        assert_eq!(Color::from_hex("F808"), Ok(Color::with(255, 136, 0, 136)));
+        assert_eq!(
+            Color::from_hex("#12aBef"),
+            Ok(Color::with(18, 171, 239, 255))
+        );
+        assert_eq!(
+            Color::from_hex("12abef80"),
+            Ok(Color::with(18, 171, 239, 128))
+        );
+        for invalid in [
+            "",
+            "#",
+            "#12",
+            "#12345",
+            "#1234567",
+            "#123456789",
+            "#12345g",
+            "##123",
+            "+1+2+3",
+            "#１２３",
+        ] {
+            assert_eq!(
+                Color::from_hex(invalid),
+                Err(ParseColorError(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn hex_strings_hold_alpha_only_if_translucent() {
+        assert_eq!(Color::with(18, 171, 239, 255).to_hex(), "#12abef");
+        assert_eq!(Color::with(0, 1, 2, 3).to_hex(), "#00010203");
+        for color in [Color::with(18, 171, 239, 255), Color::with(0, 1, 2, 3)] {
+            assert_eq!(Color::from_hex(&color.to_hex()), Ok(color));
+        }
+    }
+
+    #[test]
+    fn named_colors_are_sorted() {
+        // `Color::named` looks the names up by binary search
+        for pair in CSS_NAMED_COLORS.windows(2) {
+            assert!(pair[0].0 < pair[1].0, "{} before {}", pair[0].0, pair[1].0);
+        }
+        for (name, _) in &CSS_NAMED_COLORS {
+            assert_eq!(name.to_ascii_lowercase(), *name);
+            assert!(Color::named(name).is_some(), "{} not found", name);
+        }
+    }
+
+    #[test]
+    fn named_colors_are_found_regardless_of_case() {
+        assert_eq!(
+            Color::named("AliceBlue"),
+            Some(Color::with(240, 248, 255, 255))
+        );
+        assert_eq!(
+            Color::named("rebeccapurple"),
+            Some(Color::with(102, 51, 153, 255))
+        );
+        assert_eq!(
+            Color::named("YELLOWGREEN"),
+            Some(Color::with(154, 205, 50, 255))
+        );
+        assert_eq!(Color::named("Transparent"), Some(Color::transparent()));
+        assert_eq!(Color::named("grey"), Color::named("gray"));
+        assert_eq!(Color::named("nocolor"), None);
+        assert_eq!(Color::named(""), None);
+        assert_eq!(" Teal ".parse(), Ok(Color::with(0, 128, 128, 255)));
+        assert_eq!("#0808".parse(), Ok(Color::with(0, 136, 0, 136)));
+        assert!("teal!".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn lerp_interpolates_every_channel() {
+        let from = Color::with(0, 100, 255, 0);
+        let to = Color::with(255, 200, 0, 255);
+        assert_eq!(from.lerp(&to, 0f64), from);
+        assert_eq!(from.lerp(&to, 1f64), to);
+        assert_eq!(from.lerp(&to, 0.5f64), Color::with(128, 150, 128, 128));
+        assert_eq!(from.lerp(&to, 0.25f64), Color::with(64, 125, 191, 64));
+        assert_eq!(from.lerp(&to, -1f64), from);
+        assert_eq!(from.lerp(&to, 2f64), to);
+    }
+
+    #[test]
+    fn pixels_are_opaque_colors() {
+        assert_eq!(Color::from(Pixel::with(1, 2, 3)), Color::with(1, 2, 3, 255));
+        assert_eq!(Pixel::from(Color::with(1, 2, 3, 4)), Pixel::with(1, 2, 3));
+        assert_eq!(Pixel::from(Color::transparent()), Pixel::black());
+    }
+}
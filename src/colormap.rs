@@ -0,0 +1,378 @@
+//! Colormaps turning values into colors and palettes of distinct colors for categories.
+//!
+//! The perceptually uniform maps `viridis`, `magma`, `plasma`, `inferno` and `cividis` are
+//! those of matplotlib, the diverging maps and the palettes besides `tableau10` and `okabe_ito`
+//! those of ColorBrewer. Maps interpolate linearly between evenly spaced samples of the
+//! originals, which keeps them within a few steps per channel of them.
+
+use crate::Color;
+
+/* --- --- --- Colormap --- --- --- */
+
+/// Maps values inside a range continuously onto colors.
+///
+/// The stops are spread evenly over the range and the colors between them are interpolated
+/// linearly, the first stop belongs to the minimum and the last one to the maximum.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Colormap {
+    pub stops: Vec<Color>,
+}
+
+impl Colormap {
+    /// Creates a new colormap from the given stops, without any stop every value is
+    /// transparent.
+    pub fn with(stops: Vec<Color>) -> Self {
+        Self { stops }
+    }
+
+    /// Dark blue over green to yellow, perceptually uniform.
+    pub fn viridis() -> Self {
+        Self::from_table(&VIRIDIS)
+    }
+
+    /// Black over purple and orange to light yellow, perceptually uniform.
+    pub fn magma() -> Self {
+        Self::from_table(&MAGMA)
+    }
+
+    /// Dark blue over purple and orange to yellow, perceptually uniform.
+    pub fn plasma() -> Self {
+        Self::from_table(&PLASMA)
+    }
+
+    /// Black over purple and orange to bright yellow, perceptually uniform.
+    pub fn inferno() -> Self {
+        Self::from_table(&INFERNO)
+    }
+
+    /// Dark blue over gray to yellow, perceptually uniform and readable with color vision
+    /// deficiencies.
+    pub fn cividis() -> Self {
+        Self::from_table(&CIVIDIS)
+    }
+
+    /// Dark red over white to dark blue, diverging.
+    pub fn red_blue() -> Self {
+        Self::from_table(&RED_BLUE)
+    }
+
+    /// Red over light yellow to blue, diverging.
+    pub fn red_yellow_blue() -> Self {
+        Self::from_table(&RED_YELLOW_BLUE)
+    }
+
+    /// Red over light yellow and green to purple, diverging.
+    pub fn spectral() -> Self {
+        Self::from_table(&SPECTRAL)
+    }
+
+    /// Brown over white to blue green, diverging.
+    pub fn brown_blue_green() -> Self {
+        Self::from_table(&BROWN_BLUE_GREEN)
+    }
+
+    /// Pink over white to yellow green, diverging.
+    pub fn pink_yellow_green() -> Self {
+        Self::from_table(&PINK_YELLOW_GREEN)
+    }
+
+    /// Orange over white to purple, diverging.
+    pub fn purple_orange() -> Self {
+        Self::from_table(&PURPLE_ORANGE)
+    }
+
+    /// Returns the colormap running from the last to the first stop.
+    pub fn reversed(mut self) -> Self {
+        self.stops.reverse();
+        self
+    }
+
+    /// Returns the color of the value inside the range from minimum to maximum.
+    ///
+    /// Values outside of the range get the color of the nearest end, a range without extent
+    /// the color of its center. Values which are not a number are transparent.
+    pub fn map(&self, value: f64, minimum: f64, maximum: f64) -> Color {
+        let fraction = if maximum != minimum {
+            (value - minimum) / (maximum - minimum)
+        } else {
+            0.5f64
+        };
+        self.color_at(fraction)
+    }
+
+    /// Returns the color at the fraction, where `0` is the first and `1` the last stop.
+    ///
+    /// Fractions outside of `[0, 1]` get the color of the nearest end, fractions which are not
+    /// a number are transparent.
+    pub fn color_at(&self, fraction: f64) -> Color {
+        if fraction.is_nan() || self.stops.is_empty() {
+            return Color::transparent();
+        }
+        let position = fraction.clamp(0f64, 1f64) * (self.stops.len() - 1) as f64;
+        let index = (position.floor() as usize).min(self.stops.len().saturating_sub(2));
+        match self.stops.get(index + 1) {
+            Some(next) => self.stops[index].lerp(next, position - index as f64),
+            None => self.stops[index],
+        }
+    }
+
+    fn from_table(table: &[u32]) -> Self {
+        Self::with(table.iter().map(|rgb| color_from_rgb(*rgb)).collect())
+    }
+}
+
+/* --- --- --- Palette --- --- --- */
+
+/// Distinct colors for categories like the ids of agents, repeated once exhausted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    pub colors: Vec<Color>,
+}
+
+impl Palette {
+    pub fn with(colors: Vec<Color>) -> Self {
+        Self { colors }
+    }
+
+    /// The ten categorical colors of Tableau, the default colors of matplotlib.
+    pub fn tableau10() -> Self {
+        Self::from_table(&TABLEAU10)
+    }
+
+    /// Nine strong colors.
+    pub fn set1() -> Self {
+        Self::from_table(&SET1)
+    }
+
+    /// Eight pastel colors.
+    pub fn set2() -> Self {
+        Self::from_table(&SET2)
+    }
+
+    /// Eight dark colors.
+    pub fn dark2() -> Self {
+        Self::from_table(&DARK2)
+    }
+
+    /// Six pairs of a light and a dark color of the same hue.
+    pub fn paired() -> Self {
+        Self::from_table(&PAIRED)
+    }
+
+    /// The eight colors of Okabe and Ito, distinguishable with color vision deficiencies.
+    pub fn okabe_ito() -> Self {
+        Self::from_table(&OKABE_ITO)
+    }
+
+    /// Returns the color of the category, starting over with the first color after the last
+    /// one. Without any colors every category is transparent.
+    pub fn color(&self, index: usize) -> Color {
+        if self.colors.is_empty() {
+            Color::transparent()
+        } else {
+            self.colors[index % self.colors.len()]
+        }
+    }
+
+    fn from_table(table: &[u32]) -> Self {
+        Self::with(table.iter().map(|rgb| color_from_rgb(*rgb)).collect())
+    }
+}
+
+fn color_from_rgb(rgb: u32) -> Color {
+    Color::with((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+}
+
+/* --- --- --- Tables --- --- --- */
+
+const VIRIDIS: [u32; 33] = [
+    0x440154, 0x460c5f, 0x47176a, 0x482273, 0x472c7b, 0x453681, 0x423f86, 0x3e4889, 0x3b518b,
+    0x385a8d, 0x34628e, 0x306a8e, 0x2c728e, 0x29798e, 0x26808e, 0x23888d, 0x20908c, 0x1f988b,
+    0x20a088, 0x23a785, 0x29ae81, 0x32b57b, 0x3dbc74, 0x4cc26c, 0x5dc962, 0x70cf57, 0x82d44a,
+    0x97d83d, 0xacdc30, 0xc2e026, 0xd8e21f, 0xece51e, 0xfde725,
+];
+
+const MAGMA: [u32; 33] = [
+    0x000004, 0x030515, 0x0a0a25, 0x130d35, 0x1d0f45, 0x290f57, 0x360f67, 0x430f75, 0x4f137b,
+    0x5c177f, 0x691c81, 0x762082, 0x832483, 0x8f2982, 0x9b2e80, 0xa9327c, 0xb63679, 0xc33b74,
+    0xd0426f, 0xdb4969, 0xe55263, 0xee5c5e, 0xf5695d, 0xf9775f, 0xfc8763, 0xfd9869, 0xffa671,
+    0xffb57b, 0xfec387, 0xfcd495, 0xfae4a3, 0xf9f2b2, 0xfcfdbf,
+];
+
+const PLASMA: [u32; 33] = [
+    0x0d0887, 0x1e088e, 0x2f0795, 0x3e049c, 0x4c02a1, 0x5900a5, 0x6500a7, 0x7101a8, 0x7d05a7,
+    0x890ba5, 0x9512a1, 0x9f1a9c, 0xa92296, 0xb22b8f, 0xbb3588, 0xc33e80, 0xcb4779, 0xd35071,
+    0xda5969, 0xe06363, 0xe66c5c, 0xeb7655, 0xf0804e, 0xf58a47, 0xf89540, 0xfba039, 0xfdac33,
+    0xfeb82d, 0xfec428, 0xfdd223, 0xfae01f, 0xf6ed1e, 0xf0f921,
+];
+
+const INFERNO: [u32; 33] = [
+    0x000004, 0x03051c, 0x0b082e, 0x150b3b, 0x210c48, 0x2e0c55, 0x3b0c61, 0x480c6a, 0x560f6d,
+    0x63146f, 0x70196e, 0x7c1d6d, 0x89216b, 0x962667, 0xa22b61, 0xaf315c, 0xbb3755, 0xc63e4d,
+    0xd14644, 0xdb4f3a, 0xe35a31, 0xeb6528, 0xf1711d, 0xf67f12, 0xf98d0a, 0xfb9d07, 0xfbab10,
+    0xfabb1e, 0xf8ca32, 0xf6da48, 0xf5e960, 0xf6f57f, 0xfcffa4,
+];
+
+const CIVIDIS: [u32; 10] = [
+    0x00224e, 0x123570, 0x3b496c, 0x575d6d, 0x707173, 0x8a8678, 0xa59c74, 0xc3b369, 0xe1cc55,
+    0xfee838,
+];
+
+const RED_BLUE: [u32; 11] = [
+    0x67001f, 0xb2182b, 0xd6604d, 0xf4a582, 0xfddbc7, 0xf7f7f7, 0xd1e5f0, 0x92c5de, 0x4393c3,
+    0x2166ac, 0x053061,
+];
+
+const RED_YELLOW_BLUE: [u32; 11] = [
+    0xa50026, 0xd73027, 0xf46d43, 0xfdae61, 0xfee090, 0xffffbf, 0xe0f3f8, 0xabd9e9, 0x74add1,
+    0x4575b4, 0x313695,
+];
+
+const SPECTRAL: [u32; 11] = [
+    0x9e0142, 0xd53e4f, 0xf46d43, 0xfdae61, 0xfee08b, 0xffffbf, 0xe6f598, 0xabdda4, 0x66c2a5,
+    0x3288bd, 0x5e4fa2,
+];
+
+const BROWN_BLUE_GREEN: [u32; 11] = [
+    0x543005, 0x8c510a, 0xbf812d, 0xdfc27d, 0xf6e8c3, 0xf5f5f5, 0xc7eae5, 0x80cdc1, 0x35978f,
+    0x01665e, 0x003c30,
+];
+
+const PINK_YELLOW_GREEN: [u32; 11] = [
+    0x8e0152, 0xc51b7d, 0xde77ae, 0xf1b6da, 0xfde0ef, 0xf7f7f7, 0xe6f5d0, 0xb8e186, 0x7fbc41,
+    0x4d9221, 0x276419,
+];
+
+const PURPLE_ORANGE: [u32; 11] = [
+    0x7f3b08, 0xb35806, 0xe08214, 0xfdb863, 0xfee0b6, 0xf7f7f7, 0xd8daeb, 0xb2abd2, 0x8073ac,
+    0x542788, 0x2d004b,
+];
+
+const TABLEAU10: [u32; 10] = [
+    0x1f77b4, 0xff7f0e, 0x2ca02c, 0xd62728, 0x9467bd, 0x8c564b, 0xe377c2, 0x7f7f7f, 0xbcbd22,
+    0x17becf,
+];
+
+const SET1: [u32; 9] = [
+    0xe41a1c, 0x377eb8, 0x4daf4a, 0x984ea3, 0xff7f00, 0xffff33, 0xa65628, 0xf781bf, 0x999999,
+];
+
+const SET2: [u32; 8] = [
+    0x66c2a5, 0xfc8d62, 0x8da0cb, 0xe78ac3, 0xa6d854, 0xffd92f, 0xe5c494, 0xb3b3b3,
+];
+
+const DARK2: [u32; 8] = [
+    0x1b9e77, 0xd95f02, 0x7570b3, 0xe7298a, 0x66a61e, 0xe6ab02, 0xa6761d, 0x666666,
+];
+
+const PAIRED: [u32; 12] = [
+    0xa6cee3, 0x1f78b4, 0xb2df8a, 0x33a02c, 0xfb9a99, 0xe31a1c, 0xfdbf6f, 0xff7f00, 0xcab2d6,
+    0x6a3d9a, 0xffff99, 0xb15928,
+];
+
+const OKABE_ITO: [u32; 8] = [
+    0xe69f00, 0x56b4e9, 0x009e73, 0xf0e442, 0x0072b2, 0xd55e00, 0xcc79a7, 0x000000,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn black_to_white() -> Colormap {
+        Colormap::with(vec![
+            Color::with(0, 0, 0, 255),
+            Color::with(100, 100, 100, 255),
+            Color::with(255, 255, 255, 255),
+        ])
+    }
+
+    #[test]
+    fn colors_are_interpolated_between_stops() {
+        let colormap = black_to_white();
+        assert_eq!(colormap.color_at(0f64), Color::with(0, 0, 0, 255));
+        assert_eq!(colormap.color_at(0.25f64), Color::with(50, 50, 50, 255));
+        assert_eq!(colormap.color_at(0.5f64), Color::with(100, 100, 100, 255));
+        assert_eq!(colormap.color_at(0.75f64), Color::with(178, 178, 178, 255));
+        assert_eq!(colormap.color_at(1f64), Color::with(255, 255, 255, 255));
+        assert_eq!(
+            Colormap::viridis().color_at(0f64),
+            color_from_rgb(VIRIDIS[0])
+        );
+        assert_eq!(
+            Colormap::viridis().color_at(1f64),
+            color_from_rgb(VIRIDIS[32])
+        );
+    }
+
+    #[test]
+    fn fractions_outside_of_the_stops_get_the_nearest_end() {
+        let colormap = black_to_white();
+        assert_eq!(colormap.color_at(-0.5f64), colormap.color_at(0f64));
+        assert_eq!(
+            colormap.color_at(f64::NEG_INFINITY),
+            colormap.color_at(0f64)
+        );
+        assert_eq!(colormap.color_at(7f64), colormap.color_at(1f64));
+        assert_eq!(colormap.color_at(f64::INFINITY), colormap.color_at(1f64));
+        assert_eq!(colormap.color_at(f64::NAN), Color::transparent());
+    }
+
+    #[test]
+    fn values_are_mapped_onto_their_range() {
+        let colormap = black_to_white();
+        assert_eq!(colormap.map(15f64, 10f64, 20f64), colormap.color_at(0.5f64));
+        assert_eq!(colormap.map(0f64, 10f64, 20f64), colormap.color_at(0f64));
+        assert_eq!(colormap.map(25f64, 10f64, 20f64), colormap.color_at(1f64));
+        // a reversed range runs from the last to the first stop
+        assert_eq!(
+            colormap.map(12.5f64, 20f64, 10f64),
+            colormap.color_at(0.75f64)
+        );
+        assert_eq!(colormap.map(-3f64, 4f64, 4f64), colormap.color_at(0.5f64));
+        assert_eq!(colormap.map(4f64, 4f64, 4f64), colormap.color_at(0.5f64));
+        assert_eq!(colormap.map(f64::NAN, 0f64, 1f64), Color::transparent());
+        assert_eq!(colormap.map(0.5f64, 0f64, f64::NAN), Color::transparent());
+    }
+
+    #[test]
+    fn maps_with_few_stops_are_constant_or_transparent() {
+        let single = Colormap::with(vec![Color::red()]);
+        for fraction in [-1f64, 0f64, 0.3f64, 1f64, 2f64] {
+            assert_eq!(single.color_at(fraction), Color::red());
+        }
+        assert_eq!(single.color_at(f64::NAN), Color::transparent());
+        let empty = Colormap::with(Vec::new());
+        for fraction in [0f64, 0.5f64, 1f64, f64::NAN] {
+            assert_eq!(empty.color_at(fraction), Color::transparent());
+        }
+        assert_eq!(empty.map(1f64, 0f64, 2f64), Color::transparent());
+    }
+
+    #[test]
+    fn reversed_maps_run_backwards() {
+        let colormap = black_to_white();
+        let reversed = colormap.clone().reversed();
+        for fraction in [0f64, 0.25f64, 0.5f64, 0.75f64, 1f64] {
+            assert_eq!(
+                reversed.color_at(fraction),
+                colormap.color_at(1f64 - fraction)
+            );
+        }
+        assert_eq!(reversed.reversed(), colormap);
+    }
+
+    #[test]
+    fn palettes_start_over_after_their_last_color() {
+        let palette = Palette::with(vec![Color::red(), Color::white(), Color::black()]);
+        assert_eq!(palette.color(0), Color::red());
+        assert_eq!(palette.color(2), Color::black());
+        assert_eq!(palette.color(3), Color::red());
+        assert_eq!(palette.color(7), Color::white());
+        assert_eq!(palette.color(usize::MAX), palette.color(usize::MAX % 3));
+        assert_eq!(
+            Palette::tableau10().color(10),
+            Palette::tableau10().color(0)
+        );
+        assert_eq!(Palette::with(Vec::new()).color(4), Color::transparent());
+    }
+}
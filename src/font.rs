@@ -0,0 +1,241 @@
+//! The built-in monospaced bitmap font used for `Geometry2D::Text`.
+//!
+//! Every glyph consists of 5x7 dots inside a cell of 6x10 dots, so it looks the same on every
+//! machine without depending on system fonts. A cell is `font_size` high, the baseline lies at
+//! `ASCENT` and every character advances by `ADVANCE`, both relative to the font size.
+
+use gymnarium_base::math::{Position2D, Size2D};
+
+/// Horizontal advance of every character relative to the font size.
+pub const ADVANCE: f64 = 0.6f64;
+/// Distance from the top of a line to its baseline relative to the font size.
+pub const ASCENT: f64 = 0.8f64;
+/// Distance from the baseline to the bottom of a line relative to the font size.
+pub const DESCENT: f64 = 0.2f64;
+
+/// Number of dots per font size.
+const DOTS_PER_CELL_HEIGHT: f64 = 10f64;
+
+/// Glyph shown for characters without their own glyph.
+const REPLACEMENT_GLYPH: [u8; 5] = [0x7F, 0x41, 0x41, 0x41, 0x7F];
+
+/// Glyphs of the printable ASCII characters from `' '` to `'~'`.
+///
+/// Every glyph consists of five columns, the least significant bit is the topmost dot.
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // '!'
+    [0x00, 0x07, 0x00, 0x07, 0x00], // '"'
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // '#'
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // '$'
+    [0x23, 0x13, 0x08, 0x64, 0x62], // '%'
+    [0x36, 0x49, 0x55, 0x22, 0x50], // '&'
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '''
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // '('
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // ')'
+    [0x14, 0x08, 0x3E, 0x08, 0x14], // '*'
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // '+'
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ','
+    [0x08, 0x08, 0x08, 0x08, 0x08], // '-'
+    [0x00, 0x60, 0x60, 0x00, 0x00], // '.'
+    [0x20, 0x10, 0x08, 0x04, 0x02], // '/'
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // '0'
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // '1'
+    [0x42, 0x61, 0x51, 0x49, 0x46], // '2'
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // '3'
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // '4'
+    [0x27, 0x45, 0x45, 0x45, 0x39], // '5'
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // '6'
+    [0x01, 0x71, 0x09, 0x05, 0x03], // '7'
+    [0x36, 0x49, 0x49, 0x49, 0x36], // '8'
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // '9'
+    [0x00, 0x36, 0x36, 0x00, 0x00], // ':'
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ';'
+    [0x08, 0x14, 0x22, 0x41, 0x00], // '<'
+    [0x14, 0x14, 0x14, 0x14, 0x14], // '='
+    [0x00, 0x41, 0x22, 0x14, 0x08], // '>'
+    [0x02, 0x01, 0x51, 0x09, 0x06], // '?'
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // '@'
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // 'A'
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // 'B'
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // 'C'
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // 'D'
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // 'E'
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // 'F'
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // 'G'
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // 'H'
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // 'I'
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // 'J'
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // 'K'
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // 'L'
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // 'M'
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // 'N'
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // 'O'
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // 'P'
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // 'Q'
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // 'R'
+    [0x46, 0x49, 0x49, 0x49, 0x31], // 'S'
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // 'T'
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // 'U'
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // 'V'
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // 'W'
+    [0x63, 0x14, 0x08, 0x14, 0x63], // 'X'
+    [0x07, 0x08, 0x70, 0x08, 0x07], // 'Y'
+    [0x61, 0x51, 0x49, 0x45, 0x43], // 'Z'
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // '['
+    [0x02, 0x04, 0x08, 0x10, 0x20], // '\'
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ']'
+    [0x04, 0x02, 0x01, 0x02, 0x04], // '^'
+    [0x40, 0x40, 0x40, 0x40, 0x40], // '_'
+    [0x00, 0x01, 0x02, 0x04, 0x00], // '`'
+    [0x20, 0x54, 0x54, 0x54, 0x78], // 'a'
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // 'b'
+    [0x38, 0x44, 0x44, 0x44, 0x20], // 'c'
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // 'd'
+    [0x38, 0x54, 0x54, 0x54, 0x18], // 'e'
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // 'f'
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // 'g'
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // 'h'
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // 'i'
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // 'j'
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // 'k'
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // 'l'
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // 'm'
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // 'n'
+    [0x38, 0x44, 0x44, 0x44, 0x38], // 'o'
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // 'p'
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // 'q'
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // 'r'
+    [0x48, 0x54, 0x54, 0x54, 0x20], // 's'
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // 't'
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // 'u'
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // 'v'
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // 'w'
+    [0x44, 0x28, 0x10, 0x28, 0x44], // 'x'
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // 'y'
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // 'z'
+    [0x00, 0x08, 0x36, 0x41, 0x00], // '{'
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // '|'
+    [0x00, 0x41, 0x36, 0x08, 0x00], // '}'
+    [0x10, 0x08, 0x08, 0x10, 0x08], // '~'
+];
+
+/// Returns the five columns of the glyph for the character.
+///
+/// Tabs are shown as space, all other characters outside of printable ASCII as a box.
+pub fn glyph(character: char) -> [u8; 5] {
+    match character {
+        '\t' => GLYPHS[0],
+        ' '..='~' => GLYPHS[character as usize - ' ' as usize],
+        _ => REPLACEMENT_GLYPH,
+    }
+}
+
+/// Returns the size of the text written with the given font size.
+///
+/// Lines are separated by `\n`, the width is the one of the longest line.
+pub fn measure_text(text: &str, font_size: f64) -> Size2D {
+    let longest_line = text
+        .split('\n')
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    Size2D::with(
+        longest_line as f64 * ADVANCE * font_size,
+        text.split('\n').count() as f64 * font_size,
+    )
+}
+
+/// Returns the rectangles covered by the dots of the text as top left corner and size.
+///
+/// Dots next to each other within a row are merged into one rectangle.
+pub(crate) fn dot_rectangles(
+    text: &str,
+    font_size: f64,
+    top_left: &Position2D,
+) -> Vec<(Position2D, Size2D)> {
+    let dot_size = font_size / DOTS_PER_CELL_HEIGHT;
+    let mut rectangles = Vec::new();
+    for (line_index, line) in text.split('\n').enumerate() {
+        let glyphs: Vec<[u8; 5]> = line.chars().map(glyph).collect();
+        for row in 0..7 {
+            // the first row of dots lies one dot below the top of the line
+            let y = top_left.y
+                + (line_index as f64 * DOTS_PER_CELL_HEIGHT + 1f64 + row as f64) * dot_size;
+            let mut run_start: Option<usize> = None;
+            let columns = glyphs.len() * 6;
+            for column in 0..=columns {
+                let set = column < columns
+                    && column % 6 < 5
+                    && glyphs[column / 6][column % 6] & (1 << row) != 0;
+                match (set, run_start) {
+                    (true, None) => run_start = Some(column),
+                    (false, Some(start)) => {
+                        rectangles.push((
+                            Position2D::with(top_left.x + start as f64 * dot_size, y),
+                            Size2D::with((column - start) as f64 * dot_size, dot_size),
+                        ));
+                        run_start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    rectangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_lines_advance_by_character() {
+        assert_eq!(measure_text("", 10f64), Size2D::with(0f64, 10f64));
+        assert_eq!(measure_text("abc", 10f64), Size2D::with(18f64, 10f64));
+        assert_eq!(measure_text("a b\t", 5f64), Size2D::with(12f64, 5f64));
+        // characters are counted, not bytes
+        assert_eq!(measure_text("äö€", 10f64), Size2D::with(18f64, 10f64));
+    }
+
+    #[test]
+    fn multiple_lines_take_the_longest_line() {
+        assert_eq!(
+            measure_text("ab\nwxyz\nc", 10f64),
+            Size2D::with(24f64, 30f64)
+        );
+        assert_eq!(measure_text("ab\n", 10f64), Size2D::with(12f64, 20f64));
+        assert_eq!(measure_text("\n\n", 2f64), Size2D::with(0f64, 6f64));
+    }
+
+    #[test]
+    fn unsupported_characters_are_shown_as_box() {
+        for character in ['€', 'ä', '\u{7f}', '\r', '\u{1f600}'] {
+            assert_eq!(glyph(character), REPLACEMENT_GLYPH);
+        }
+        assert_eq!(glyph('\t'), glyph(' '));
+        assert_eq!(glyph(' '), [0; 5]);
+        assert_ne!(glyph('A'), REPLACEMENT_GLYPH);
+        assert_eq!(glyph('~'), GLYPHS[94]);
+
+        // the top and bottom rows of the box are one run of dots, the rows between two dots
+        let rectangles = dot_rectangles("a\n€", 10f64, &Position2D::with(5f64, 0f64));
+        let box_rectangles: Vec<_> = rectangles
+            .iter()
+            .filter(|(top_left, _)| top_left.y >= 10f64)
+            .collect();
+        assert_eq!(box_rectangles.len(), 1 + 5 * 2 + 1);
+        assert_eq!(
+            *box_rectangles[0],
+            (Position2D::with(5f64, 11f64), Size2D::with(5f64, 1f64))
+        );
+        assert_eq!(
+            *box_rectangles[2],
+            (Position2D::with(9f64, 12f64), Size2D::with(1f64, 1f64))
+        );
+        assert_eq!(
+            *box_rectangles[11],
+            (Position2D::with(5f64, 17f64), Size2D::with(5f64, 1f64))
+        );
+    }
+}
@@ -0,0 +1,1267 @@
+//! Encoding and decoding of `PixelArray`s as PNG, PPM and PGM images.
+//!
+//! PNG images are always written as 8 bit RGB. Reading supports every color type, bit depth
+//! and interlacing method of the PNG specification, but drops the alpha channel, because pixel
+//! arrays are always opaque. Use `load_texture` to keep it.
+//!
+//! Sequences of pixel arrays can be written as looping animated GIF or APNG images.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::{Pixel, PixelArray, TextureSource};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+/// Shortest GIF frame delay in 1/100 s which browsers do not replace with a longer one.
+const MINIMUM_GIF_DELAY: u16 = 2;
+
+/* --- --- --- ImageFormat --- --- --- */
+
+/// The supported image formats.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ImageFormat {
+    Png,
+    /// Binary portable pixmap.
+    Ppm,
+    /// Binary portable graymap, colors are converted to their luminance.
+    Pgm,
+}
+
+impl ImageFormat {
+    /// Determines the format from the extension of the given path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(Self::Png),
+            "ppm" => Some(Self::Ppm),
+            "pgm" => Some(Self::Pgm),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Ppm => "ppm",
+            Self::Pgm => "pgm",
+        }
+    }
+}
+
+/* --- --- --- AnimationFormat --- --- --- */
+
+/// The supported formats for animations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AnimationFormat {
+    /// Animated GIF, frames with more than 256 colors are reduced to a fixed palette.
+    Gif,
+    /// Animated PNG, lossless.
+    Apng,
+}
+
+impl AnimationFormat {
+    /// Determines the format from the extension of the given path.
+    ///
+    /// Both `.apng` and `.png` are recognised as APNG.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gif" => Some(Self::Gif),
+            "apng" | "png" => Some(Self::Apng),
+            _ => None,
+        }
+    }
+}
+
+/* --- --- --- ImageError --- --- --- */
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(std::io::Error),
+    /// The data is not a valid image of the expected format.
+    Malformed(String),
+    /// The image or format is valid, but not supported.
+    Unsupported(String),
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not read or write image: {}", error),
+            Self::Malformed(reason) => write!(f, "Malformed image: {}", reason),
+            Self::Unsupported(reason) => write!(f, "Unsupported image: {}", reason),
+        }
+    }
+}
+
+impl Error for ImageError {}
+
+impl From<std::io::Error> for ImageError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/* --- --- --- Files --- --- --- */
+
+/// Writes the pixel array into a file with the format chosen by the extension of the path.
+pub fn save<P: AsRef<Path>>(pixel_array: &PixelArray, path: P) -> Result<(), ImageError> {
+    let format = ImageFormat::from_path(&path).ok_or_else(|| {
+        ImageError::Unsupported(format!(
+            "no image format for extension of {}",
+            path.as_ref().display()
+        ))
+    })?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(pixel_array, format, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a PNG, PPM or PGM file, the format is detected from its content.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<PixelArray, ImageError> {
+    read(&mut BufReader::new(File::open(path)?))
+}
+
+/// Reads a PNG, PPM or PGM file into a `TextureSource::Bytes` keeping the alpha channel.
+pub fn load_texture<P: AsRef<Path>>(path: P) -> Result<TextureSource, ImageError> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+    let (width, height, rgba) = if data.starts_with(&PNG_SIGNATURE) {
+        decode_png(&data)?
+    } else {
+        let pixel_array = decode_netpbm(&data)?;
+        let rgba = pixel_array
+            .data
+            .iter()
+            .flat_map(|pixel| vec![pixel.red, pixel.green, pixel.blue, 255])
+            .collect();
+        (pixel_array.width, pixel_array.height, rgba)
+    };
+    Ok(TextureSource::bytes(rgba, width as u32, height as u32))
+}
+
+/* --- --- --- Streams --- --- --- */
+
+pub fn write<W: Write>(
+    pixel_array: &PixelArray,
+    format: ImageFormat,
+    writer: &mut W,
+) -> Result<(), ImageError> {
+    match format {
+        ImageFormat::Png => write_png(pixel_array, writer),
+        ImageFormat::Ppm => write_ppm(pixel_array, writer),
+        ImageFormat::Pgm => write_pgm(pixel_array, writer),
+    }
+}
+
+/// Reads a PNG, PPM or PGM image, the format is detected from its content.
+pub fn read<R: Read>(reader: &mut R) -> Result<PixelArray, ImageError> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    if data.starts_with(&PNG_SIGNATURE) {
+        let (width, height, rgba) = decode_png(&data)?;
+        Ok(PixelArray {
+            data: rgba
+                .chunks(4)
+                .map(|rgba| Pixel::with(rgba[0], rgba[1], rgba[2]))
+                .collect(),
+            width,
+            height,
+        })
+    } else {
+        decode_netpbm(&data)
+    }
+}
+
+pub fn write_png<W: Write>(pixel_array: &PixelArray, writer: &mut W) -> Result<(), ImageError> {
+    check_size(pixel_array)?;
+    let rgb: Vec<u8> = pixel_array
+        .data
+        .iter()
+        .flat_map(|pixel| vec![pixel.red, pixel.green, pixel.blue])
+        .collect();
+    writer.write_all(&encode_png(
+        &rgb,
+        pixel_array.width as u32,
+        pixel_array.height as u32,
+        false,
+    ))?;
+    Ok(())
+}
+
+pub fn read_png<R: Read>(reader: &mut R) -> Result<PixelArray, ImageError> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return Err(ImageError::Malformed("missing PNG signature".to_string()));
+    }
+    read(&mut data.as_slice())
+}
+
+pub fn write_ppm<W: Write>(pixel_array: &PixelArray, writer: &mut W) -> Result<(), ImageError> {
+    check_size(pixel_array)?;
+    write!(
+        writer,
+        "P6\n{} {}\n255\n",
+        pixel_array.width, pixel_array.height
+    )?;
+    let rgb: Vec<u8> = pixel_array
+        .data
+        .iter()
+        .flat_map(|pixel| vec![pixel.red, pixel.green, pixel.blue])
+        .collect();
+    writer.write_all(&rgb)?;
+    Ok(())
+}
+
+pub fn write_pgm<W: Write>(pixel_array: &PixelArray, writer: &mut W) -> Result<(), ImageError> {
+    check_size(pixel_array)?;
+    write!(
+        writer,
+        "P5\n{} {}\n255\n",
+        pixel_array.width, pixel_array.height
+    )?;
+    let gray: Vec<u8> = pixel_array.data.iter().map(luminance).collect();
+    writer.write_all(&gray)?;
+    Ok(())
+}
+
+/// Reads a PPM or PGM image in either binary or plain text form.
+pub fn read_netpbm<R: Read>(reader: &mut R) -> Result<PixelArray, ImageError> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode_netpbm(&data)
+}
+
+/// Converts the pixel to gray with the weights of ITU-R BT.601.
+pub fn luminance(pixel: &Pixel) -> u8 {
+    (0.299f64 * pixel.red as f64 + 0.587f64 * pixel.green as f64 + 0.114f64 * pixel.blue as f64)
+        .round() as u8
+}
+
+pub(crate) fn check_size(pixel_array: &PixelArray) -> Result<(), ImageError> {
+    if pixel_array.data.len() != pixel_array.width * pixel_array.height {
+        Err(ImageError::Malformed(format!(
+            "pixel array of size {}x{} contains {} pixels",
+            pixel_array.width,
+            pixel_array.height,
+            pixel_array.data.len()
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/* --- --- --- Animations --- --- --- */
+
+/// Writes the frames as endlessly looping animation into a file with the format chosen by the
+/// extension of the path.
+pub fn save_animation<P: AsRef<Path>>(
+    frames: &[PixelArray],
+    frames_per_second: f64,
+    path: P,
+) -> Result<(), ImageError> {
+    let format = AnimationFormat::from_path(&path).ok_or_else(|| {
+        ImageError::Unsupported(format!(
+            "no animation format for extension of {}",
+            path.as_ref().display()
+        ))
+    })?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_animation(frames, frames_per_second, format, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the frames as endlessly looping animation.
+///
+/// The animation is as large as the largest frame, smaller frames are placed in the upper left
+/// corner on black. Frame delays are rounded to the resolution of the format (1/100 s for GIF,
+/// 1/1000 s for APNG) without accumulating drift.
+///
+/// Browsers show GIF frames shorter than 2/100 s for about 1/10 s, so GIF frames are shown for at
+/// least 2/100 s. Above 50 frames per second frames are dropped and their time is added to the
+/// previous frame.
+pub fn write_animation<W: Write>(
+    frames: &[PixelArray],
+    frames_per_second: f64,
+    format: AnimationFormat,
+    writer: &mut W,
+) -> Result<(), ImageError> {
+    if frames.is_empty() {
+        return Err(ImageError::Unsupported(
+            "animation without frames".to_string(),
+        ));
+    }
+    if !(frames_per_second > 0f64 && frames_per_second.is_finite()) {
+        return Err(ImageError::Unsupported(format!(
+            "{} frames per second",
+            frames_per_second
+        )));
+    }
+    for frame in frames {
+        check_size(frame)?;
+    }
+    let width = frames.iter().map(|frame| frame.width).max().unwrap_or(0);
+    let height = frames.iter().map(|frame| frame.height).max().unwrap_or(0);
+    if width == 0 || height == 0 || width > 65535 || height > 65535 {
+        return Err(ImageError::Unsupported(format!(
+            "animation of size {}x{}",
+            width, height
+        )));
+    }
+    let frames: Vec<Vec<Pixel>> = frames
+        .iter()
+        .map(|frame| {
+            let mut pixels = vec![Pixel::black(); width * height];
+            for y in 0..frame.height {
+                pixels[y * width..y * width + frame.width]
+                    .copy_from_slice(&frame.data[y * frame.width..(y + 1) * frame.width]);
+            }
+            pixels
+        })
+        .collect();
+    match format {
+        AnimationFormat::Gif => {
+            let delays = frame_delays(frames.len(), frames_per_second, 100f64);
+            let (frames, delays) = merge_short_frames(frames, &delays, MINIMUM_GIF_DELAY);
+            writer.write_all(&encode_gif(&frames, width, height, &delays))?
+        }
+        AnimationFormat::Apng => writer.write_all(&encode_apng(
+            &frames,
+            width,
+            height,
+            &frame_delays(frames.len(), frames_per_second, 1000f64),
+        ))?,
+    }
+    Ok(())
+}
+
+/// Delays of the frames in the given time units, rounded so that their sum stays on time.
+fn frame_delays(count: usize, frames_per_second: f64, units_per_second: f64) -> Vec<u16> {
+    let end_of_frame = |index: usize| (index as f64 * units_per_second / frames_per_second).round();
+    (0..count)
+        .map(|index| (end_of_frame(index + 1) - end_of_frame(index)).min(65535f64) as u16)
+        .collect()
+}
+
+/// Drops every frame shown before the previous one reached the minimum delay and adds its
+/// delay to the previous one. The last frame is extended to the minimum delay.
+fn merge_short_frames<T>(frames: Vec<T>, delays: &[u16], minimum_delay: u16) -> (Vec<T>, Vec<u16>) {
+    let mut kept_frames = Vec::new();
+    let mut kept_delays: Vec<u16> = Vec::new();
+    for (frame, delay) in frames.into_iter().zip(delays) {
+        match kept_delays.last_mut() {
+            Some(kept_delay) if *kept_delay < minimum_delay => {
+                *kept_delay = kept_delay.saturating_add(*delay)
+            }
+            _ => {
+                kept_frames.push(frame);
+                kept_delays.push(*delay);
+            }
+        }
+    }
+    if let Some(kept_delay) = kept_delays.last_mut() {
+        *kept_delay = (*kept_delay).max(minimum_delay);
+    }
+    (kept_frames, kept_delays)
+}
+
+fn encode_gif(frames: &[Vec<Pixel>], width: usize, height: usize, delays: &[u16]) -> Vec<u8> {
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&(width as u16).to_le_bytes());
+    gif.extend_from_slice(&(height as u16).to_le_bytes());
+    gif.extend_from_slice(&[0, 0, 0]);
+    // application extension letting the animation loop endlessly
+    gif.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+    gif.extend_from_slice(b"NETSCAPE2.0");
+    gif.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    for (pixels, delay) in frames.iter().zip(delays) {
+        let (palette, indices) = gif_palette(pixels);
+        let table_bits = (1..=8).find(|bits| palette.len() <= 1 << bits).unwrap_or(8);
+
+        gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0x04]);
+        gif.extend_from_slice(&delay.to_le_bytes());
+        gif.extend_from_slice(&[0x00, 0x00]);
+
+        gif.push(0x2C);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&(width as u16).to_le_bytes());
+        gif.extend_from_slice(&(height as u16).to_le_bytes());
+        gif.push(0x80 | (table_bits - 1) as u8);
+        for index in 0..1 << table_bits {
+            let color = palette.get(index).copied().unwrap_or_else(Pixel::black);
+            gif.extend_from_slice(&[color.red, color.green, color.blue]);
+        }
+
+        let minimum_code_size = table_bits.max(2) as u8;
+        gif.push(minimum_code_size);
+        for block in lzw_compress(&indices, minimum_code_size).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+    gif.push(0x3B);
+    gif
+}
+
+/// Returns the exact palette of the pixels if they contain at most 256 colors and otherwise
+/// maps them to the nearest color of a 6x7x6 color cube.
+fn gif_palette(pixels: &[Pixel]) -> (Vec<Pixel>, Vec<u8>) {
+    let mut palette = Vec::new();
+    let mut lookup = HashMap::new();
+    let mut indices = Vec::with_capacity(pixels.len());
+    for pixel in pixels {
+        let key = (pixel.red, pixel.green, pixel.blue);
+        let index = match lookup.get(&key) {
+            Some(index) => *index,
+            None if palette.len() < 256 => {
+                lookup.insert(key, palette.len() as u8);
+                palette.push(*pixel);
+                (palette.len() - 1) as u8
+            }
+            None => break,
+        };
+        indices.push(index);
+    }
+    if indices.len() == pixels.len() {
+        return (palette, indices);
+    }
+
+    let level = |value: u8, levels: u16| ((value as u16 * (levels - 1) + 127) / 255) as u8;
+    let palette = (0..6u16)
+        .flat_map(|red| {
+            (0..7u16).flat_map(move |green| (0..6u16).map(move |blue| (red, green, blue)))
+        })
+        .map(|(red, green, blue)| {
+            Pixel::with(
+                (red * 255 / 5) as u8,
+                (green * 255 / 6) as u8,
+                (blue * 255 / 5) as u8,
+            )
+        })
+        .collect();
+    let indices = pixels
+        .iter()
+        .map(|pixel| level(pixel.red, 6) * 42 + level(pixel.green, 7) * 6 + level(pixel.blue, 6))
+        .collect();
+    (palette, indices)
+}
+
+/// Compresses the color indices with the variable code length LZW variant of GIF.
+fn lzw_compress(indices: &[u8], minimum_code_size: u8) -> Vec<u8> {
+    const MAXIMUM_CODE: u16 = 4096;
+    let clear_code = 1u16 << minimum_code_size;
+    let end_code = clear_code + 1;
+
+    let mut output = CodeWriter::default();
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = minimum_code_size + 1;
+    output.write(clear_code, code_size);
+
+    let mut prefix = match indices.first() {
+        Some(index) => *index as u16,
+        None => {
+            output.write(end_code, code_size);
+            return output.finish();
+        }
+    };
+    for index in &indices[1..] {
+        if let Some(code) = dictionary.get(&(prefix, *index)) {
+            prefix = *code;
+            continue;
+        }
+        // the decoder lags one entry behind, so it widens its codes one code later
+        if next_code > 1 << code_size && code_size < 12 {
+            code_size += 1;
+        }
+        output.write(prefix, code_size);
+        if next_code < MAXIMUM_CODE {
+            dictionary.insert((prefix, *index), next_code);
+            next_code += 1;
+        } else {
+            output.write(clear_code, code_size);
+            dictionary.clear();
+            next_code = end_code + 1;
+            code_size = minimum_code_size + 1;
+        }
+        prefix = *index as u16;
+    }
+    if next_code > 1 << code_size && code_size < 12 {
+        code_size += 1;
+    }
+    output.write(prefix, code_size);
+    if next_code >= 1 << code_size && code_size < 12 {
+        code_size += 1;
+    }
+    output.write(end_code, code_size);
+    output.finish()
+}
+
+/// Packs codes of variable length least significant bit first.
+#[derive(Default)]
+struct CodeWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    length: u8,
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u16, code_size: u8) {
+        self.buffer |= (code as u32) << self.length;
+        self.length += code_size;
+        while self.length >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.length -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.length > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+fn encode_apng(frames: &[Vec<Pixel>], width: usize, height: usize, delays: &[u16]) -> Vec<u8> {
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_png_chunk(
+        &mut png,
+        b"IHDR",
+        &png_header(width as u32, height as u32, false),
+    );
+    let mut animation_control = (frames.len() as u32).to_be_bytes().to_vec();
+    animation_control.extend_from_slice(&0u32.to_be_bytes());
+    write_png_chunk(&mut png, b"acTL", &animation_control);
+
+    let mut sequence_number = 0u32;
+    for (index, (pixels, delay)) in frames.iter().zip(delays).enumerate() {
+        let mut frame_control = sequence_number.to_be_bytes().to_vec();
+        frame_control.extend_from_slice(&(width as u32).to_be_bytes());
+        frame_control.extend_from_slice(&(height as u32).to_be_bytes());
+        frame_control.extend_from_slice(&[0; 8]);
+        frame_control.extend_from_slice(&delay.to_be_bytes());
+        frame_control.extend_from_slice(&1000u16.to_be_bytes());
+        frame_control.extend_from_slice(&[0, 0]);
+        write_png_chunk(&mut png, b"fcTL", &frame_control);
+        sequence_number += 1;
+
+        let rgb: Vec<u8> = pixels
+            .iter()
+            .flat_map(|pixel| vec![pixel.red, pixel.green, pixel.blue])
+            .collect();
+        let compressed = compress_png_rows(&rgb, width as u32, height as u32, false);
+        if index == 0 {
+            write_png_chunk(&mut png, b"IDAT", &compressed);
+        } else {
+            let mut frame_data = sequence_number.to_be_bytes().to_vec();
+            frame_data.extend_from_slice(&compressed);
+            write_png_chunk(&mut png, b"fdAT", &frame_data);
+            sequence_number += 1;
+        }
+    }
+    write_png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/* --- --- --- PNG --- --- --- */
+
+/// Encodes 8 bit RGB or RGBA samples as PNG.
+pub(crate) fn encode_png(samples: &[u8], width: u32, height: u32, with_alpha: bool) -> Vec<u8> {
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_png_chunk(&mut png, b"IHDR", &png_header(width, height, with_alpha));
+    write_png_chunk(
+        &mut png,
+        b"IDAT",
+        &compress_png_rows(samples, width, height, with_alpha),
+    );
+    write_png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn png_header(width: u32, height: u32, with_alpha: bool) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, if with_alpha { 6 } else { 2 }, 0, 0, 0]);
+    header
+}
+
+/// Filters the rows of 8 bit RGB or RGBA samples adaptively and compresses them with zlib.
+fn compress_png_rows(samples: &[u8], width: u32, height: u32, with_alpha: bool) -> Vec<u8> {
+    let channels = if with_alpha { 4 } else { 3 };
+    let row_length = width as usize * channels;
+    let mut filtered = Vec::with_capacity((row_length + 1) * height as usize);
+    let empty_row = vec![0u8; row_length];
+    let mut candidate = vec![0u8; row_length];
+    let mut best = vec![0u8; row_length];
+    for row in 0..height as usize {
+        let current = &samples[row * row_length..(row + 1) * row_length];
+        let previous = if row == 0 {
+            &empty_row[..]
+        } else {
+            &samples[(row - 1) * row_length..row * row_length]
+        };
+        let mut best_filter = 0u8;
+        let mut best_score = u64::MAX;
+        for filter in 0..5u8 {
+            for index in 0..row_length {
+                let left = if index >= channels {
+                    current[index - channels]
+                } else {
+                    0
+                };
+                let upper_left = if index >= channels {
+                    previous[index - channels]
+                } else {
+                    0
+                };
+                let predicted = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => previous[index],
+                    3 => ((left as u16 + previous[index] as u16) / 2) as u8,
+                    _ => paeth(left, previous[index], upper_left),
+                };
+                candidate[index] = current[index].wrapping_sub(predicted);
+            }
+            let score = candidate
+                .iter()
+                .map(|&value| (value as i8).unsigned_abs() as u64)
+                .sum();
+            if score < best_score {
+                best_score = score;
+                best_filter = filter;
+                best.copy_from_slice(&candidate);
+            }
+        }
+        filtered.push(best_filter);
+        filtered.extend_from_slice(&best);
+    }
+    miniz_oxide::deflate::compress_to_vec_zlib(&filtered, 6)
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Decodes a PNG image into its width, height and 8 bit RGBA samples.
+fn decode_png(data: &[u8]) -> Result<(usize, usize, Vec<u8>), ImageError> {
+    let malformed = |reason: &str| ImageError::Malformed(reason.to_string());
+
+    let mut header: Option<&[u8]> = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+    let mut position = PNG_SIGNATURE.len();
+    loop {
+        if position + 12 > data.len() {
+            return Err(malformed("unexpected end of data"));
+        }
+        let length = u32::from_be_bytes([
+            data[position],
+            data[position + 1],
+            data[position + 2],
+            data[position + 3],
+        ]) as usize;
+        let end = (position + 12).saturating_add(length);
+        if end > data.len() {
+            return Err(malformed("chunk exceeds data"));
+        }
+        let chunk_type = &data[position + 4..position + 8];
+        let chunk_data = &data[position + 8..position + 8 + length];
+        let crc = u32::from_be_bytes([data[end - 4], data[end - 3], data[end - 2], data[end - 1]]);
+        if crc32(&data[position + 4..end - 4]) != crc {
+            return Err(malformed("chunk checksum mismatch"));
+        }
+        match chunk_type {
+            b"IHDR" => header = Some(chunk_data),
+            b"PLTE" => palette = chunk_data,
+            b"tRNS" => transparency = chunk_data,
+            b"IDAT" => compressed.extend_from_slice(chunk_data),
+            b"IEND" => break,
+            _ => {}
+        }
+        position = end;
+    }
+
+    let header = header
+        .filter(|header| header.len() == 13)
+        .ok_or_else(|| malformed("missing header"))?;
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let (bit_depth, color_type, interlace) = (header[8] as usize, header[9], header[12]);
+    let channels = match (color_type, bit_depth) {
+        (0, 1) | (0, 2) | (0, 4) | (0, 8) | (0, 16) => 1,
+        (3, 1) | (3, 2) | (3, 4) | (3, 8) => 1,
+        (2, 8) | (2, 16) => 3,
+        (4, 8) | (4, 16) => 2,
+        (6, 8) | (6, 16) => 4,
+        _ => {
+            return Err(ImageError::Unsupported(format!(
+                "color type {} with bit depth {}",
+                color_type, bit_depth
+            )))
+        }
+    };
+    if header[10] != 0 || header[11] != 0 || interlace > 1 {
+        return Err(ImageError::Unsupported(
+            "compression, filter or interlace method".to_string(),
+        ));
+    }
+
+    let bits_per_pixel = channels * bit_depth;
+    let bytes_per_pixel = (bits_per_pixel / 8).max(1);
+    let passes: Vec<(usize, usize, usize, usize)> = if interlace == 1 {
+        vec![
+            (0, 0, 8, 8),
+            (4, 0, 8, 8),
+            (0, 4, 4, 8),
+            (2, 0, 4, 4),
+            (0, 2, 2, 4),
+            (1, 0, 2, 2),
+            (0, 1, 1, 2),
+        ]
+    } else {
+        vec![(0, 0, 1, 1)]
+    };
+
+    // Both sizes are derived from the header, so they are checked before anything is allocated.
+    let too_large = || malformed("image too large");
+    let rgba_length = width
+        .checked_mul(height)
+        .and_then(|count| count.checked_mul(4))
+        .ok_or_else(too_large)?;
+    let mut filtered_length = 0usize;
+    for &(start_x, start_y, step_x, step_y) in &passes {
+        let pass_width = (width + step_x - 1 - start_x) / step_x;
+        let pass_height = (height + step_y - 1 - start_y) / step_y;
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+        filtered_length = pass_width
+            .checked_mul(bits_per_pixel)
+            .map(|bits| bits.div_ceil(8) + 1)
+            .and_then(|row_length| row_length.checked_mul(pass_height))
+            .and_then(|pass_length| pass_length.checked_add(filtered_length))
+            .ok_or_else(too_large)?;
+    }
+    let filtered =
+        miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&compressed, filtered_length)
+            .map_err(|_| malformed("invalid compressed data"))?;
+    if filtered.len() != filtered_length {
+        return Err(malformed("image data does not match the header"));
+    }
+
+    let mut rgba = vec![0u8; rgba_length];
+    let mut offset = 0;
+    for (start_x, start_y, step_x, step_y) in passes {
+        let pass_width = (width + step_x - 1 - start_x) / step_x;
+        let pass_height = (height + step_y - 1 - start_y) / step_y;
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+        let row_length = (pass_width * bits_per_pixel).div_ceil(8);
+        let mut previous = vec![0u8; row_length];
+        for pass_row in 0..pass_height {
+            if offset + 1 + row_length > filtered.len() {
+                return Err(malformed("not enough image data"));
+            }
+            let filter = filtered[offset];
+            let mut row = filtered[offset + 1..offset + 1 + row_length].to_vec();
+            offset += 1 + row_length;
+            for index in 0..row_length {
+                let left = if index >= bytes_per_pixel {
+                    row[index - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let upper_left = if index >= bytes_per_pixel {
+                    previous[index - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let predicted = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => previous[index],
+                    3 => ((left as u16 + previous[index] as u16) / 2) as u8,
+                    4 => paeth(left, previous[index], upper_left),
+                    _ => return Err(malformed("unknown row filter")),
+                };
+                row[index] = row[index].wrapping_add(predicted);
+            }
+
+            let y = start_y + pass_row * step_y;
+            for pass_column in 0..pass_width {
+                let x = start_x + pass_column * step_x;
+                let sample = |channel: usize| -> u16 {
+                    let bit = (pass_column * channels + channel) * bit_depth;
+                    match bit_depth {
+                        16 => u16::from_be_bytes([row[bit / 8], row[bit / 8 + 1]]),
+                        8 => row[bit / 8] as u16,
+                        _ => {
+                            let shift = 8 - bit_depth - bit % 8;
+                            ((row[bit / 8] >> shift) & ((1u8 << bit_depth) - 1)) as u16
+                        }
+                    }
+                };
+                let scale = |value: u16| -> u8 {
+                    match bit_depth {
+                        16 => (value >> 8) as u8,
+                        8 => value as u8,
+                        _ => (value as usize * 255 / ((1 << bit_depth) - 1)) as u8,
+                    }
+                };
+                let transparent_sample = |index: usize| -> Option<u16> {
+                    transparency
+                        .get(index * 2..index * 2 + 2)
+                        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+                };
+                let pixel = match color_type {
+                    0 => {
+                        let gray = sample(0);
+                        let alpha = if transparent_sample(0) == Some(gray) {
+                            0
+                        } else {
+                            255
+                        };
+                        [scale(gray), scale(gray), scale(gray), alpha]
+                    }
+                    2 => {
+                        let (red, green, blue) = (sample(0), sample(1), sample(2));
+                        let alpha = if transparent_sample(0) == Some(red)
+                            && transparent_sample(1) == Some(green)
+                            && transparent_sample(2) == Some(blue)
+                        {
+                            0
+                        } else {
+                            255
+                        };
+                        [scale(red), scale(green), scale(blue), alpha]
+                    }
+                    3 => {
+                        let index = sample(0) as usize;
+                        let color = palette
+                            .get(index * 3..index * 3 + 3)
+                            .ok_or_else(|| malformed("palette index out of range"))?;
+                        let alpha = *transparency.get(index).unwrap_or(&255);
+                        [color[0], color[1], color[2], alpha]
+                    }
+                    4 => {
+                        let gray = scale(sample(0));
+                        [gray, gray, gray, scale(sample(1))]
+                    }
+                    _ => [
+                        scale(sample(0)),
+                        scale(sample(1)),
+                        scale(sample(2)),
+                        scale(sample(3)),
+                    ],
+                };
+                let index = (y * width + x) * 4;
+                rgba[index..index + 4].copy_from_slice(&pixel);
+            }
+            previous = row;
+        }
+    }
+    Ok((width, height, rgba))
+}
+
+fn paeth(left: u8, upper: u8, upper_left: u8) -> u8 {
+    let estimate = left as i16 + upper as i16 - upper_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_upper = (estimate - upper as i16).abs();
+    let distance_upper_left = (estimate - upper_left as i16).abs();
+    if distance_left <= distance_upper && distance_left <= distance_upper_left {
+        left
+    } else if distance_upper <= distance_upper_left {
+        upper
+    } else {
+        upper_left
+    }
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/* --- --- --- PPM / PGM --- --- --- */
+
+fn decode_netpbm(data: &[u8]) -> Result<PixelArray, ImageError> {
+    let malformed = |reason: &str| ImageError::Malformed(reason.to_string());
+    let mut position = 0;
+    let next_token = |position: &mut usize| -> Option<String> {
+        loop {
+            while *position < data.len() && data[*position].is_ascii_whitespace() {
+                *position += 1;
+            }
+            if *position < data.len() && data[*position] == b'#' {
+                while *position < data.len() && data[*position] != b'\n' {
+                    *position += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        let start = *position;
+        while *position < data.len() && !data[*position].is_ascii_whitespace() {
+            *position += 1;
+        }
+        if start == *position {
+            None
+        } else {
+            Some(String::from_utf8_lossy(&data[start..*position]).into_owned())
+        }
+    };
+    let number = |position: &mut usize| -> Result<usize, ImageError> {
+        next_token(position)
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| malformed("expected number"))
+    };
+
+    let magic = next_token(&mut position).ok_or_else(|| malformed("missing magic number"))?;
+    let (channels, binary) = match magic.as_str() {
+        "P2" => (1, false),
+        "P3" => (3, false),
+        "P5" => (1, true),
+        "P6" => (3, true),
+        _ => return Err(ImageError::Unsupported(format!("netpbm format {}", magic))),
+    };
+    let width = number(&mut position)?;
+    let height = number(&mut position)?;
+    let maximum = number(&mut position)?;
+    if maximum == 0 || maximum > 65535 {
+        return Err(malformed("maximum value out of range"));
+    }
+
+    let sample_count = width
+        .checked_mul(height)
+        .and_then(|count| count.checked_mul(channels))
+        .ok_or_else(|| malformed("image too large"))?;
+    let samples: Vec<usize> = if binary {
+        // Exactly one whitespace character separates the header from the data.
+        position += 1;
+        let bytes_per_sample = if maximum > 255 { 2 } else { 1 };
+        let end = sample_count
+            .checked_mul(bytes_per_sample)
+            .and_then(|length| length.checked_add(position))
+            .ok_or_else(|| malformed("image too large"))?;
+        if end > data.len() {
+            return Err(malformed("not enough image data"));
+        }
+        data[position..end]
+            .chunks(bytes_per_sample)
+            .map(|bytes| {
+                bytes
+                    .iter()
+                    .fold(0usize, |value, byte| value << 8 | *byte as usize)
+            })
+            .collect()
+    } else {
+        // Every sample takes at least one digit and one separator.
+        if sample_count > data.len() / 2 {
+            return Err(malformed("not enough image data"));
+        }
+        (0..sample_count)
+            .map(|_| number(&mut position))
+            .collect::<Result<_, _>>()?
+    };
+
+    let scale = |value: usize| (value.min(maximum) * 255 / maximum) as u8;
+    Ok(PixelArray {
+        data: samples
+            .chunks(channels)
+            .map(|sample| {
+                if channels == 1 {
+                    Pixel::with(scale(sample[0]), scale(sample[0]), scale(sample[0]))
+                } else {
+                    Pixel::with(scale(sample[0]), scale(sample[1]), scale(sample[2]))
+                }
+            })
+            .collect(),
+        width,
+        height,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn gradient(width: usize, height: usize) -> PixelArray {
+        PixelArray {
+            data: (0..width * height)
+                .map(|index| Pixel::with(index as u8, (index * 7) as u8, 255 - index as u8))
+                .collect(),
+            width,
+            height,
+        }
+    }
+
+    fn is_malformed<T>(result: Result<T, ImageError>) -> bool {
+        matches!(result, Err(ImageError::Malformed(_)))
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let pixel_array = gradient(5, 3);
+        let mut png = Vec::new();
+        write_png(&pixel_array, &mut png).unwrap();
+        assert_eq!(read(&mut png.as_slice()).unwrap(), pixel_array);
+    }
+
+    #[test]
+    fn netpbm_round_trip() {
+        let pixel_array = gradient(4, 2);
+        let mut ppm = Vec::new();
+        write_ppm(&pixel_array, &mut ppm).unwrap();
+        assert_eq!(read(&mut ppm.as_slice()).unwrap(), pixel_array);
+
+        let plain = read(&mut &b"P2 2 1 # comment\n 4 4 0"[..]).unwrap();
+        assert_eq!(plain.data, vec![Pixel::white(), Pixel::black()]);
+    }
+
+    #[test]
+    fn png_header_larger_than_data_is_rejected() {
+        let mut png = PNG_SIGNATURE.to_vec();
+        write_png_chunk(&mut png, b"IHDR", &png_header(1 << 30, 1 << 30, true));
+        write_png_chunk(
+            &mut png,
+            b"IDAT",
+            &miniz_oxide::deflate::compress_to_vec_zlib(&[0u8; 4096], 6),
+        );
+        write_png_chunk(&mut png, b"IEND", &[]);
+        assert!(is_malformed(read(&mut png.as_slice())));
+    }
+
+    #[test]
+    fn png_data_larger_than_header_is_rejected() {
+        let mut png = PNG_SIGNATURE.to_vec();
+        write_png_chunk(&mut png, b"IHDR", &png_header(1, 1, false));
+        write_png_chunk(
+            &mut png,
+            b"IDAT",
+            &miniz_oxide::deflate::compress_to_vec_zlib(&[0u8; 1 << 20], 6),
+        );
+        write_png_chunk(&mut png, b"IEND", &[]);
+        assert!(is_malformed(read(&mut png.as_slice())));
+    }
+
+    #[test]
+    fn netpbm_size_overflow_is_rejected() {
+        let header = format!("P6 {} {} 255\n", u64::MAX / 2, u64::MAX / 2);
+        assert!(is_malformed(read(&mut header.as_bytes())));
+        assert!(is_malformed(read(
+            &mut &b"P6 4294967296 4294967296 255\n\x00"[..]
+        )));
+        assert!(is_malformed(read(&mut &b"P3 65536 65536 255\n0 0 0"[..])));
+    }
+
+    /// Decompresses the variable code length LZW variant of GIF.
+    fn lzw_decompress(data: &[u8], minimum_code_size: u8) -> Vec<u8> {
+        let clear_code = 1usize << minimum_code_size;
+        let end_code = clear_code + 1;
+        let initial_dictionary: Vec<Vec<u8>> = (0..clear_code)
+            .map(|index| vec![index as u8])
+            .chain(vec![Vec::new(), Vec::new()])
+            .collect();
+
+        let mut dictionary = initial_dictionary.clone();
+        let mut code_size = minimum_code_size + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut output = Vec::new();
+        let mut bit = 0;
+        loop {
+            let code = (0..code_size as usize).fold(0usize, |code, offset| {
+                let position = bit + offset;
+                code | ((data[position / 8] as usize >> (position % 8)) & 1) << offset
+            });
+            bit += code_size as usize;
+            if code == clear_code {
+                dictionary = initial_dictionary.clone();
+                code_size = minimum_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end_code {
+                return output;
+            }
+            let entry = match (dictionary.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) if code == dictionary.len() => {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                _ => panic!("invalid code {}", code),
+            };
+            output.extend_from_slice(&entry);
+            if let Some(mut previous) = previous {
+                if dictionary.len() < 4096 {
+                    previous.push(entry[0]);
+                    dictionary.push(previous);
+                }
+            }
+            if dictionary.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            previous = Some(entry);
+        }
+    }
+
+    /// Returns the delay and the pixels of every frame of a GIF written by `encode_gif`.
+    pub(crate) fn decode_gif(gif: &[u8]) -> Vec<(u16, Vec<Pixel>)> {
+        let sub_blocks = |position: &mut usize| {
+            let mut data = Vec::new();
+            while gif[*position] != 0 {
+                let length = gif[*position] as usize;
+                data.extend_from_slice(&gif[*position + 1..*position + 1 + length]);
+                *position += 1 + length;
+            }
+            *position += 1;
+            data
+        };
+        assert_eq!(&gif[..6], b"GIF89a");
+        let mut position = 13;
+        let mut delay = 0;
+        let mut frames = Vec::new();
+        loop {
+            match gif[position] {
+                0x21 => {
+                    let label = gif[position + 1];
+                    position += 2;
+                    let data = sub_blocks(&mut position);
+                    if label == 0xF9 {
+                        delay = u16::from_le_bytes([data[1], data[2]]);
+                    }
+                }
+                0x2C => {
+                    let table_length = 3 << ((gif[position + 9] & 0x07) + 1);
+                    let palette = &gif[position + 10..position + 10 + table_length];
+                    position += 10 + table_length;
+                    let minimum_code_size = gif[position];
+                    position += 1;
+                    let indices = lzw_decompress(&sub_blocks(&mut position), minimum_code_size);
+                    let pixels = indices
+                        .iter()
+                        .map(|index| {
+                            let color = &palette[*index as usize * 3..*index as usize * 3 + 3];
+                            Pixel::with(color[0], color[1], color[2])
+                        })
+                        .collect();
+                    frames.push((delay, pixels));
+                }
+                0x3B => return frames,
+                block => panic!("unexpected block {}", block),
+            }
+        }
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        let mut state = 1u32;
+        let mut random = |bound: u32| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((state >> 16) % bound) as u8
+        };
+        let noise: Vec<u8> = (0..100_000).map(|_| random(256)).collect();
+        let runs: Vec<u8> = (0..20_000).map(|index| (index / 37 % 4) as u8).collect();
+        let few: Vec<u8> = (0..5_000).map(|_| random(3)).collect();
+        for (indices, minimum_code_size) in &[
+            (Vec::new(), 2),
+            (vec![1], 2),
+            (vec![0; 10_000], 2),
+            (runs, 2),
+            (few, 2),
+            (noise, 8),
+        ] {
+            let compressed = lzw_compress(indices, *minimum_code_size);
+            assert_eq!(&lzw_decompress(&compressed, *minimum_code_size), indices);
+        }
+    }
+
+    #[test]
+    fn gif_round_trip() {
+        let frames: Vec<PixelArray> = (0..3)
+            .map(|index| {
+                let mut frame = gradient(7, 5);
+                frame[(index, 0)] = Pixel::white();
+                frame
+            })
+            .collect();
+        let mut gif = Vec::new();
+        write_animation(&frames, 10.0, AnimationFormat::Gif, &mut gif).unwrap();
+        let decoded = decode_gif(&gif);
+        assert_eq!(decoded.len(), 3);
+        for ((delay, pixels), frame) in decoded.iter().zip(&frames) {
+            assert_eq!(*delay, 10);
+            assert_eq!(pixels, &frame.data);
+        }
+
+        // More than 256 colors are mapped onto the color cube.
+        let colorful = gradient(300, 2);
+        let mut gif = Vec::new();
+        write_animation(&[colorful], 10.0, AnimationFormat::Gif, &mut gif).unwrap();
+        assert_eq!(decode_gif(&gif)[0].1.len(), 600);
+    }
+
+    #[test]
+    fn gif_frames_are_at_least_two_hundredths_of_a_second() {
+        let frames: Vec<PixelArray> = (0..12)
+            .map(|index| PixelArray {
+                data: vec![Pixel::with(index as u8, 0, 0)],
+                width: 1,
+                height: 1,
+            })
+            .collect();
+        let decode = |frames_per_second: f64| {
+            let mut gif = Vec::new();
+            write_animation(&frames, frames_per_second, AnimationFormat::Gif, &mut gif).unwrap();
+            decode_gif(&gif)
+                .into_iter()
+                .map(|(delay, pixels)| (delay, pixels[0].red))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            decode(50.0),
+            (0..12).map(|red| (2, red)).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            decode(100.0),
+            (0..6).map(|index| (2, index * 2)).collect::<Vec<_>>()
+        );
+        assert_eq!(decode(1000.0), vec![(2, 0)]);
+        let frames_at_60 = decode(60.0);
+        assert!(frames_at_60.iter().all(|(delay, _)| *delay >= 2));
+        let total: u16 = frames_at_60.iter().map(|(delay, _)| delay).sum();
+        assert_eq!(total, 20);
+    }
+}
@@ -16,12 +16,35 @@ use std::ops::{Index, IndexMut};
 use gymnarium_base::math::{Position2D, Size2D, Transformation2D, Transformations2D, Vector2D};
 use gymnarium_base::{Agent, AgentAction, EnvironmentState, Reward, Seed};
 
+use crate::math3d::{Position3D, Size3D, Transformation3D, Transformations3D, Vector3D};
+use crate::tessellation::{
+    contours_contain, distance_to_ellipse, ellipse_bounds, polygon_bounds, rectangle_contour,
+    stroke_bounds, stroke_contains, Affine2D, Bounds,
+};
+
+pub mod color;
+pub mod colormap;
+pub mod font;
+pub mod image;
 pub mod input;
+pub mod math3d;
+pub mod model;
+pub mod rasterizer;
+pub mod rasterizer3d;
+pub mod recorder;
+pub mod replay;
+pub mod scene;
+pub mod scene_stream;
+pub mod svg;
+pub mod terminal;
+pub mod view_mapping;
+
+mod tessellation;
 
 /* --- --- --- Rgb --- --- --- */
 
 /// Used for the RgbArray.
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Pixel {
     pub red: u8,
     pub green: u8,
@@ -101,7 +124,7 @@ impl Pixel {
 /* --- --- --- Color --- --- --- */
 
 /// Classic red, green, blue and alpha for defining color.
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Color {
     pub red: u8,
     pub green: u8,
@@ -202,6 +225,9 @@ impl Color {
 }
 
 impl Color {
+    /// Returns the channels scaled to `[0, 1]` with straight, not premultiplied, alpha.
+    ///
+    /// These are the values `BlendMode` operates on.
     pub fn float_array(&self) -> [f32; 4] {
         [
             self.red as f32 / 255f32,
@@ -210,12 +236,108 @@ impl Color {
             self.alpha as f32 / 255f32,
         ]
     }
+
+    /// Returns the channels scaled to `[0, 1]` with the color channels multiplied by alpha.
+    pub fn premultiplied_float_array(&self) -> [f32; 4] {
+        let [red, green, blue, alpha] = self.float_array();
+        [red * alpha, green * alpha, blue * alpha, alpha]
+    }
+
+    /// Composites this color on top of the backdrop color.
+    ///
+    /// The resulting alpha is `alpha_s + alpha_b * (1 - alpha_s)` and the resulting color
+    /// `(alpha_s * ((1 - alpha_b) * C_s + alpha_b * B(C_b, C_s)) + alpha_b * C_b * (1 - alpha_s)) / alpha`
+    /// with `B` being the blend function of the given blend mode.
+    pub fn blend(&self, backdrop: &Color, blend_mode: BlendMode) -> Color {
+        let source = self.float_array();
+        let backdrop = backdrop.float_array();
+        let alpha = source[3] + backdrop[3] * (1f32 - source[3]);
+        if alpha <= 0f32 {
+            return Color::transparent();
+        }
+        let channel = |index: usize| {
+            let mixed_source = (1f32 - backdrop[3]) * source[index]
+                + backdrop[3] * blend_mode.blend_channel(backdrop[index], source[index]);
+            let composited =
+                source[3] * mixed_source + backdrop[3] * backdrop[index] * (1f32 - source[3]);
+            float_to_channel(composited / alpha)
+        };
+        Color::with(channel(0), channel(1), channel(2), float_to_channel(alpha))
+    }
+}
+
+impl Pixel {
+    /// Composites the color on top of this pixel.
+    ///
+    /// Pixels are always opaque, so this equals `Color::blend` with a fully opaque backdrop.
+    pub fn blend(&self, color: &Color, blend_mode: BlendMode) -> Pixel {
+        let [red, green, blue, alpha] = color.float_array();
+        self.blend_channels([red, green, blue], alpha, blend_mode)
+    }
+
+    pub(crate) fn blend_channels(
+        &self,
+        source: [f32; 3],
+        source_alpha: f32,
+        blend_mode: BlendMode,
+    ) -> Pixel {
+        let channel = |backdrop: u8, source: f32| {
+            let backdrop = backdrop as f32 / 255f32;
+            float_to_channel(
+                source_alpha * blend_mode.blend_channel(backdrop, source)
+                    + (1f32 - source_alpha) * backdrop,
+            )
+        };
+        Pixel::with(
+            channel(self.red, source[0]),
+            channel(self.green, source[1]),
+            channel(self.blue, source[2]),
+        )
+    }
+}
+
+fn float_to_channel(value: f32) -> u8 {
+    (value.clamp(0f32, 1f32) * 255f32).round() as u8
+}
+
+/* --- --- --- BlendMode --- --- --- */
+
+/// Defines how the color of a geometry is mixed with the colors already drawn beneath it.
+///
+/// Every mode is applied with source-over compositing, so translucent geometries always let
+/// the backdrop shine through according to their alpha.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum BlendMode {
+    /// The color simply replaces the backdrop.
+    #[default]
+    SourceOver,
+    /// The color is added to the backdrop, saturating at white.
+    Additive,
+    /// The color is multiplied with the backdrop, which never brightens it.
+    Multiply,
+    /// The inverted color is multiplied with the inverted backdrop, which never darkens it.
+    Screen,
+}
+
+impl BlendMode {
+    /// The blend function `B(C_b, C_s)` for a single channel in `[0, 1]`.
+    pub fn blend_channel(&self, backdrop: f32, source: f32) -> f32 {
+        match self {
+            Self::SourceOver => source,
+            Self::Additive => (backdrop + source).min(1f32),
+            Self::Multiply => backdrop * source,
+            Self::Screen => backdrop + source - backdrop * source,
+        }
+    }
 }
 
 /* --- --- --- RgbArray --- --- --- */
 
 /// The type returned from the RgbArrayDrawing.
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+///
+/// Pixel arrays are always opaque, translucent colors are composited onto them with
+/// `Pixel::blend`.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PixelArray {
     pub data: Vec<Pixel>,
     pub width: usize,
@@ -255,7 +377,7 @@ impl IndexMut<(usize, usize)> for PixelArray {
 /* --- --- --- LineShape --- --- --- */
 
 /// The shape of a Line or Polyline.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum LineShape {
     /// Square edges
     Square,
@@ -268,7 +390,7 @@ pub enum LineShape {
 /* --- --- --- CornerShape --- --- --- */
 
 /// The shape of corners.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CornerShape {
     /// Square corners.
     Square,
@@ -278,9 +400,35 @@ pub enum CornerShape {
     Bevel(f64),
 }
 
+/* --- --- --- HorizontalAnchor --- --- --- */
+
+/// The horizontal alignment of a Text relative to its position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HorizontalAnchor {
+    /// The text starts at the position.
+    Left,
+    Center,
+    /// The text ends at the position.
+    Right,
+}
+
+/* --- --- --- VerticalAnchor --- --- --- */
+
+/// The vertical alignment of a Text relative to its position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum VerticalAnchor {
+    /// The top of the first line is at the position.
+    Top,
+    Middle,
+    /// The baseline of the first line is at the position.
+    Baseline,
+    /// The bottom of the last line is at the position.
+    Bottom,
+}
+
 /* --- --- --- TextureSource --- --- --- */
 
-#[derive(Hash, Debug, Clone, PartialEq, Eq)]
+#[derive(Hash, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TextureSource {
     Path(String),
     /// Contains the texture as byte array.
@@ -320,12 +468,31 @@ impl TextureSource {
             height,
         }
     }
+
+    /// Returns the data with its width and height if it holds exactly `width * height` RGBA
+    /// pixels.
+    pub(crate) fn rgba_bytes(&self) -> Option<(&[u8], u32, u32)> {
+        match self {
+            Self::Bytes {
+                data,
+                width,
+                height,
+            } if (*width as usize)
+                .checked_mul(*height as usize)
+                .and_then(|count| count.checked_mul(4))
+                == Some(data.len()) =>
+            {
+                Some((data, *width, *height))
+            }
+            _ => None,
+        }
+    }
 }
 
 /* --- --- --- Geometry2D --- --- --- */
 
 /// All supported primitives inside the two dimensional space.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Geometry2D {
     Point {
         position: Position2D,
@@ -394,7 +561,22 @@ pub enum Geometry2D {
         border_width: f64,
         transformations: Transformations2D,
     },
-    // TODO: Text
+    /// Text in the built-in `font`, lines are separated by `\n` and laid out along the positive
+    /// y axis.
+    ///
+    /// Every line is `font_size` high, see `font::measure_text` for the size of the whole text.
+    /// Unlike other geometries the text is not mirrored by flipped viewports, but mirrored back
+    /// around its position, so that it stays readable in worlds with the y axis pointing up.
+    /// `contains` and the bounding boxes assume a viewport without flipped axes.
+    Text {
+        position: Position2D,
+        text: String,
+        font_size: f64,
+        color: Color,
+        horizontal_anchor: HorizontalAnchor,
+        vertical_anchor: VerticalAnchor,
+        transformations: Transformations2D,
+    },
     Image {
         center_position: Position2D,
         size: Size2D,
@@ -540,6 +722,22 @@ impl Geometry2D {
         }
     }
 
+    /// Creates a new text at the given position.
+    ///
+    /// Defaults are `color: Color::black()`, `horizontal_anchor: HorizontalAnchor::Left` and
+    /// `vertical_anchor: VerticalAnchor::Top`.
+    pub fn text(position: Position2D, text: String, font_size: f64) -> Self {
+        Self::Text {
+            position,
+            text,
+            font_size,
+            color: Color::black(),
+            horizontal_anchor: HorizontalAnchor::Left,
+            vertical_anchor: VerticalAnchor::Top,
+            transformations: Transformations2D::default(),
+        }
+    }
+
     /// Creates a new image with the given center position and selected image.
     ///
     /// Defaults are `source_rectangle: None` and `fill_color: None`.
@@ -577,146 +775,438 @@ impl Geometry2D {
     }
 
     pub fn minimum_position_in_transformed_bounding_box(&self) -> Position2D {
-        self.conditional_position_in_transformed_bounding_box(|a, b| {
-            Position2D::with(a.x.min(b.x), a.y.min(b.y))
-        })
-        .unwrap_or_else(|| Position2D::with(std::f64::MAX, std::f64::MAX))
+        self.transformed_bounds()
+            .map(|bounds| bounds.minimum)
+            .unwrap_or_else(|| Position2D::with(std::f64::MAX, std::f64::MAX))
     }
 
     pub fn maximum_position_in_transformed_bounding_box(&self) -> Position2D {
-        self.conditional_position_in_transformed_bounding_box(|a, b| {
-            Position2D::with(a.x.max(b.x), a.y.max(b.y))
-        })
-        .unwrap_or_else(|| Position2D::with(std::f64::MIN, std::f64::MIN))
+        self.transformed_bounds()
+            .map(|bounds| bounds.maximum)
+            .unwrap_or_else(|| Position2D::with(std::f64::MIN, std::f64::MIN))
     }
 
-    fn conditional_position_in_transformed_bounding_box<
-        F: Fn(Position2D, Position2D) -> Position2D + Copy,
-    >(
-        &self,
-        merge_two_positions: F,
-    ) -> Option<Position2D> {
+    /// Returns the tight bounds of everything the geometry covers after its transformations.
+    ///
+    /// Line and border widths are included together with the caps and joins of their line
+    /// shape, borders being centered on the outline and joined like `LineShape::Square`.
+    fn transformed_bounds(&self) -> Option<Bounds> {
         match self {
             Self::Point {
                 position,
                 transformations,
                 ..
-            } => Some(position.transform(transformations)),
+            } => polygon_bounds(
+                &[*position],
+                &Affine2D::from_transformations(transformations),
+            ),
             Self::Line {
                 points,
+                line_width,
+                line_shape,
                 transformations,
                 ..
-            } => Some(merge_two_positions(
-                points[0].transform(transformations),
-                points[1].transform(transformations),
-            )),
+            } => stroke_bounds(
+                points,
+                *line_width,
+                line_shape,
+                false,
+                &Affine2D::from_transformations(transformations),
+            ),
             Self::Polyline {
                 points,
+                line_width,
+                line_shape,
                 transformations,
                 ..
-            } => points
-                .iter()
-                .map(|a| a.transform(transformations))
-                .reduce(merge_two_positions),
+            } => stroke_bounds(
+                points,
+                *line_width,
+                line_shape,
+                false,
+                &Affine2D::from_transformations(transformations),
+            ),
             Self::Triangle {
                 points,
+                border_width,
                 transformations,
                 ..
-            } => Some(merge_two_positions(
-                merge_two_positions(
-                    points[0].transform(transformations),
-                    points[1].transform(transformations),
-                ),
-                points[2].transform(transformations),
-            )),
+            } => stroke_bounds(
+                points,
+                *border_width,
+                &LineShape::Square,
+                true,
+                &Affine2D::from_transformations(transformations),
+            ),
             Self::Square {
                 center_position,
                 edge_length,
+                border_width,
+                corner_shape,
                 transformations,
                 ..
-            } => [
-                *center_position - Vector2D::with(edge_length / 2f64, edge_length / 2f64),
-                *center_position - Vector2D::with(-edge_length / 2f64, edge_length / 2f64),
-                *center_position - Vector2D::with(-edge_length / 2f64, -edge_length / 2f64),
-                *center_position - Vector2D::with(edge_length / 2f64, -edge_length / 2f64),
-            ]
-            .iter()
-            .map(|a| a.transform(transformations))
-            .reduce(merge_two_positions),
+            } => stroke_bounds(
+                &rectangle_contour(center_position, *edge_length, *edge_length, corner_shape),
+                *border_width,
+                &LineShape::Square,
+                true,
+                &Affine2D::from_transformations(transformations),
+            ),
             Self::Rectangle {
                 center_position,
                 size,
+                border_width,
+                corner_shape,
                 transformations,
                 ..
-            } => [
-                *center_position - Vector2D::with(size.width / 2f64, size.height / 2f64),
-                *center_position - Vector2D::with(-size.width / 2f64, size.height / 2f64),
-                *center_position - Vector2D::with(-size.width / 2f64, -size.height / 2f64),
-                *center_position - Vector2D::with(size.width / 2f64, -size.height / 2f64),
-            ]
-            .iter()
-            .map(|a| a.transform(transformations))
-            .reduce(merge_two_positions),
+            } => stroke_bounds(
+                &rectangle_contour(center_position, size.width, size.height, corner_shape),
+                *border_width,
+                &LineShape::Square,
+                true,
+                &Affine2D::from_transformations(transformations),
+            ),
             Self::Polygon {
                 points,
+                border_width,
                 transformations,
                 ..
-            } => points
-                .iter()
-                .map(|a| a.transform(transformations))
-                .reduce(merge_two_positions),
+            } => stroke_bounds(
+                points,
+                *border_width,
+                &LineShape::Square,
+                true,
+                &Affine2D::from_transformations(transformations),
+            ),
             Self::Circle {
                 center_position,
                 radius,
+                border_width,
                 transformations,
                 ..
-            } => [
-                *center_position - Vector2D::with(*radius, *radius),
-                *center_position - Vector2D::with(-*radius, *radius),
-                *center_position - Vector2D::with(-*radius, -*radius),
-                *center_position - Vector2D::with(*radius, -*radius),
-            ]
-            .iter()
-            .map(|a| a.transform(transformations))
-            .reduce(merge_two_positions),
+            } => Some(ellipse_bounds(
+                center_position,
+                radius.abs(),
+                radius.abs(),
+                border_width / 2f64,
+                &Affine2D::from_transformations(transformations),
+            )),
             Self::Ellipse {
                 center_position,
                 size,
+                border_width,
+                transformations,
+                ..
+            } => Some(ellipse_bounds(
+                center_position,
+                size.width.abs() / 2f64,
+                size.height.abs() / 2f64,
+                border_width / 2f64,
+                &Affine2D::from_transformations(transformations),
+            )),
+            Self::Text {
+                position,
+                text,
+                font_size,
+                horizontal_anchor,
+                vertical_anchor,
+                transformations,
+                ..
+            } => polygon_bounds(
+                &Self::text_corners(
+                    position,
+                    text,
+                    *font_size,
+                    horizontal_anchor,
+                    vertical_anchor,
+                ),
+                &Affine2D::from_transformations(transformations),
+            ),
+            Self::Image {
+                center_position,
+                size,
+                transformations,
+                ..
+            } => polygon_bounds(
+                &rectangle_contour(
+                    center_position,
+                    size.width,
+                    size.height,
+                    &CornerShape::Square,
+                ),
+                &Affine2D::from_transformations(transformations),
+            ),
+            Self::Group(geometries) => geometries
+                .iter()
+                .filter_map(|geometry| geometry.transformed_bounds())
+                .reduce(|a, b| a.union(&b)),
+        }
+    }
+
+    /// Returns whether the position lies on the visible parts of the geometry after its
+    /// transformations.
+    ///
+    /// Fills with a transparent color are hollow and lines or borders with a transparent color
+    /// are ignored, while visible ones count with their width, line shape and, for borders,
+    /// square joins. Points contain the square with an edge length of one around their
+    /// transformed position, which is the pixel drawn for them if world units are pixels. Texts
+    /// and images contain the whole box they are drawn into. Groups contain everything one of
+    /// their members contains.
+    pub fn contains(&self, position: &Position2D) -> bool {
+        let local_position = |transformations: &Transformations2D| {
+            Affine2D::from_transformations(transformations)
+                .inverse()
+                .map(|inverse| inverse.apply(position))
+        };
+        match self {
+            Self::Point {
+                position: point_position,
                 transformations,
                 ..
-            } => [
-                *center_position - Vector2D::with(size.width / 2f64, size.height / 2f64),
-                *center_position - Vector2D::with(-size.width / 2f64, size.height / 2f64),
-                *center_position - Vector2D::with(-size.width / 2f64, -size.height / 2f64),
-                *center_position - Vector2D::with(size.width / 2f64, -size.height / 2f64),
-            ]
-            .iter()
-            .map(|a| a.transform(transformations))
-            .reduce(merge_two_positions),
+            } => {
+                let point_position = point_position.transform(transformations);
+                (position.x - point_position.x).abs() <= 0.5f64
+                    && (position.y - point_position.y).abs() <= 0.5f64
+            }
+            Self::Line {
+                points,
+                line_color,
+                line_width,
+                line_shape,
+                transformations,
+            } => local_position(transformations).is_some_and(|local_position| {
+                line_color.alpha > 0
+                    && stroke_contains(points, *line_width, line_shape, false, &local_position)
+            }),
+            Self::Polyline {
+                points,
+                line_color,
+                line_width,
+                line_shape,
+                transformations,
+            } => local_position(transformations).is_some_and(|local_position| {
+                line_color.alpha > 0
+                    && stroke_contains(points, *line_width, line_shape, false, &local_position)
+            }),
+            Self::Triangle {
+                points,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => local_position(transformations).is_some_and(|local_position| {
+                Self::shape_contains(
+                    points,
+                    fill_color,
+                    border_color,
+                    *border_width,
+                    &local_position,
+                )
+            }),
+            Self::Square {
+                center_position,
+                edge_length,
+                fill_color,
+                border_color,
+                border_width,
+                corner_shape,
+                transformations,
+            } => local_position(transformations).is_some_and(|local_position| {
+                Self::shape_contains(
+                    &rectangle_contour(center_position, *edge_length, *edge_length, corner_shape),
+                    fill_color,
+                    border_color,
+                    *border_width,
+                    &local_position,
+                )
+            }),
+            Self::Rectangle {
+                center_position,
+                size,
+                fill_color,
+                border_color,
+                border_width,
+                corner_shape,
+                transformations,
+            } => local_position(transformations).is_some_and(|local_position| {
+                Self::shape_contains(
+                    &rectangle_contour(center_position, size.width, size.height, corner_shape),
+                    fill_color,
+                    border_color,
+                    *border_width,
+                    &local_position,
+                )
+            }),
+            Self::Polygon {
+                points,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => local_position(transformations).is_some_and(|local_position| {
+                Self::shape_contains(
+                    points,
+                    fill_color,
+                    border_color,
+                    *border_width,
+                    &local_position,
+                )
+            }),
+            Self::Circle {
+                center_position,
+                radius,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => local_position(transformations).is_some_and(|local_position| {
+                Self::ellipse_contains(
+                    center_position,
+                    (radius.abs(), radius.abs()),
+                    fill_color,
+                    border_color,
+                    *border_width,
+                    &local_position,
+                )
+            }),
+            Self::Ellipse {
+                center_position,
+                size,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => local_position(transformations).is_some_and(|local_position| {
+                Self::ellipse_contains(
+                    center_position,
+                    (size.width.abs() / 2f64, size.height.abs() / 2f64),
+                    fill_color,
+                    border_color,
+                    *border_width,
+                    &local_position,
+                )
+            }),
+            Self::Text {
+                position: text_position,
+                text,
+                font_size,
+                color,
+                horizontal_anchor,
+                vertical_anchor,
+                transformations,
+            } => local_position(transformations).is_some_and(|local_position| {
+                let corners = Self::text_corners(
+                    text_position,
+                    text,
+                    *font_size,
+                    horizontal_anchor,
+                    vertical_anchor,
+                );
+                color.alpha > 0 && contours_contain(&[corners.to_vec()], &local_position)
+            }),
             Self::Image {
                 center_position,
                 size,
                 transformations,
                 ..
-            } => [
-                *center_position - Vector2D::with(size.width / 2f64, size.height / 2f64),
-                *center_position - Vector2D::with(-size.width / 2f64, size.height / 2f64),
-                *center_position - Vector2D::with(-size.width / 2f64, -size.height / 2f64),
-                *center_position - Vector2D::with(size.width / 2f64, -size.height / 2f64),
-            ]
-            .iter()
-            .map(|a| a.transform(transformations))
-            .reduce(merge_two_positions),
+            } => local_position(transformations).is_some_and(|local_position| {
+                let outline = rectangle_contour(
+                    center_position,
+                    size.width,
+                    size.height,
+                    &CornerShape::Square,
+                );
+                contours_contain(&[outline], &local_position)
+            }),
             Self::Group(geometries) => geometries
                 .iter()
-                .map(|geometry| {
-                    geometry.conditional_position_in_transformed_bounding_box(merge_two_positions)
-                })
-                .filter(|position| position.is_some())
-                .map(|position| position.unwrap())
-                .reduce(merge_two_positions),
+                .any(|geometry| geometry.contains(position)),
         }
     }
+
+    fn shape_contains(
+        outline: &[Position2D],
+        fill_color: &Color,
+        border_color: &Color,
+        border_width: f64,
+        local_position: &Position2D,
+    ) -> bool {
+        fill_color.alpha > 0 && contours_contain(&[outline.to_vec()], local_position)
+            || border_color.alpha > 0
+                && stroke_contains(
+                    outline,
+                    border_width,
+                    &LineShape::Square,
+                    true,
+                    local_position,
+                )
+    }
+
+    fn ellipse_contains(
+        center_position: &Position2D,
+        (radius_x, radius_y): (f64, f64),
+        fill_color: &Color,
+        border_color: &Color,
+        border_width: f64,
+        local_position: &Position2D,
+    ) -> bool {
+        let (x, y) = (
+            (local_position.x - center_position.x) / radius_x,
+            (local_position.y - center_position.y) / radius_y,
+        );
+        fill_color.alpha > 0 && x * x + y * y <= 1f64
+            || border_color.alpha > 0
+                && distance_to_ellipse(center_position, radius_x, radius_y, local_position)
+                    <= border_width.abs() / 2f64
+    }
+
+    /// Returns the untransformed corners of the box around a text.
+    pub(crate) fn text_corners(
+        position: &Position2D,
+        text: &str,
+        font_size: f64,
+        horizontal_anchor: &HorizontalAnchor,
+        vertical_anchor: &VerticalAnchor,
+    ) -> [Position2D; 4] {
+        let Size2D { width, height } = font::measure_text(text, font_size);
+        let left = match horizontal_anchor {
+            HorizontalAnchor::Left => position.x,
+            HorizontalAnchor::Center => position.x - width / 2f64,
+            HorizontalAnchor::Right => position.x - width,
+        };
+        let top = match vertical_anchor {
+            VerticalAnchor::Top => position.y,
+            VerticalAnchor::Middle => position.y - height / 2f64,
+            VerticalAnchor::Baseline => position.y - font_size * font::ASCENT,
+            VerticalAnchor::Bottom => position.y - height,
+        };
+        [
+            Position2D::with(left, top),
+            Position2D::with(left + width, top),
+            Position2D::with(left + width, top + height),
+            Position2D::with(left, top + height),
+        ]
+    }
+
+    /// Returns the transformation of a text into pixel coordinates, which mirrors the text
+    /// around its position along the axes flipped by the viewport.
+    pub(crate) fn text_transform(
+        position: &Position2D,
+        transformations: &Transformations2D,
+        pixel_transform: &Affine2D,
+    ) -> Affine2D {
+        let [scale_x, _, _, _, scale_y, _] = pixel_transform.0;
+        let mirror = |scale: f64| if scale < 0f64 { -1f64 } else { 1f64 };
+        let (mirror_x, mirror_y) = (mirror(scale_x), mirror(scale_y));
+        Affine2D::scale_and_translate(
+            mirror_x,
+            mirror_y,
+            position.x * (1f64 - mirror_x),
+            position.y * (1f64 - mirror_y),
+        )
+        .then(&Affine2D::from_transformations(transformations))
+        .then(pixel_transform)
+    }
 }
 
 impl Geometry2D {
@@ -839,6 +1329,7 @@ impl Geometry2D {
                 border_width,
                 transformations,
             },
+            t @ Self::Text { .. } => t,
             i @ Self::Image { .. } => i,
             Self::Group(geometries) => Self::Group(
                 geometries
@@ -968,6 +1459,7 @@ impl Geometry2D {
                 border_width: new_line_or_border_width,
                 transformations,
             },
+            t @ Self::Text { .. } => t,
             i @ Self::Image { .. } => i,
             Self::Group(geometries) => Self::Group(
                 geometries
@@ -1050,6 +1542,70 @@ impl Geometry2D {
         }
     }
 
+    pub fn font_size(self, new_font_size: f64) -> Self {
+        match self {
+            Self::Text {
+                position,
+                text,
+                color,
+                horizontal_anchor,
+                vertical_anchor,
+                transformations,
+                ..
+            } => Self::Text {
+                position,
+                text,
+                font_size: new_font_size,
+                color,
+                horizontal_anchor,
+                vertical_anchor,
+                transformations,
+            },
+            Self::Group(geometries) => Self::Group(
+                geometries
+                    .into_iter()
+                    .map(|geometry| geometry.font_size(new_font_size))
+                    .collect(),
+            ),
+            g => g,
+        }
+    }
+
+    pub fn text_anchor(
+        self,
+        new_horizontal_anchor: HorizontalAnchor,
+        new_vertical_anchor: VerticalAnchor,
+    ) -> Self {
+        match self {
+            Self::Text {
+                position,
+                text,
+                font_size,
+                color,
+                transformations,
+                ..
+            } => Self::Text {
+                position,
+                text,
+                font_size,
+                color,
+                horizontal_anchor: new_horizontal_anchor,
+                vertical_anchor: new_vertical_anchor,
+                transformations,
+            },
+            Self::Group(geometries) => Self::Group(
+                geometries
+                    .into_iter()
+                    .map(|geometry| {
+                        geometry
+                            .text_anchor(new_horizontal_anchor.clone(), new_vertical_anchor.clone())
+                    })
+                    .collect(),
+            ),
+            g => g,
+        }
+    }
+
     pub fn fill_color(self, new_fill_color: Color) -> Self {
         match self {
             Self::Point {
@@ -1153,6 +1709,23 @@ impl Geometry2D {
                 border_width,
                 transformations,
             },
+            Self::Text {
+                position,
+                text,
+                font_size,
+                horizontal_anchor,
+                vertical_anchor,
+                transformations,
+                ..
+            } => Self::Text {
+                position,
+                text,
+                font_size,
+                color: new_fill_color,
+                horizontal_anchor,
+                vertical_anchor,
+                transformations,
+            },
             Self::Image {
                 center_position,
                 size,
@@ -1331,6 +1904,26 @@ impl Geometry2D {
                     transformations
                 },
             },
+            Self::Text {
+                position,
+                text,
+                font_size,
+                color,
+                horizontal_anchor,
+                vertical_anchor,
+                mut transformations,
+            } => Self::Text {
+                position,
+                text,
+                font_size,
+                color,
+                horizontal_anchor,
+                vertical_anchor,
+                transformations: {
+                    transformations.transformations.push(transformation);
+                    transformations
+                },
+            },
             Self::Image {
                 center_position,
                 size,
@@ -1416,23 +2009,472 @@ impl Geometry2D {
     }
 }
 
+/* --- --- --- Material --- --- --- */
+
+/// How the surface of a three dimensional geometry reflects light.
+///
+/// The shaded color is the color times `ambient` plus the color times `diffuse` times how
+/// directly the light falls onto the surface, with an additional white highlight of `specular`
+/// strength whose size shrinks with growing `shininess`.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Material {
+    pub color: Color,
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    pub shininess: f64,
+}
+
+impl Material {
+    /// Creates a matte material with the given color.
+    ///
+    /// Defaults are `ambient: 0.3f64`, `diffuse: 0.7f64`, `specular: 0f64` and
+    /// `shininess: 32f64`.
+    pub fn with(color: Color) -> Self {
+        Self {
+            color,
+            ambient: 0.3f64,
+            diffuse: 0.7f64,
+            specular: 0f64,
+            shininess: 32f64,
+        }
+    }
+
+    pub fn ambient(self, ambient: f64) -> Self {
+        Self { ambient, ..self }
+    }
+
+    pub fn diffuse(self, diffuse: f64) -> Self {
+        Self { diffuse, ..self }
+    }
+
+    pub fn specular(self, specular: f64, shininess: f64) -> Self {
+        Self {
+            specular,
+            shininess,
+            ..self
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::with(Color::black())
+    }
+}
+
 /* --- --- --- Geometry3D --- --- --- */
 
 /// All supported primitives inside the three dimensional space.
 ///
-/// *To be honest, currently I have no idea which are needed, and will add them later.*
-#[derive(Debug, Clone, PartialEq)]
+/// Solids are centered on their center position, the axis of cylinders and cones and the
+/// normal of planes point along the positive y axis. Line widths are given in pixels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Geometry3D {
-    // TODO: Which primitives are there?
-// Possibilities:
-// - https://www.peachpit.com/articles/article.aspx?p=30594&seqNum=5
-// - http://docs.daz3d.com/doku.php/public/software/hexagon/2/referenceguide/3d_primitives/start
+    Point {
+        position: Position3D,
+        color: Color,
+        transformations: Transformations3D,
+    },
+    Line {
+        points: [Position3D; 2],
+        color: Color,
+        line_width: f64,
+        transformations: Transformations3D,
+    },
+    /// A single sided triangle, its front is the side from which its points appear
+    /// counterclockwise.
+    Triangle {
+        points: [Position3D; 3],
+        material: Material,
+        transformations: Transformations3D,
+    },
+    /// Single sided triangles sharing their vertices, wound like `Triangle`.
+    ///
+    /// `normals` holds one normal per vertex for smooth shading, without normals the triangles
+    /// are shaded flat.
+    Mesh {
+        vertices: Vec<Position3D>,
+        normals: Vec<Vector3D>,
+        triangles: Vec<[usize; 3]>,
+        material: Material,
+        transformations: Transformations3D,
+    },
+    Box {
+        center_position: Position3D,
+        size: Size3D,
+        material: Material,
+        transformations: Transformations3D,
+    },
+    Sphere {
+        center_position: Position3D,
+        radius: f64,
+        /// Number of segments around the y axis, half of it from pole to pole.
+        segments: u32,
+        material: Material,
+        transformations: Transformations3D,
+    },
+    Cylinder {
+        center_position: Position3D,
+        radius: f64,
+        height: f64,
+        /// Number of segments around the axis.
+        segments: u32,
+        material: Material,
+        transformations: Transformations3D,
+    },
+    /// A cone with its base at the bottom and its apex at the top.
+    Cone {
+        center_position: Position3D,
+        radius: f64,
+        height: f64,
+        /// Number of segments around the axis.
+        segments: u32,
+        material: Material,
+        transformations: Transformations3D,
+    },
+    /// A rectangle inside the plane spanned by the x and z axis, visible from above.
+    Plane {
+        center_position: Position3D,
+        size: Size2D,
+        material: Material,
+        transformations: Transformations3D,
+    },
+    Group(Vec<Geometry3D>),
+}
+
+impl Geometry3D {
+    /// Creates a new point at the given position.
+    ///
+    /// Defaults are `color: Color::black()`.
+    pub fn point(position: Position3D) -> Self {
+        Self::Point {
+            position,
+            color: Color::black(),
+            transformations: Transformations3D::default(),
+        }
+    }
+
+    /// Creates a new line between the given positions.
+    ///
+    /// Defaults are `color: Color::black()` and `line_width: 1f64`.
+    pub fn line(start: Position3D, end: Position3D) -> Self {
+        Self::Line {
+            points: [start, end],
+            color: Color::black(),
+            line_width: 1f64,
+            transformations: Transformations3D::default(),
+        }
+    }
+
+    /// Creates a new triangle with the given three positions.
+    ///
+    /// Defaults are `material: Material::default()`.
+    pub fn triangle(
+        position_a: Position3D,
+        position_b: Position3D,
+        position_c: Position3D,
+    ) -> Self {
+        Self::Triangle {
+            points: [position_a, position_b, position_c],
+            material: Material::default(),
+            transformations: Transformations3D::default(),
+        }
+    }
+
+    /// Creates a new mesh of triangles given by the indices of their vertices.
+    ///
+    /// Defaults are `normals: Vec::new()` and `material: Material::default()`.
+    pub fn mesh(vertices: Vec<Position3D>, triangles: Vec<[usize; 3]>) -> Self {
+        Self::Mesh {
+            vertices,
+            normals: Vec::new(),
+            triangles,
+            material: Material::default(),
+            transformations: Transformations3D::default(),
+        }
+    }
+
+    /// Creates a new box with the given center position and size.
+    ///
+    /// Defaults are `material: Material::default()`.
+    pub fn cuboid(center_position: Position3D, size: Size3D) -> Self {
+        Self::Box {
+            center_position,
+            size,
+            material: Material::default(),
+            transformations: Transformations3D::default(),
+        }
+    }
+
+    /// Creates a new sphere with the given center position and radius.
+    ///
+    /// Defaults are `segments: 32` and `material: Material::default()`.
+    pub fn sphere(center_position: Position3D, radius: f64) -> Self {
+        Self::Sphere {
+            center_position,
+            radius,
+            segments: 32,
+            material: Material::default(),
+            transformations: Transformations3D::default(),
+        }
+    }
+
+    /// Creates a new cylinder with the given center position, radius and height.
+    ///
+    /// Defaults are `segments: 32` and `material: Material::default()`.
+    pub fn cylinder(center_position: Position3D, radius: f64, height: f64) -> Self {
+        Self::Cylinder {
+            center_position,
+            radius,
+            height,
+            segments: 32,
+            material: Material::default(),
+            transformations: Transformations3D::default(),
+        }
+    }
+
+    /// Creates a new cone with the given center position, radius of its base and height.
+    ///
+    /// Defaults are `segments: 32` and `material: Material::default()`.
+    pub fn cone(center_position: Position3D, radius: f64, height: f64) -> Self {
+        Self::Cone {
+            center_position,
+            radius,
+            height,
+            segments: 32,
+            material: Material::default(),
+            transformations: Transformations3D::default(),
+        }
+    }
+
+    /// Creates a new plane with the given center position and size along the x and z axis.
+    ///
+    /// Defaults are `material: Material::default()`.
+    pub fn plane(center_position: Position3D, size: Size2D) -> Self {
+        Self::Plane {
+            center_position,
+            size,
+            material: Material::default(),
+            transformations: Transformations3D::default(),
+        }
+    }
+
+    /// Creates a new group with the given geometries.
+    pub fn group(geometries: Vec<Geometry3D>) -> Self {
+        Self::Group(geometries)
+    }
+}
+
+impl Geometry3D {
+    /// Sets the color of points and lines and the color of the material of all other
+    /// geometries.
+    pub fn color(mut self, new_color: Color) -> Self {
+        match &mut self {
+            Self::Point { color, .. } | Self::Line { color, .. } => {
+                *color = new_color;
+                self
+            }
+            Self::Group(geometries) => Self::Group(
+                geometries
+                    .drain(..)
+                    .map(|geometry| geometry.color(new_color))
+                    .collect(),
+            ),
+            geometry => {
+                if let Some(material) = geometry.material_mut() {
+                    material.color = new_color;
+                }
+                self
+            }
+        }
+    }
+
+    /// Sets the material of all geometries except points and lines.
+    pub fn material(mut self, new_material: Material) -> Self {
+        match &mut self {
+            Self::Group(geometries) => Self::Group(
+                geometries
+                    .drain(..)
+                    .map(|geometry| geometry.material(new_material))
+                    .collect(),
+            ),
+            geometry => {
+                if let Some(material) = geometry.material_mut() {
+                    *material = new_material;
+                }
+                self
+            }
+        }
+    }
+
+    pub fn line_width(mut self, new_line_width: f64) -> Self {
+        match &mut self {
+            Self::Line { line_width, .. } => {
+                *line_width = new_line_width;
+                self
+            }
+            Self::Group(geometries) => Self::Group(
+                geometries
+                    .drain(..)
+                    .map(|geometry| geometry.line_width(new_line_width))
+                    .collect(),
+            ),
+            _ => self,
+        }
+    }
+
+    /// Sets the number of segments curved surfaces are approximated with, at least three.
+    pub fn segments(mut self, new_segments: u32) -> Self {
+        match &mut self {
+            Self::Sphere { segments, .. }
+            | Self::Cylinder { segments, .. }
+            | Self::Cone { segments, .. } => {
+                *segments = new_segments.max(3);
+                self
+            }
+            Self::Group(geometries) => Self::Group(
+                geometries
+                    .drain(..)
+                    .map(|geometry| geometry.segments(new_segments))
+                    .collect(),
+            ),
+            _ => self,
+        }
+    }
+
+    /// Sets the vertex normals of meshes.
+    pub fn normals(mut self, new_normals: Vec<Vector3D>) -> Self {
+        if let Self::Mesh { normals, .. } = &mut self {
+            *normals = new_normals;
+        }
+        self
+    }
+
+    /// Sets the vertex normals of meshes to the average normal of their adjacent triangles,
+    /// weighted by the area of the triangles.
+    pub fn smooth_normals(mut self) -> Self {
+        match &mut self {
+            Self::Mesh {
+                vertices,
+                normals,
+                triangles,
+                ..
+            } => {
+                let mut sums = vec![Vector3D::zero(); vertices.len()];
+                for triangle in triangles.iter() {
+                    if let (Some(a), Some(b), Some(c)) = (
+                        vertices.get(triangle[0]),
+                        vertices.get(triangle[1]),
+                        vertices.get(triangle[2]),
+                    ) {
+                        let normal = a.vector_to(b).cross(&a.vector_to(c));
+                        for index in triangle {
+                            sums[*index] = sums[*index] + normal;
+                        }
+                    }
+                }
+                *normals = sums.iter().map(Vector3D::normalized).collect();
+                self
+            }
+            Self::Group(geometries) => Self::Group(
+                geometries
+                    .drain(..)
+                    .map(|geometry| geometry.smooth_normals())
+                    .collect(),
+            ),
+            _ => self,
+        }
+    }
+
+    pub fn append_transformation(mut self, transformation: Transformation3D) -> Self {
+        match &mut self {
+            Self::Group(geometries) => Self::Group(
+                geometries
+                    .drain(..)
+                    .map(|geometry| geometry.append_transformation(transformation))
+                    .collect(),
+            ),
+            geometry => {
+                if let Some(transformations) = geometry.transformations_mut() {
+                    transformations.transformations.push(transformation);
+                }
+                self
+            }
+        }
+    }
+
+    pub fn move_by(self, distance: Vector3D) -> Self {
+        self.append_transformation(Transformation3D::translation(distance))
+    }
+
+    pub fn rotate_around_origin(self, axis: &Vector3D, degree: f64) -> Self {
+        self.append_transformation(Transformation3D::rotation(axis, degree))
+    }
+
+    pub fn rotate_around(self, rotate_position: &Position3D, axis: &Vector3D, degree: f64) -> Self {
+        self.append_transformation(Transformation3D::rotation_around_position(
+            rotate_position,
+            axis,
+            degree,
+        ))
+    }
+
+    pub fn scale(self, x: f64, y: f64, z: f64) -> Self {
+        self.append_transformation(Transformation3D::scale(x, y, z))
+    }
+
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        match self {
+            Self::Triangle { material, .. }
+            | Self::Mesh { material, .. }
+            | Self::Box { material, .. }
+            | Self::Sphere { material, .. }
+            | Self::Cylinder { material, .. }
+            | Self::Cone { material, .. }
+            | Self::Plane { material, .. } => Some(material),
+            Self::Point { .. } | Self::Line { .. } | Self::Group(_) => None,
+        }
+    }
+
+    fn transformations_mut(&mut self) -> Option<&mut Transformations3D> {
+        match self {
+            Self::Point {
+                transformations, ..
+            }
+            | Self::Line {
+                transformations, ..
+            }
+            | Self::Triangle {
+                transformations, ..
+            }
+            | Self::Mesh {
+                transformations, ..
+            }
+            | Self::Box {
+                transformations, ..
+            }
+            | Self::Sphere {
+                transformations, ..
+            }
+            | Self::Cylinder {
+                transformations, ..
+            }
+            | Self::Cone {
+                transformations, ..
+            }
+            | Self::Plane {
+                transformations, ..
+            } => Some(transformations),
+            Self::Group(_) => None,
+        }
+    }
 }
 
 /* --- --- --- Viewport2D --- --- --- */
 
 /// A viewing rectangle inside the two dimensional space.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Viewport2D {
     pub center: Position2D,
     pub size: Size2D,
@@ -1469,24 +2511,191 @@ impl Viewport2D {
     }
 }
 
+/* --- --- --- Projection3D --- --- --- */
+
+/// How a `Viewport3D` projects the space onto its image.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Projection3D {
+    /// Objects shrink with their distance, the vertical field of view is given in degrees.
+    Perspective { field_of_view: f64 },
+    /// Objects keep their size, the given height is visible at every distance.
+    Orthographic { height: f64 },
+}
+
 /* --- --- --- Viewport3D --- --- --- */
 
-/// Maybe the camera view inside the three dimensional space.
+/// The camera view inside the three dimensional space.
 ///
-/// *To be honest, currently I have no idea how to define something like that, and will add it
-/// later.*
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// The camera at `camera_position` looks at `look_at`, with `up` pointing upwards in its image.
+/// Only what lies between the `near` and `far` plane in front of the camera is visible.
+///
+/// The image is projected into a two dimensional space in which the visible area reaches from
+/// `-1` at the bottom to `1` at the top and, depending on the aspect ratio, equally far to the
+/// left and right. `viewport_2d` returns the matching `Viewport2D`, in which two dimensional
+/// overlays are drawn on top of the image, e.g. at positions returned by `project`.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Viewport3D {
-    /*camera_position: Position3D,
-view_direction: TODO: How to define camera view direction?,
-view_depth: f64,
-field_of_view: Size2D,
-TODO: Is this the correct way to define a 3D viewport?
-*/}
+    pub camera_position: Position3D,
+    pub look_at: Position3D,
+    pub up: Vector3D,
+    pub projection: Projection3D,
+    pub near: f64,
+    pub far: f64,
+}
+
+impl Viewport3D {
+    /// Creates a new camera with perspective projection and the vertical field of view in
+    /// degrees.
+    ///
+    /// Defaults are `up: Vector3D::with(0f64, 1f64, 0f64)`, `near: 0.1f64` and
+    /// `far: 1000f64`.
+    pub fn perspective(
+        camera_position: Position3D,
+        look_at: Position3D,
+        field_of_view: f64,
+    ) -> Self {
+        Self {
+            camera_position,
+            look_at,
+            up: Vector3D::with(0f64, 1f64, 0f64),
+            projection: Projection3D::Perspective { field_of_view },
+            near: 0.1f64,
+            far: 1000f64,
+        }
+    }
+
+    /// Creates a new camera with orthographic projection showing the given height.
+    ///
+    /// Defaults are `up: Vector3D::with(0f64, 1f64, 0f64)`, `near: 0.1f64` and
+    /// `far: 1000f64`.
+    pub fn orthographic(camera_position: Position3D, look_at: Position3D, height: f64) -> Self {
+        Self {
+            projection: Projection3D::Orthographic { height },
+            ..Self::perspective(camera_position, look_at, 60f64)
+        }
+    }
+
+    pub fn up(self, up: Vector3D) -> Self {
+        Self { up, ..self }
+    }
+
+    pub fn clipping_planes(self, near: f64, far: f64) -> Self {
+        Self { near, far, ..self }
+    }
+
+    /// Returns the transformation into the space of the camera.
+    ///
+    /// In there the camera sits at the origin and looks along the negative z axis, with the
+    /// y axis pointing up and the x axis pointing right.
+    pub fn view_transformation(&self) -> Transformation3D {
+        let backward = self.look_at.vector_to(&self.camera_position).normalized();
+        let mut right = self.up.cross(&backward).normalized();
+        if right == Vector3D::zero() {
+            // looking along the up vector, any perpendicular direction will do
+            right = Vector3D::with(backward.y, backward.z, backward.x)
+                .cross(&backward)
+                .normalized();
+        }
+        let up = backward.cross(&right);
+        let eye = Position3D::zero().vector_to(&self.camera_position);
+        Transformation3D {
+            matrix: [
+                [right.x, right.y, right.z, -right.dot(&eye)],
+                [up.x, up.y, up.z, -up.dot(&eye)],
+                [backward.x, backward.y, backward.z, -backward.dot(&eye)],
+            ],
+        }
+    }
+
+    /// Returns the row major view matrix, which is `view_transformation` as 4x4 matrix.
+    pub fn view_matrix(&self) -> [[f64; 4]; 4] {
+        let matrix = self.view_transformation().matrix;
+        [matrix[0], matrix[1], matrix[2], [0f64, 0f64, 0f64, 1f64]]
+    }
+
+    /// Returns the row major projection matrix from the space of the camera into clip space.
+    ///
+    /// After dividing by `w`, the visible volume reaches from `-1` to `1` along every axis, with
+    /// the near plane at `z = -1` like in OpenGL.
+    pub fn projection_matrix(&self, aspect_ratio: f64) -> [[f64; 4]; 4] {
+        let (near, far) = (self.near, self.far);
+        let depth = far - near;
+        match self.projection {
+            Projection3D::Perspective { field_of_view } => {
+                let focal_length = 1f64 / (field_of_view.to_radians() / 2f64).tan();
+                [
+                    [focal_length / aspect_ratio, 0f64, 0f64, 0f64],
+                    [0f64, focal_length, 0f64, 0f64],
+                    [
+                        0f64,
+                        0f64,
+                        -(far + near) / depth,
+                        -2f64 * far * near / depth,
+                    ],
+                    [0f64, 0f64, -1f64, 0f64],
+                ]
+            }
+            Projection3D::Orthographic { height } => [
+                [2f64 / (height * aspect_ratio), 0f64, 0f64, 0f64],
+                [0f64, 2f64 / height, 0f64, 0f64],
+                [0f64, 0f64, -2f64 / depth, -(far + near) / depth],
+                [0f64, 0f64, 0f64, 1f64],
+            ],
+        }
+    }
+
+    /// Returns the row major matrix applying the view matrix first and the projection matrix
+    /// afterwards.
+    pub fn view_projection_matrix(&self, aspect_ratio: f64) -> [[f64; 4]; 4] {
+        let (projection, view) = (self.projection_matrix(aspect_ratio), self.view_matrix());
+        let mut matrix = [[0f64; 4]; 4];
+        for (row, matrix_row) in matrix.iter_mut().enumerate() {
+            for (column, value) in matrix_row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| projection[row][k] * view[k][column]).sum();
+            }
+        }
+        matrix
+    }
+
+    /// Returns the two dimensional space the image is projected into for the aspect ratio, to
+    /// be used with any `Viewport2DModification`.
+    pub fn viewport_2d(&self, aspect_ratio: f64) -> Viewport2D {
+        Viewport2D::with(Position2D::zero(), Size2D::with(2f64 * aspect_ratio, 2f64))
+            .flipped_y_axis(true)
+    }
+
+    /// Projects the position into the two dimensional space of `viewport_2d` and returns it
+    /// together with its distance in front of the camera.
+    ///
+    /// Returns `None` for positions in front of the near or behind the far plane.
+    pub fn project(&self, position: &Position3D) -> Option<(Position2D, f64)> {
+        let camera_position = self.view_transformation().apply(position);
+        let distance = -camera_position.z;
+        if distance < self.near || distance > self.far {
+            return None;
+        }
+        let scale = self.projection_scale(distance);
+        Some((
+            Position2D::with(camera_position.x * scale, camera_position.y * scale),
+            distance,
+        ))
+    }
+
+    /// Returns the factor scaling positions in the space of the camera at the given distance in
+    /// front of it into the space of `viewport_2d`.
+    pub(crate) fn projection_scale(&self, distance: f64) -> f64 {
+        match self.projection {
+            Projection3D::Perspective { field_of_view } => {
+                1f64 / ((field_of_view.to_radians() / 2f64).tan() * distance)
+            }
+            Projection3D::Orthographic { height } => 2f64 / height,
+        }
+    }
+}
 
 /* --- --- --- Viewport2DModification --- --- --- */
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Viewport2DModification {
     LooseAspectRatio,
     KeepAspectRatio,
@@ -1499,6 +2708,125 @@ impl Default for Viewport2DModification {
     }
 }
 
+impl Viewport2DModification {
+    /// Fits the viewport into a target area of the given size in pixels.
+    ///
+    /// Pixel coordinates have their origin in the upper left corner of the target area and the
+    /// y axis pointing down. `LooseAspectRatio` stretches the viewport onto the whole area, the
+    /// other modifications scale it uniformly and center it, so that it fits completely.
+    /// `KeepAspectRatioAndScissorRemains` additionally cuts off everything outside of it.
+    pub fn layout(&self, viewport: &Viewport2D, target_size: &Size2D) -> Viewport2DLayout {
+        let (mut scale_x, mut scale_y) = (
+            target_size.width / viewport.size.width,
+            target_size.height / viewport.size.height,
+        );
+        if *self != Self::LooseAspectRatio {
+            let scale = scale_x.abs().min(scale_y.abs());
+            scale_x = scale;
+            scale_y = scale;
+        }
+        let scissor = if *self == Self::KeepAspectRatioAndScissorRemains {
+            let scissor_size = Size2D::with(
+                (viewport.size.width * scale_x).abs(),
+                (viewport.size.height * scale_y).abs(),
+            );
+            Some((
+                Position2D::with(
+                    (target_size.width - scissor_size.width) / 2f64,
+                    (target_size.height - scissor_size.height) / 2f64,
+                ),
+                scissor_size,
+            ))
+        } else {
+            None
+        };
+        let visible_size = match scissor {
+            Some(_) => viewport.size,
+            None => Size2D::with(
+                (target_size.width / scale_x).abs(),
+                (target_size.height / scale_y).abs(),
+            ),
+        };
+        if viewport.flipped_x_axis {
+            scale_x = -scale_x;
+        }
+        if viewport.flipped_y_axis {
+            scale_y = -scale_y;
+        }
+        Viewport2DLayout {
+            scale: (scale_x, scale_y),
+            offset: Position2D::with(
+                target_size.width / 2f64 - viewport.center.x * scale_x,
+                target_size.height / 2f64 - viewport.center.y * scale_y,
+            ),
+            visible_area: Viewport2D {
+                size: visible_size,
+                ..*viewport
+            },
+            scissor,
+        }
+    }
+}
+
+/* --- --- --- Viewport2DLayout --- --- --- */
+
+/// A `Viewport2D` fitted into a target area of pixels by `Viewport2DModification::layout`.
+///
+/// A world position is shown at the pixel position `offset + world * scale`, calculated
+/// separately for both axes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport2DLayout {
+    /// Pixels per world unit along both axes, negative for flipped axes.
+    pub scale: (f64, f64),
+    /// Pixel position of the world origin.
+    pub offset: Position2D,
+    /// The world rectangle shown in the target area, or inside of the scissor rectangle if
+    /// there is one.
+    pub visible_area: Viewport2D,
+    /// Top left corner and size of the pixel rectangle outside of which nothing may be drawn.
+    pub scissor: Option<(Position2D, Size2D)>,
+}
+
+impl Viewport2DLayout {
+    pub fn world_to_pixel(&self, world_position: &Position2D) -> Position2D {
+        Position2D::with(
+            self.offset.x + world_position.x * self.scale.0,
+            self.offset.y + world_position.y * self.scale.1,
+        )
+    }
+
+    /// Returns `None` if the target area or the viewport has no area.
+    pub fn pixel_to_world(&self, pixel_position: &Position2D) -> Option<Position2D> {
+        if !self.scale.0.is_normal() || !self.scale.1.is_normal() {
+            return None;
+        }
+        Some(Position2D::with(
+            (pixel_position.x - self.offset.x) / self.scale.0,
+            (pixel_position.y - self.offset.y) / self.scale.1,
+        ))
+    }
+
+    /// Returns whether anything may be drawn at the pixel position of a target area with the
+    /// given size, which is everywhere inside of it and the scissor rectangle.
+    pub fn is_visible(&self, pixel_position: &Position2D, target_size: &Size2D) -> bool {
+        let (top_left, size) = self.scissor.unwrap_or((Position2D::zero(), *target_size));
+        (top_left.x..=top_left.x + size.width).contains(&pixel_position.x)
+            && (top_left.y..=top_left.y + size.height).contains(&pixel_position.y)
+    }
+
+    /// Returns the transformation from world into pixel coordinates, e.g. to append it to
+    /// geometries.
+    pub fn transformation(&self) -> Transformation2D {
+        Transformation2D::composition(
+            "Viewport2DLayout".to_string(),
+            vec![
+                Transformation2D::scale(self.scale.0, self.scale.1),
+                Transformation2D::translation(Position2D::zero().vector_to(&self.offset)),
+            ],
+        )
+    }
+}
+
 /* --- --- --- InputProvider --- --- --- */
 
 /// Trait to use for the InputAgent or any other Agent.
@@ -1705,9 +3033,21 @@ pub trait TwoDimensionalDrawableEnvironment<EnvironmentError: Error>: DrawableEn
 pub trait ThreeDimensionalDrawableEnvironment<EnvironmentError: Error>:
     DrawableEnvironment
 {
+    /// Returns the three dimensional geometries and the two dimensional ones drawn on top of
+    /// them in the space of `Viewport3D::viewport_2d`.
     fn draw_three_dimensional(
         &self,
     ) -> Result<(Vec<Geometry3D>, Vec<Geometry2D>), EnvironmentError>;
+
+    /// Returns the camera to show the geometries with, `None` leaves it to the visualiser.
+    fn preferred_view_3d(&self) -> Option<Viewport3D> {
+        None
+    }
+
+    /// Returns the color to clear the frame with, `None` leaves it to the visualiser.
+    fn preferred_background_color_3d(&self) -> Option<Color> {
+        None
+    }
 }
 
 /* --- --- --- RgbArrayDrawableEnvironment --- --- --- */
@@ -1723,3 +3063,304 @@ pub trait PixelArrayDrawableEnvironment<EnvironmentError: Error>: DrawableEnviro
 pub trait TextDrawableEnvironment<EnvironmentError: Error>: DrawableEnvironment {
     fn draw_text(&self) -> Result<String, EnvironmentError>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn assert_positions_close(actual: &Position2D, expected: &Position2D) {
+        assert_close(actual.x, expected.x);
+        assert_close(actual.y, expected.y);
+    }
+
+    /* --- --- --- Geometry2D::contains --- --- --- */
+
+    #[test]
+    fn point_contains_its_pixel() {
+        let point = Geometry2D::point(Position2D::with(1.0, 2.0));
+        assert!(point.contains(&Position2D::with(1.0, 2.0)));
+        assert!(point.contains(&Position2D::with(1.4, 1.6)));
+        assert!(!point.contains(&Position2D::with(1.6, 2.0)));
+        assert!(!point.contains(&Position2D::with(1.0, 2.6)));
+
+        let moved =
+            point.append_transformation(Transformation2D::translation(Vector2D::with(0.1, 0.2)));
+        assert!(moved.contains(&Position2D::with(1.1 + 0.45, 2.2 - 0.45)));
+        assert!(!moved.contains(&Position2D::with(1.1 - 0.55, 2.2)));
+    }
+
+    #[test]
+    fn shape_contains_fill_and_border() {
+        let filled = Geometry2D::rectangle(Position2D::zero(), Size2D::with(4.0, 2.0));
+        assert!(filled.contains(&Position2D::with(1.5, 0.5)));
+        assert!(!filled.contains(&Position2D::with(2.2, 0.0)));
+
+        let bordered = filled
+            .clone()
+            .fill_color(Color::transparent())
+            .line_or_border_color(Color::white())
+            .line_or_border_width(1.0);
+        assert!(!bordered.contains(&Position2D::zero()));
+        assert!(bordered.contains(&Position2D::with(1.6, 0.0)));
+        assert!(bordered.contains(&Position2D::with(2.4, 0.0)));
+        assert!(!bordered.contains(&Position2D::with(2.6, 0.0)));
+        // Borders have square joins.
+        assert!(bordered.contains(&Position2D::with(2.45, 1.45)));
+
+        let invisible = bordered.line_or_border_color(Color::transparent());
+        assert!(!invisible.contains(&Position2D::with(2.0, 0.0)));
+
+        let circle = Geometry2D::circle(Position2D::with(1.0, 1.0), 2.0)
+            .line_or_border_color(Color::black())
+            .line_or_border_width(1.0);
+        assert!(circle.contains(&Position2D::with(1.0, 1.0)));
+        assert!(circle.contains(&Position2D::with(3.4, 1.0)));
+        assert!(!circle.contains(&Position2D::with(3.6, 1.0)));
+        let ring = circle.fill_color(Color::transparent());
+        assert!(!ring.contains(&Position2D::with(1.0, 1.0)));
+        assert!(ring.contains(&Position2D::with(1.0, -0.6)));
+    }
+
+    #[test]
+    fn line_contains_its_width() {
+        let line = Geometry2D::line(Position2D::zero(), Position2D::with(10.0, 0.0))
+            .line_or_border_width(2.0)
+            .line_shape(LineShape::Bevel);
+        assert!(line.contains(&Position2D::with(5.0, 0.9)));
+        assert!(line.contains(&Position2D::with(5.0, -0.9)));
+        assert!(!line.contains(&Position2D::with(5.0, 1.1)));
+        assert!(!line.contains(&Position2D::with(10.5, 0.0)));
+
+        let wide = line.clone().line_or_border_width(4.0);
+        assert!(wide.contains(&Position2D::with(5.0, 1.9)));
+        assert!(!wide.contains(&Position2D::with(5.0, 2.1)));
+
+        let round = line.clone().line_shape(LineShape::Round);
+        assert!(round.contains(&Position2D::with(10.6, 0.6)));
+        assert!(!round.contains(&Position2D::with(10.8, 0.8)));
+
+        let polyline = Geometry2D::polyline(vec![
+            Position2D::zero(),
+            Position2D::with(4.0, 0.0),
+            Position2D::with(4.0, 4.0),
+        ]);
+        assert!(polyline.contains(&Position2D::with(4.2, 2.0)));
+        assert!(!polyline.contains(&Position2D::with(2.0, 2.0)));
+
+        let invisible = line.line_or_border_color(Color::transparent());
+        assert!(!invisible.contains(&Position2D::with(5.0, 0.0)));
+    }
+
+    #[test]
+    fn transformed_geometry_contains() {
+        let rectangle = Geometry2D::rectangle(Position2D::zero(), Size2D::with(4.0, 2.0));
+
+        let moved = rectangle
+            .clone()
+            .append_transformation(Transformation2D::translation(Vector2D::with(10.0, 0.0)));
+        assert!(moved.contains(&Position2D::with(11.5, 0.5)));
+        assert!(!moved.contains(&Position2D::zero()));
+
+        let rotated = rectangle
+            .clone()
+            .append_transformation(Transformation2D::rotation(90.0));
+        assert!(rotated.contains(&Position2D::with(0.0, 1.5)));
+        assert!(!rotated.contains(&Position2D::with(1.5, 0.0)));
+
+        let scaled = rectangle.append_transformation(Transformation2D::scale(2.0, 0.5));
+        assert!(scaled.contains(&Position2D::with(3.5, 0.0)));
+        assert!(!scaled.contains(&Position2D::with(0.0, 0.75)));
+
+        let collapsed = Geometry2D::circle(Position2D::zero(), 1.0)
+            .append_transformation(Transformation2D::scale(0.0, 1.0));
+        assert!(!collapsed.contains(&Position2D::zero()));
+    }
+
+    #[test]
+    fn text_image_and_group_contain_their_box() {
+        let text = Geometry2D::text(Position2D::zero(), "Ab".to_string(), 10.0);
+        let Size2D { width, height } = font::measure_text("Ab", 10.0);
+        assert!(text.contains(&Position2D::with(width - 0.1, height - 0.1)));
+        assert!(!text.contains(&Position2D::with(width + 0.1, 0.1)));
+        assert!(!text.contains(&Position2D::with(0.1, -0.1)));
+
+        let image = Geometry2D::image(
+            Position2D::zero(),
+            Size2D::with(2.0, 2.0),
+            TextureSource::bytes(vec![0, 0, 0, 0], 1, 1),
+        );
+        assert!(image.contains(&Position2D::with(0.9, -0.9)));
+        assert!(!image.contains(&Position2D::with(1.1, 0.0)));
+
+        let group = Geometry2D::group(vec![
+            image,
+            Geometry2D::group(vec![Geometry2D::point(Position2D::with(5.0, 5.0))]),
+        ]);
+        assert!(group.contains(&Position2D::zero()));
+        assert!(group.contains(&Position2D::with(5.0, 5.0)));
+        assert!(!group.contains(&Position2D::with(3.0, 3.0)));
+    }
+
+    /* --- --- --- Viewport2DLayout --- --- --- */
+
+    fn viewport() -> Viewport2D {
+        Viewport2D::with(Position2D::with(2.0, 1.0), Size2D::with(4.0, 2.0))
+    }
+
+    const WIDE: Size2D = Size2D {
+        width: 400.0,
+        height: 100.0,
+    };
+    const TALL: Size2D = Size2D {
+        width: 100.0,
+        height: 400.0,
+    };
+
+    fn assert_layout(
+        layout: &Viewport2DLayout,
+        scale: (f64, f64),
+        offset: (f64, f64),
+        visible_size: (f64, f64),
+        scissor: Option<((f64, f64), (f64, f64))>,
+    ) {
+        assert_close(layout.scale.0, scale.0);
+        assert_close(layout.scale.1, scale.1);
+        assert_positions_close(&layout.offset, &Position2D::with(offset.0, offset.1));
+        assert_eq!(layout.visible_area.center, viewport().center);
+        assert_close(layout.visible_area.size.width, visible_size.0);
+        assert_close(layout.visible_area.size.height, visible_size.1);
+        match (layout.scissor, scissor) {
+            (None, None) => {}
+            (Some((top_left, size)), Some((expected_top_left, expected_size))) => {
+                assert_positions_close(
+                    &top_left,
+                    &Position2D::with(expected_top_left.0, expected_top_left.1),
+                );
+                assert_close(size.width, expected_size.0);
+                assert_close(size.height, expected_size.1);
+            }
+            (actual, expected) => panic!("expected scissor {:?}, got {:?}", expected, actual),
+        }
+    }
+
+    #[test]
+    fn loose_aspect_ratio_layout() {
+        let modification = Viewport2DModification::LooseAspectRatio;
+        let wide = modification.layout(&viewport(), &WIDE);
+        assert_layout(&wide, (100.0, 50.0), (0.0, 0.0), (4.0, 2.0), None);
+        let tall = modification.layout(&viewport(), &TALL);
+        assert_layout(&tall, (25.0, 200.0), (0.0, 0.0), (4.0, 2.0), None);
+    }
+
+    #[test]
+    fn keep_aspect_ratio_layout() {
+        let modification = Viewport2DModification::KeepAspectRatio;
+        let wide = modification.layout(&viewport(), &WIDE);
+        assert_layout(&wide, (50.0, 50.0), (100.0, 0.0), (8.0, 2.0), None);
+        let tall = modification.layout(&viewport(), &TALL);
+        assert_layout(&tall, (25.0, 25.0), (0.0, 175.0), (4.0, 16.0), None);
+    }
+
+    #[test]
+    fn keep_aspect_ratio_and_scissor_remains_layout() {
+        let modification = Viewport2DModification::KeepAspectRatioAndScissorRemains;
+        let wide = modification.layout(&viewport(), &WIDE);
+        assert_layout(
+            &wide,
+            (50.0, 50.0),
+            (100.0, 0.0),
+            (4.0, 2.0),
+            Some(((100.0, 0.0), (200.0, 100.0))),
+        );
+        assert!(wide.is_visible(&Position2D::with(150.0, 50.0), &WIDE));
+        assert!(!wide.is_visible(&Position2D::with(50.0, 50.0), &WIDE));
+        let tall = modification.layout(&viewport(), &TALL);
+        assert_layout(
+            &tall,
+            (25.0, 25.0),
+            (0.0, 175.0),
+            (4.0, 2.0),
+            Some(((0.0, 175.0), (100.0, 50.0))),
+        );
+    }
+
+    #[test]
+    fn flipped_axes_layout() {
+        let modification = Viewport2DModification::KeepAspectRatio;
+        let flipped_y = modification.layout(&viewport().flipped_y_axis(true), &WIDE);
+        assert_layout(&flipped_y, (50.0, -50.0), (100.0, 100.0), (8.0, 2.0), None);
+        // The world origin is the lower left corner of the viewport.
+        assert_positions_close(
+            &flipped_y.world_to_pixel(&Position2D::zero()),
+            &Position2D::with(100.0, 100.0),
+        );
+
+        let flipped_x = modification.layout(&viewport().flipped_x_axis(true), &WIDE);
+        assert_layout(&flipped_x, (-50.0, 50.0), (300.0, 0.0), (8.0, 2.0), None);
+        assert_positions_close(
+            &flipped_x.world_to_pixel(&Position2D::zero()),
+            &Position2D::with(300.0, 0.0),
+        );
+
+        let flipped_both = Viewport2DModification::LooseAspectRatio
+            .layout(&viewport().flipped_x_axis(true).flipped_y_axis(true), &TALL);
+        assert_layout(
+            &flipped_both,
+            (-25.0, -200.0),
+            (100.0, 400.0),
+            (4.0, 2.0),
+            None,
+        );
+        // The center of the viewport stays in the center of the target area.
+        assert_positions_close(
+            &flipped_both.world_to_pixel(&viewport().center),
+            &Position2D::with(50.0, 200.0),
+        );
+    }
+
+    #[test]
+    fn pixel_to_world_reverses_world_to_pixel() {
+        let positions = [
+            Position2D::zero(),
+            Position2D::with(2.0, 1.0),
+            Position2D::with(-3.25, 7.5),
+            Position2D::with(1e3, -1e-3),
+        ];
+        for modification in &[
+            Viewport2DModification::LooseAspectRatio,
+            Viewport2DModification::KeepAspectRatio,
+            Viewport2DModification::KeepAspectRatioAndScissorRemains,
+        ] {
+            for target_size in &[WIDE, TALL] {
+                for &(flipped_x, flipped_y) in
+                    &[(false, false), (true, false), (false, true), (true, true)]
+                {
+                    let viewport = viewport()
+                        .flipped_x_axis(flipped_x)
+                        .flipped_y_axis(flipped_y);
+                    let layout = modification.layout(&viewport, target_size);
+                    for position in &positions {
+                        let pixel = layout.world_to_pixel(position);
+                        assert_positions_close(&layout.pixel_to_world(&pixel).unwrap(), position);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pixel_to_world_without_area() {
+        let empty =
+            Viewport2DModification::KeepAspectRatio.layout(&viewport(), &Size2D::with(0.0, 100.0));
+        assert_eq!(empty.pixel_to_world(&Position2D::zero()), None);
+    }
+}
@@ -0,0 +1,495 @@
+//! Positions, vectors and transformations inside the three dimensional space.
+//!
+//! The space is right-handed. Rotations are given in degrees and turn counterclockwise when
+//! looking from the positive end of their axis towards the origin.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+/* --- --- --- Position3D --- --- --- */
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Position3D {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Position3D {
+    pub fn with(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn zero() -> Self {
+        Self::with(0f64, 0f64, 0f64)
+    }
+
+    pub fn vector_to(&self, other: &Position3D) -> Vector3D {
+        Vector3D::with(other.x - self.x, other.y - self.y, other.z - self.z)
+    }
+
+    pub fn transform(&self, transformations: &Transformations3D) -> Self {
+        transformations.combined().apply(self)
+    }
+}
+
+impl Add<Vector3D> for Position3D {
+    type Output = Position3D;
+
+    fn add(self, vector: Vector3D) -> Self::Output {
+        Position3D::with(self.x + vector.x, self.y + vector.y, self.z + vector.z)
+    }
+}
+
+impl Sub<Vector3D> for Position3D {
+    type Output = Position3D;
+
+    fn sub(self, vector: Vector3D) -> Self::Output {
+        Position3D::with(self.x - vector.x, self.y - vector.y, self.z - vector.z)
+    }
+}
+
+/* --- --- --- Vector3D --- --- --- */
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vector3D {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector3D {
+    pub fn with(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn zero() -> Self {
+        Self::with(0f64, 0f64, 0f64)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// Returns the vector with length one, or the zero vector for the zero vector.
+    pub fn normalized(&self) -> Self {
+        let length = self.length();
+        if length > 0f64 {
+            *self * (1f64 / length)
+        } else {
+            Self::zero()
+        }
+    }
+
+    pub fn dot(&self, other: &Vector3D) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: &Vector3D) -> Self {
+        Self::with(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+}
+
+impl Add for Vector3D {
+    type Output = Vector3D;
+
+    fn add(self, other: Vector3D) -> Self::Output {
+        Vector3D::with(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vector3D {
+    type Output = Vector3D;
+
+    fn sub(self, other: Vector3D) -> Self::Output {
+        Vector3D::with(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f64> for Vector3D {
+    type Output = Vector3D;
+
+    fn mul(self, factor: f64) -> Self::Output {
+        Vector3D::with(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl Neg for Vector3D {
+    type Output = Vector3D;
+
+    fn neg(self) -> Self::Output {
+        Vector3D::with(-self.x, -self.y, -self.z)
+    }
+}
+
+/* --- --- --- Size3D --- --- --- */
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Size3D {
+    pub width: f64,
+    pub height: f64,
+    pub depth: f64,
+}
+
+impl Size3D {
+    pub fn with(width: f64, height: f64, depth: f64) -> Self {
+        Self {
+            width,
+            height,
+            depth,
+        }
+    }
+}
+
+/* --- --- --- Transformation3D --- --- --- */
+
+/// An affine transformation given as the upper three rows of a 4x4 matrix.
+///
+/// `x' = m[0][0] * x + m[0][1] * y + m[0][2] * z + m[0][3]` and the same for `y'` and `z'`
+/// with the second and third row.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transformation3D {
+    pub matrix: [[f64; 4]; 3],
+}
+
+impl Transformation3D {
+    pub fn identity() -> Self {
+        Self {
+            matrix: [
+                [1f64, 0f64, 0f64, 0f64],
+                [0f64, 1f64, 0f64, 0f64],
+                [0f64, 0f64, 1f64, 0f64],
+            ],
+        }
+    }
+
+    pub fn translation(distance: Vector3D) -> Self {
+        Self {
+            matrix: [
+                [1f64, 0f64, 0f64, distance.x],
+                [0f64, 1f64, 0f64, distance.y],
+                [0f64, 0f64, 1f64, distance.z],
+            ],
+        }
+    }
+
+    pub fn scale(x: f64, y: f64, z: f64) -> Self {
+        Self {
+            matrix: [
+                [x, 0f64, 0f64, 0f64],
+                [0f64, y, 0f64, 0f64],
+                [0f64, 0f64, z, 0f64],
+            ],
+        }
+    }
+
+    pub fn rotation_x(degree: f64) -> Self {
+        Self::rotation(&Vector3D::with(1f64, 0f64, 0f64), degree)
+    }
+
+    pub fn rotation_y(degree: f64) -> Self {
+        Self::rotation(&Vector3D::with(0f64, 1f64, 0f64), degree)
+    }
+
+    pub fn rotation_z(degree: f64) -> Self {
+        Self::rotation(&Vector3D::with(0f64, 0f64, 1f64), degree)
+    }
+
+    /// Rotates around the axis through the origin, a zero axis results in no rotation.
+    pub fn rotation(axis: &Vector3D, degree: f64) -> Self {
+        let axis = axis.normalized();
+        if axis == Vector3D::zero() {
+            return Self::identity();
+        }
+        let (sin, cos) = degree.to_radians().sin_cos();
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        let t = 1f64 - cos;
+        Self {
+            matrix: [
+                [
+                    t * x * x + cos,
+                    t * x * y - sin * z,
+                    t * x * z + sin * y,
+                    0f64,
+                ],
+                [
+                    t * x * y + sin * z,
+                    t * y * y + cos,
+                    t * y * z - sin * x,
+                    0f64,
+                ],
+                [
+                    t * x * z - sin * y,
+                    t * y * z + sin * x,
+                    t * z * z + cos,
+                    0f64,
+                ],
+            ],
+        }
+    }
+
+    /// Rotates around the axis through the given position.
+    pub fn rotation_around_position(position: &Position3D, axis: &Vector3D, degree: f64) -> Self {
+        let to_origin = position.vector_to(&Position3D::zero());
+        Self::translation(to_origin)
+            .then(&Self::rotation(axis, degree))
+            .then(&Self::translation(-to_origin))
+    }
+
+    /// Returns the transformation applying all given transformations in order.
+    pub fn composition(transformations: &[Transformation3D]) -> Self {
+        transformations
+            .iter()
+            .fold(Self::identity(), |combined, next| combined.then(next))
+    }
+
+    /// Returns the transformation applying `self` first and `next` afterwards.
+    pub fn then(&self, next: &Transformation3D) -> Self {
+        let (a, b) = (&self.matrix, &next.matrix);
+        let mut matrix = [[0f64; 4]; 3];
+        for (row, matrix_row) in matrix.iter_mut().enumerate() {
+            for (column, value) in matrix_row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| b[row][k] * a[k][column]).sum::<f64>();
+            }
+            matrix_row[3] += b[row][3];
+        }
+        Self { matrix }
+    }
+
+    pub fn apply(&self, position: &Position3D) -> Position3D {
+        let m = &self.matrix;
+        Position3D::with(
+            m[0][0] * position.x + m[0][1] * position.y + m[0][2] * position.z + m[0][3],
+            m[1][0] * position.x + m[1][1] * position.y + m[1][2] * position.z + m[1][3],
+            m[2][0] * position.x + m[2][1] * position.y + m[2][2] * position.z + m[2][3],
+        )
+    }
+
+    /// Transforms a direction, which ignores the translation.
+    pub fn apply_to_vector(&self, vector: &Vector3D) -> Vector3D {
+        let m = &self.matrix;
+        Vector3D::with(
+            m[0][0] * vector.x + m[0][1] * vector.y + m[0][2] * vector.z,
+            m[1][0] * vector.x + m[1][1] * vector.y + m[1][2] * vector.z,
+            m[2][0] * vector.x + m[2][1] * vector.y + m[2][2] * vector.z,
+        )
+    }
+
+    /// Transforms a surface normal, which stays perpendicular to the transformed surface.
+    ///
+    /// The result is normalized, non-invertible transformations return the zero vector.
+    pub fn apply_to_normal(&self, normal: &Vector3D) -> Vector3D {
+        match self.inverse() {
+            Some(inverse) => {
+                let m = &inverse.matrix;
+                Vector3D::with(
+                    m[0][0] * normal.x + m[1][0] * normal.y + m[2][0] * normal.z,
+                    m[0][1] * normal.x + m[1][1] * normal.y + m[2][1] * normal.z,
+                    m[0][2] * normal.x + m[1][2] * normal.y + m[2][2] * normal.z,
+                )
+                .normalized()
+            }
+            None => Vector3D::zero(),
+        }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let m = &self.matrix;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant.abs() < f64::EPSILON {
+            return None;
+        }
+        let m = &self.matrix;
+        let mut linear = [[0f64; 3]; 3];
+        for (row, linear_row) in linear.iter_mut().enumerate() {
+            for (column, value) in linear_row.iter_mut().enumerate() {
+                // the transposed cofactor matrix divided by the determinant
+                let (r0, r1) = ((column + 1) % 3, (column + 2) % 3);
+                let (c0, c1) = ((row + 1) % 3, (row + 2) % 3);
+                *value = (m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]) / determinant;
+            }
+        }
+        let mut matrix = [[0f64; 4]; 3];
+        for row in 0..3 {
+            matrix[row][..3].copy_from_slice(&linear[row]);
+            matrix[row][3] = -(0..3).map(|k| linear[row][k] * m[k][3]).sum::<f64>();
+        }
+        Some(Self { matrix })
+    }
+}
+
+impl Default for Transformation3D {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/* --- --- --- Transformations3D --- --- --- */
+
+/// Transformations applied one after another, starting with the first.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transformations3D {
+    pub transformations: Vec<Transformation3D>,
+}
+
+impl Transformations3D {
+    pub fn with(transformations: Vec<Transformation3D>) -> Self {
+        Self { transformations }
+    }
+
+    /// Returns one transformation doing the same as all of them in order.
+    pub fn combined(&self) -> Transformation3D {
+        Transformation3D::composition(&self.transformations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_transformations_close(actual: &Transformation3D, expected: &Transformation3D) {
+        for (actual_row, expected_row) in actual.matrix.iter().zip(&expected.matrix) {
+            for (actual, expected) in actual_row.iter().zip(expected_row) {
+                assert!(
+                    (actual - expected).abs() < 1e-9,
+                    "expected {:?}, got {:?}",
+                    expected,
+                    actual
+                );
+            }
+        }
+    }
+
+    fn assert_vectors_close(actual: &Vector3D, expected: &Vector3D) {
+        assert!(
+            (*actual - *expected).length() < 1e-9,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    fn transformations() -> Vec<Transformation3D> {
+        vec![
+            Transformation3D::identity(),
+            Transformation3D::translation(Vector3D::with(1f64, -2f64, 3f64)),
+            Transformation3D::scale(2f64, 0.5f64, -4f64),
+            Transformation3D::rotation_x(30f64),
+            Transformation3D::rotation(&Vector3D::with(1f64, 2f64, -3f64), 275f64),
+            Transformation3D::rotation_around_position(
+                &Position3D::with(4f64, 5f64, 6f64),
+                &Vector3D::with(0f64, 1f64, 1f64),
+                -45f64,
+            ),
+            Transformation3D::composition(&[
+                Transformation3D::scale(3f64, 1f64, 1f64),
+                Transformation3D::rotation_z(60f64),
+                Transformation3D::translation(Vector3D::with(0f64, 7f64, 0f64)),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn inverses_undo_transformations() {
+        for transformation in transformations() {
+            let inverse = transformation.inverse().unwrap();
+            assert_transformations_close(
+                &transformation.then(&inverse),
+                &Transformation3D::identity(),
+            );
+            assert_transformations_close(
+                &inverse.then(&transformation),
+                &Transformation3D::identity(),
+            );
+        }
+        assert_eq!(Transformation3D::scale(1f64, 0f64, 1f64).inverse(), None);
+        assert_eq!(
+            Transformation3D::scale(1f64, 0f64, 1f64)
+                .apply_to_normal(&Vector3D::with(0f64, 1f64, 0f64)),
+            Vector3D::zero()
+        );
+    }
+
+    #[test]
+    fn rotations_are_right_handed() {
+        let (x, y, z) = (
+            Vector3D::with(1f64, 0f64, 0f64),
+            Vector3D::with(0f64, 1f64, 0f64),
+            Vector3D::with(0f64, 0f64, 1f64),
+        );
+        // a quarter turn counterclockwise, looking from the tip of the axis to the origin
+        let rotate = |axis: &Vector3D, vector: &Vector3D| {
+            Transformation3D::rotation(axis, 90f64).apply_to_vector(vector)
+        };
+        assert_vectors_close(&rotate(&z, &x), &y);
+        assert_vectors_close(&rotate(&x, &y), &z);
+        assert_vectors_close(&rotate(&y, &z), &x);
+        assert_vectors_close(&Transformation3D::rotation_z(90f64).apply_to_vector(&x), &y);
+        assert_vectors_close(&Transformation3D::rotation_x(90f64).apply_to_vector(&y), &z);
+        assert_vectors_close(&Transformation3D::rotation_y(90f64).apply_to_vector(&z), &x);
+        // a third turn around the diagonal cycles the axes
+        let diagonal = Transformation3D::rotation(&Vector3D::with(1f64, 1f64, 1f64), 120f64);
+        assert_vectors_close(&diagonal.apply_to_vector(&x), &y);
+        assert_vectors_close(&diagonal.apply_to_vector(&y), &z);
+        assert_vectors_close(&diagonal.apply_to_vector(&z), &x);
+        assert_eq!(
+            Transformation3D::rotation(&Vector3D::zero(), 90f64),
+            Transformation3D::identity()
+        );
+    }
+
+    #[test]
+    fn transformations_are_applied_in_order() {
+        let position = Position3D::with(1f64, 2f64, 3f64);
+        let moved_then_scaled = Transformation3D::translation(Vector3D::with(1f64, 0f64, 0f64))
+            .then(&Transformation3D::scale(2f64, 2f64, 2f64));
+        assert_eq!(
+            moved_then_scaled.apply(&position),
+            Position3D::with(4f64, 4f64, 6f64)
+        );
+        assert_eq!(
+            position.transform(&Transformations3D::with(vec![
+                Transformation3D::scale(2f64, 2f64, 2f64),
+                Transformation3D::translation(Vector3D::with(1f64, 0f64, 0f64)),
+            ])),
+            Position3D::with(3f64, 4f64, 6f64)
+        );
+        let center = Position3D::with(4f64, 5f64, 6f64);
+        let around_center = Transformation3D::rotation_around_position(
+            &center,
+            &Vector3D::with(0f64, 0f64, 1f64),
+            90f64,
+        );
+        assert_vectors_close(
+            &Position3D::zero().vector_to(&around_center.apply(&center)),
+            &Position3D::zero().vector_to(&center),
+        );
+        assert_vectors_close(
+            &center.vector_to(&around_center.apply(&Position3D::with(5f64, 5f64, 6f64))),
+            &Vector3D::with(0f64, 1f64, 0f64),
+        );
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_to_scaled_surfaces() {
+        let scale = Transformation3D::scale(4f64, 1f64, 1f64);
+        let (along, across) = (
+            Vector3D::with(1f64, -1f64, 0f64),
+            Vector3D::with(0f64, 0f64, 1f64),
+        );
+        let normal = along.cross(&across);
+        let transformed = scale.apply_to_normal(&normal);
+        assert!(transformed.dot(&scale.apply_to_vector(&along)).abs() < 1e-9);
+        assert!(transformed.dot(&scale.apply_to_vector(&across)).abs() < 1e-9);
+        assert!((transformed.length() - 1f64).abs() < 1e-9);
+    }
+}
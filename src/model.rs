@@ -0,0 +1,1026 @@
+//! Loading three dimensional models from Wavefront OBJ files and saving them as OBJ or PLY
+//! files.
+//!
+//! Faces with more than three points are split into triangle fans, texture coordinates, curves
+//! and any other statements besides positions, normals, faces and materials are ignored. Of
+//! the MTL materials the diffuse color `Kd`, the opacity `d` or `Tr`, the specular color `Ks`
+//! and the specular exponent `Ns` are used, colors only in their RGB form.
+//!
+//! Parsing large models takes a while, so environments drawing the same model in every frame
+//! should load it only once, e.g. with a `ModelCache`.
+//!
+//! Saved models hold the geometries with all their transformations applied, so they can be
+//! inspected in external modelling tools exactly as they were drawn. Curved surfaces are saved
+//! as the triangles the built-in renderers draw.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::math3d::{Position3D, Vector3D};
+use crate::tessellation::{primitives_3d, Primitives3D};
+use crate::{Color, Geometry3D, Material};
+
+/* --- --- --- ModelFormat --- --- --- */
+
+/// The supported formats for saving models.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ModelFormat {
+    /// Wavefront OBJ, the colors are written into an accompanying MTL file.
+    Obj,
+    /// ASCII polygon file format with colors and normals at every vertex.
+    Ply,
+}
+
+impl ModelFormat {
+    /// Determines the format from the extension of the given path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "obj" => Some(Self::Obj),
+            "ply" => Some(Self::Ply),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Obj => "obj",
+            Self::Ply => "ply",
+        }
+    }
+}
+
+/* --- --- --- ModelError --- --- --- */
+
+#[derive(Debug)]
+pub enum ModelError {
+    Io(std::io::Error),
+    /// The data is not a valid model of the expected format.
+    Malformed(String),
+    /// The model or format is valid, but not supported.
+    Unsupported(String),
+}
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not read or write model: {}", error),
+            Self::Malformed(reason) => write!(f, "Malformed model: {}", reason),
+            Self::Unsupported(reason) => write!(f, "Unsupported model: {}", reason),
+        }
+    }
+}
+
+impl Error for ModelError {}
+
+impl From<std::io::Error> for ModelError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/* --- --- --- Files --- --- --- */
+
+/// Reads an OBJ file together with the MTL files it references, which are looked up relative
+/// to the directory of the OBJ file.
+///
+/// MTL files which are missing or cannot be read are skipped, so the faces using their
+/// materials get the default material. See `read_obj` for the returned geometry.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Geometry3D, ModelError> {
+    let model = parse_obj(&read_to_string(&mut File::open(&path)?)?)?;
+    let directory = path.as_ref().parent().unwrap_or_else(|| Path::new(""));
+    let mut materials = HashMap::new();
+    for library in &model.material_libraries {
+        match File::open(directory.join(library))
+            .map_err(ModelError::from)
+            .and_then(|mut file| read_mtl(&mut file))
+        {
+            Ok(library_materials) => materials.extend(library_materials),
+            Err(ModelError::Io(_)) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(model.into_geometry(&materials))
+}
+
+/// Writes the geometries into a file with the format chosen by the extension of the path.
+///
+/// OBJ files get an MTL file with the same name next to them.
+pub fn save<P: AsRef<Path>>(geometries: &[Geometry3D], path: P) -> Result<(), ModelError> {
+    let path = path.as_ref();
+    let format = ModelFormat::from_path(path).ok_or_else(|| {
+        ModelError::Unsupported(format!(
+            "no model format for extension of {}",
+            path.display()
+        ))
+    })?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        ModelFormat::Obj => {
+            let material_library = path.with_extension("mtl");
+            let mut material_writer = BufWriter::new(File::create(&material_library)?);
+            write_obj(
+                geometries,
+                material_library
+                    .file_name()
+                    .and_then(|file_name| file_name.to_str()),
+                &mut writer,
+            )?;
+            write_mtl(geometries, &mut material_writer)?;
+            material_writer.flush()?;
+        }
+        ModelFormat::Ply => write_ply(geometries, &mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes every frame into its own file by replacing `{}` in the path with the index of the
+/// frame, padded with zeros to the same length for all frames.
+///
+/// A path without `{}` is only accepted for a single frame.
+pub fn save_sequence(frames: &[Vec<Geometry3D>], path: &str) -> Result<(), ModelError> {
+    if frames.len() > 1 && !path.contains("{}") {
+        return Err(ModelError::Unsupported(format!(
+            "{} frames, but {} holds a single frame",
+            frames.len(),
+            path
+        )));
+    }
+    let index_width = frames.len().saturating_sub(1).to_string().len();
+    for (index, geometries) in frames.iter().enumerate() {
+        save(
+            geometries,
+            path.replace("{}", &format!("{:0width$}", index, width = index_width)),
+        )?;
+    }
+    Ok(())
+}
+
+/* --- --- --- Streams --- --- --- */
+
+pub fn write<W: Write>(
+    geometries: &[Geometry3D],
+    format: ModelFormat,
+    writer: &mut W,
+) -> Result<(), ModelError> {
+    match format {
+        ModelFormat::Obj => write_obj(geometries, None, writer),
+        ModelFormat::Ply => write_ply(geometries, writer),
+    }
+}
+
+/// Writes the geometries as OBJ model, referencing the given MTL file for their colors.
+///
+/// Every distinct material and every color of points and lines is named `material_<index>` in
+/// order of first use, as written by `write_mtl`. Line widths are lost.
+pub fn write_obj<W: Write>(
+    geometries: &[Geometry3D],
+    material_library: Option<&str>,
+    writer: &mut W,
+) -> Result<(), ModelError> {
+    let primitives = primitives_3d(geometries);
+    let materials = distinct_materials(&primitives);
+    let material_index = |material: &Material| {
+        materials
+            .iter()
+            .position(|distinct| distinct == material)
+            .unwrap_or(0)
+    };
+    if let Some(material_library) = material_library {
+        writeln!(writer, "mtllib {}", material_library)?;
+    }
+    let mut vertex_count = 0;
+    let mut normal_count = 0;
+    let mut current_material = None;
+    let mut use_material = |writer: &mut W, material: &Material| -> std::io::Result<()> {
+        let index = material_index(material);
+        if current_material != Some(index) {
+            current_material = Some(index);
+            writeln!(writer, "usemtl material_{}", index)?;
+        }
+        Ok(())
+    };
+    for face in &primitives.faces {
+        use_material(writer, &face.material)?;
+        for position in &face.positions {
+            writeln!(writer, "v {} {} {}", position.x, position.y, position.z)?;
+        }
+        for normal in &face.normals {
+            writeln!(writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+        }
+        writeln!(
+            writer,
+            "f {}//{} {}//{} {}//{}",
+            vertex_count + 1,
+            normal_count + 1,
+            vertex_count + 2,
+            normal_count + 2,
+            vertex_count + 3,
+            normal_count + 3
+        )?;
+        vertex_count += 3;
+        normal_count += 3;
+    }
+    for (points, color, _) in &primitives.lines {
+        use_material(writer, &Material::with(*color))?;
+        for position in points {
+            writeln!(writer, "v {} {} {}", position.x, position.y, position.z)?;
+        }
+        writeln!(writer, "l {} {}", vertex_count + 1, vertex_count + 2)?;
+        vertex_count += 2;
+    }
+    for (position, color) in &primitives.points {
+        use_material(writer, &Material::with(*color))?;
+        writeln!(writer, "v {} {} {}", position.x, position.y, position.z)?;
+        writeln!(writer, "p {}", vertex_count + 1)?;
+        vertex_count += 1;
+    }
+    Ok(())
+}
+
+/// Writes the materials referenced by `write_obj` for the same geometries as MTL file.
+pub fn write_mtl<W: Write>(geometries: &[Geometry3D], writer: &mut W) -> Result<(), ModelError> {
+    let channel = |value: u8| value as f64 / 255f64;
+    for (index, material) in distinct_materials(&primitives_3d(geometries))
+        .iter()
+        .enumerate()
+    {
+        let color = &material.color;
+        writeln!(writer, "newmtl material_{}", index)?;
+        writeln!(
+            writer,
+            "Kd {} {} {}",
+            channel(color.red),
+            channel(color.green),
+            channel(color.blue)
+        )?;
+        writeln!(writer, "d {}", channel(color.alpha))?;
+        writeln!(
+            writer,
+            "Ks {} {} {}",
+            material.specular, material.specular, material.specular
+        )?;
+        writeln!(writer, "Ns {}", material.shininess)?;
+    }
+    Ok(())
+}
+
+/// Writes the geometries as ASCII PLY model.
+///
+/// Triangles become faces, lines edges and points vertices without any face. Every vertex
+/// holds its normal, which is zero for lines and points, and its color. Line widths and all
+/// other parts of materials are lost.
+pub fn write_ply<W: Write>(geometries: &[Geometry3D], writer: &mut W) -> Result<(), ModelError> {
+    let primitives = primitives_3d(geometries);
+    let vertex_count =
+        primitives.faces.len() * 3 + primitives.lines.len() * 2 + primitives.points.len();
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format ascii 1.0")?;
+    writeln!(writer, "element vertex {}", vertex_count)?;
+    for property in &["x", "y", "z", "nx", "ny", "nz"] {
+        writeln!(writer, "property double {}", property)?;
+    }
+    for property in &["red", "green", "blue", "alpha"] {
+        writeln!(writer, "property uchar {}", property)?;
+    }
+    writeln!(writer, "element face {}", primitives.faces.len())?;
+    writeln!(writer, "property list uchar int vertex_indices")?;
+    writeln!(writer, "element edge {}", primitives.lines.len())?;
+    writeln!(writer, "property int vertex1")?;
+    writeln!(writer, "property int vertex2")?;
+    writeln!(writer, "end_header")?;
+
+    let write_vertex = |writer: &mut W, position: &Position3D, normal: &Vector3D, color: &Color| {
+        writeln!(
+            writer,
+            "{} {} {} {} {} {} {} {} {} {}",
+            position.x,
+            position.y,
+            position.z,
+            normal.x,
+            normal.y,
+            normal.z,
+            color.red,
+            color.green,
+            color.blue,
+            color.alpha
+        )
+    };
+    for face in &primitives.faces {
+        for (position, normal) in face.positions.iter().zip(&face.normals) {
+            write_vertex(writer, position, normal, &face.material.color)?;
+        }
+    }
+    for (points, color, _) in &primitives.lines {
+        for position in points {
+            write_vertex(writer, position, &Vector3D::zero(), color)?;
+        }
+    }
+    for (position, color) in &primitives.points {
+        write_vertex(writer, position, &Vector3D::zero(), color)?;
+    }
+    for index in 0..primitives.faces.len() {
+        writeln!(
+            writer,
+            "3 {} {} {}",
+            index * 3,
+            index * 3 + 1,
+            index * 3 + 2
+        )?;
+    }
+    let first_line_vertex = primitives.faces.len() * 3;
+    for index in 0..primitives.lines.len() {
+        writeln!(
+            writer,
+            "{} {}",
+            first_line_vertex + index * 2,
+            first_line_vertex + index * 2 + 1
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads an OBJ model using the given materials for its `usemtl` statements.
+///
+/// Referenced MTL files are not read. Faces with an unknown or without any material are drawn
+/// light grey.
+///
+/// All faces sharing a material become one `Geometry3D::Mesh`, which is returned as it is for
+/// models with a single material and inside of a `Geometry3D::Group` otherwise. Meshes whose
+/// faces all have normals keep them, the others are shaded flat.
+pub fn read_obj<R: Read>(
+    reader: &mut R,
+    materials: &HashMap<String, Material>,
+) -> Result<Geometry3D, ModelError> {
+    Ok(parse_obj(&read_to_string(reader)?)?.into_geometry(materials))
+}
+
+/// Reads the materials of an MTL file by their names.
+pub fn read_mtl<R: Read>(reader: &mut R) -> Result<HashMap<String, Material>, ModelError> {
+    let source = read_to_string(reader)?;
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, Material)> = None;
+    for (index, line) in source.lines().enumerate() {
+        let mut tokens = statement(line);
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments: Vec<&str> = tokens.collect();
+        if keyword == "newmtl" {
+            materials.extend(current.take());
+            current = Some((arguments.join(" "), default_material()));
+            continue;
+        }
+        let material = match &mut current {
+            Some((_, material)) => material,
+            None => continue,
+        };
+        match keyword {
+            // Spectral curves and CIE XYZ colors are not supported, RGB is used instead.
+            "Kd" | "Ks" if matches!(arguments.first(), Some(&"spectral") | Some(&"xyz")) => {}
+            "Kd" => {
+                let [red, green, blue] = numbers(&arguments, index)?;
+                material.color = Color::with(
+                    to_channel(red),
+                    to_channel(green),
+                    to_channel(blue),
+                    material.color.alpha,
+                );
+            }
+            "d" => material.color.alpha = to_channel(numbers::<1>(&arguments, index)?[0]),
+            "Tr" => material.color.alpha = to_channel(1f64 - numbers::<1>(&arguments, index)?[0]),
+            "Ks" => {
+                let [red, green, blue] = numbers(&arguments, index)?;
+                material.specular = (red + green + blue) / 3f64;
+            }
+            "Ns" => material.shininess = numbers::<1>(&arguments, index)?[0],
+            _ => {}
+        }
+    }
+    materials.extend(current);
+    Ok(materials)
+}
+
+/* --- --- --- ModelCache --- --- --- */
+
+/// Loads every model only once and hands out copies of it afterwards.
+///
+/// The cache can be used from `&self`, e.g. inside of `draw_three_dimensional`, and shared
+/// between threads.
+#[derive(Debug, Default)]
+pub struct ModelCache {
+    models: Mutex<HashMap<PathBuf, Geometry3D>>,
+}
+
+impl ModelCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the model of the OBJ file, which is only loaded by `load_obj` on the first call
+    /// for the path.
+    ///
+    /// Failed loads are not cached.
+    pub fn load_obj<P: AsRef<Path>>(&self, path: P) -> Result<Geometry3D, ModelError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(model) = self.lock().get(&path) {
+            return Ok(model.clone());
+        }
+        let model = load_obj(&path)?;
+        self.lock().insert(path, model.clone());
+        Ok(model)
+    }
+
+    /// Forgets all loaded models, e.g. to pick up changed files.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Geometry3D>> {
+        // the map stays valid even if a thread panicked while holding the lock
+        self.models
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/* --- --- --- Parsing --- --- --- */
+
+/// The content of an OBJ file with the faces grouped by their material.
+struct ObjModel {
+    material_libraries: Vec<String>,
+    /// The faces of every material in order of first use, `None` for faces without material.
+    meshes: Vec<(Option<String>, ObjMesh)>,
+}
+
+#[derive(Default)]
+struct ObjMesh {
+    vertices: Vec<Position3D>,
+    normals: Vec<Vector3D>,
+    triangles: Vec<[usize; 3]>,
+    /// The vertex of every combination of position and normal index.
+    vertex_indices: HashMap<(usize, Option<usize>), usize>,
+    has_all_normals: bool,
+}
+
+fn parse_obj(source: &str) -> Result<ObjModel, ModelError> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut model = ObjModel {
+        material_libraries: Vec::new(),
+        meshes: Vec::new(),
+    };
+    let mut current_material: Option<String> = None;
+    for (index, line) in source.lines().enumerate() {
+        let mut tokens = statement(line);
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments: Vec<&str> = tokens.collect();
+        match keyword {
+            "v" if arguments.len() >= 3 => {
+                let [x, y, z] = numbers(&arguments[..3], index)?;
+                positions.push(Position3D::with(x, y, z));
+            }
+            "v" => return Err(malformed(index, "position without three coordinates")),
+            "vn" => {
+                let [x, y, z] = numbers(&arguments, index)?;
+                normals.push(Vector3D::with(x, y, z).normalized());
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(malformed(index, "face with less than three points"));
+                }
+                let corners = arguments
+                    .iter()
+                    .map(|corner| parse_corner(corner, positions.len(), normals.len(), index))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mesh = match model
+                    .meshes
+                    .iter()
+                    .position(|(material, _)| *material == current_material)
+                {
+                    Some(mesh_index) => &mut model.meshes[mesh_index].1,
+                    None => {
+                        model.meshes.push((
+                            current_material.clone(),
+                            ObjMesh {
+                                has_all_normals: true,
+                                ..ObjMesh::default()
+                            },
+                        ));
+                        &mut model.meshes.last_mut().unwrap().1
+                    }
+                };
+                let vertices: Vec<usize> = corners
+                    .iter()
+                    .map(|corner| mesh.vertex(*corner, &positions, &normals))
+                    .collect();
+                for fan in 2..vertices.len() {
+                    mesh.triangles
+                        .push([vertices[0], vertices[fan - 1], vertices[fan]]);
+                }
+            }
+            "usemtl" => current_material = Some(arguments.join(" ")),
+            "mtllib" => model
+                .material_libraries
+                .extend(arguments.iter().map(|library| library.to_string())),
+            _ => {}
+        }
+    }
+    Ok(model)
+}
+
+impl ObjMesh {
+    fn vertex(
+        &mut self,
+        (position, normal): (usize, Option<usize>),
+        positions: &[Position3D],
+        normals: &[Vector3D],
+    ) -> usize {
+        if let Some(vertex) = self.vertex_indices.get(&(position, normal)) {
+            return *vertex;
+        }
+        self.vertices.push(positions[position]);
+        match normal {
+            Some(normal) => self.normals.push(normals[normal]),
+            None => self.has_all_normals = false,
+        }
+        self.vertex_indices
+            .insert((position, normal), self.vertices.len() - 1);
+        self.vertices.len() - 1
+    }
+}
+
+impl ObjModel {
+    fn into_geometry(self, materials: &HashMap<String, Material>) -> Geometry3D {
+        let mut meshes: Vec<Geometry3D> = self
+            .meshes
+            .into_iter()
+            .map(|(material, mesh)| {
+                let geometry = Geometry3D::mesh(mesh.vertices, mesh.triangles).material(
+                    material
+                        .and_then(|material| materials.get(&material).copied())
+                        .unwrap_or_else(default_material),
+                );
+                if mesh.has_all_normals {
+                    geometry.normals(mesh.normals)
+                } else {
+                    geometry
+                }
+            })
+            .collect();
+        if meshes.len() == 1 {
+            meshes.remove(0)
+        } else {
+            Geometry3D::group(meshes)
+        }
+    }
+}
+
+/// Returns the position and normal index of a face corner like `1`, `1/2`, `1//3` or `1/2/3`.
+///
+/// Indices start at one, negative ones count backwards from the last element read so far.
+fn parse_corner(
+    corner: &str,
+    position_count: usize,
+    normal_count: usize,
+    line_index: usize,
+) -> Result<(usize, Option<usize>), ModelError> {
+    let resolve = |value: &str, count: usize| -> Result<usize, ModelError> {
+        let index: i64 = value
+            .parse()
+            .map_err(|_| malformed(line_index, &format!("invalid index {}", value)))?;
+        let resolved = if index < 0 {
+            count as i64 + index
+        } else {
+            index - 1
+        };
+        if (0..count as i64).contains(&resolved) {
+            Ok(resolved as usize)
+        } else {
+            Err(malformed(
+                line_index,
+                &format!("index {} out of range", value),
+            ))
+        }
+    };
+    let mut parts = corner.split('/');
+    let position = resolve(parts.next().unwrap_or(""), position_count)?;
+    let normal = match parts.nth(1) {
+        Some(normal) if !normal.is_empty() => Some(resolve(normal, normal_count)?),
+        _ => None,
+    };
+    Ok((position, normal))
+}
+
+/// Returns the tokens of the line without its comment.
+fn statement(line: &str) -> std::str::SplitWhitespace<'_> {
+    line.split('#').next().unwrap_or("").split_whitespace()
+}
+
+fn numbers<const N: usize>(arguments: &[&str], line_index: usize) -> Result<[f64; N], ModelError> {
+    if arguments.len() < N {
+        return Err(malformed(
+            line_index,
+            &format!("expected {} numbers, found {}", N, arguments.len()),
+        ));
+    }
+    let mut numbers = [0f64; N];
+    for (number, argument) in numbers.iter_mut().zip(arguments) {
+        *number = argument
+            .parse()
+            .map_err(|_| malformed(line_index, &format!("invalid number {}", argument)))?;
+    }
+    Ok(numbers)
+}
+
+fn malformed(line_index: usize, reason: &str) -> ModelError {
+    ModelError::Malformed(format!("line {}: {}", line_index + 1, reason))
+}
+
+fn read_to_string<R: Read>(reader: &mut R) -> Result<String, ModelError> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Returns the distinct materials of the faces followed by those of the colors of lines and
+/// points, in order of first use.
+fn distinct_materials(primitives: &Primitives3D) -> Vec<Material> {
+    let mut materials: Vec<Material> = Vec::new();
+    let line_and_point_materials = primitives
+        .lines
+        .iter()
+        .map(|(_, color, _)| Material::with(*color))
+        .chain(
+            primitives
+                .points
+                .iter()
+                .map(|(_, color)| Material::with(*color)),
+        );
+    for material in primitives
+        .faces
+        .iter()
+        .map(|face| face.material)
+        .chain(line_and_point_materials)
+    {
+        if !materials.contains(&material) {
+            materials.push(material);
+        }
+    }
+    materials
+}
+
+fn default_material() -> Material {
+    Material::with(Color::with(204, 204, 204, 255))
+}
+
+fn to_channel(value: f64) -> u8 {
+    (value.clamp(0f64, 1f64) * 255f64).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh_material(geometry: &Geometry3D) -> Material {
+        match geometry {
+            Geometry3D::Mesh { material, .. } => *material,
+            _ => panic!("expected a mesh"),
+        }
+    }
+
+    fn read(source: &str) -> Result<Geometry3D, ModelError> {
+        read_obj(&mut source.as_bytes(), &HashMap::new())
+    }
+
+    fn mesh_parts(geometry: &Geometry3D) -> (&[Position3D], &[Vector3D], &[[usize; 3]]) {
+        match geometry {
+            Geometry3D::Mesh {
+                vertices,
+                normals,
+                triangles,
+                ..
+            } => (vertices, normals, triangles),
+            _ => panic!("expected a mesh"),
+        }
+    }
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_position_read() {
+        let relative = read(&format!("{}f -3 -2 -1\nv 5 5 5\n", TRIANGLE)).unwrap();
+        let absolute = read(&format!("{}f 1 2 3\nv 5 5 5\n", TRIANGLE)).unwrap();
+        assert_eq!(relative, absolute);
+        let (vertices, _, triangles) = mesh_parts(&relative);
+        assert_eq!(
+            vertices,
+            [
+                Position3D::with(0f64, 0f64, 0f64),
+                Position3D::with(1f64, 0f64, 0f64),
+                Position3D::with(0f64, 1f64, 0f64)
+            ]
+        );
+        assert_eq!(triangles, [[0, 1, 2]]);
+    }
+
+    #[test]
+    fn out_of_range_indices_are_rejected() {
+        for face in [
+            "f 1 2 4",
+            "f 0 1 2",
+            "f -4 -2 -1",
+            "f 1 2 x",
+            "f 1//1 2//1 3//2",
+        ] {
+            let source = format!("{}vn 0 0 1\n{}\n", TRIANGLE, face);
+            match read(&source) {
+                Err(ModelError::Malformed(reason)) => assert!(reason.starts_with("line 5:")),
+                result => panic!("{} was read as {:?}", face, result),
+            }
+        }
+        assert!(read(&format!("{}f 1 2\n", TRIANGLE)).is_err());
+    }
+
+    #[test]
+    fn polygons_are_split_into_triangle_fans() {
+        let geometry = read("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv -1 1 0\nf 1 2 3 4 5\n").unwrap();
+        let (vertices, normals, triangles) = mesh_parts(&geometry);
+        assert_eq!(vertices.len(), 5);
+        assert!(normals.is_empty());
+        assert_eq!(triangles, [[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+    }
+
+    #[test]
+    fn normals_are_kept_if_all_faces_have_them() {
+        let source = format!(
+            "{}vn 0 0 2\nvn 0 0 -1\nf 1//1 2//1 3//1\nf 1//2 3//2 2//2\n",
+            TRIANGLE
+        );
+        let geometry = read(&source).unwrap();
+        let (vertices, normals, triangles) = mesh_parts(&geometry);
+        // every position is used with two different normals
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(triangles, [[0, 1, 2], [3, 4, 5]]);
+        assert_eq!(normals[..3], [Vector3D::with(0f64, 0f64, 1f64); 3]);
+        assert_eq!(normals[3..], [Vector3D::with(0f64, 0f64, -1f64); 3]);
+
+        let mixed = read(&format!(
+            "{}vn 0 0 1\nf 1//1 2//1 3//1\nf 1 3 2\n",
+            TRIANGLE
+        ))
+        .unwrap();
+        let (vertices, normals, _) = mesh_parts(&mixed);
+        assert_eq!(vertices.len(), 6);
+        assert!(normals.is_empty());
+    }
+
+    #[test]
+    fn model_cache_loads_every_path_once() {
+        let directory =
+            std::env::temp_dir().join(format!("gymnarium_model_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("triangle.obj");
+        let cache = ModelCache::new();
+
+        let missing = cache.load_obj(&path);
+        std::fs::write(&path, format!("{}f 1 2 3\n", TRIANGLE)).unwrap();
+        let first = cache.load_obj(&path);
+        std::fs::write(&path, format!("{}f 3 2 1\n", TRIANGLE)).unwrap();
+        let cached = cache.load_obj(&path);
+        cache.clear();
+        let reloaded = cache.load_obj(&path);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(matches!(missing, Err(ModelError::Io(_))));
+        let first = first.unwrap();
+        assert_eq!(mesh_parts(&first).2, [[0, 1, 2]]);
+        assert_eq!(cached.unwrap(), first);
+        assert_eq!(
+            mesh_parts(&reloaded.unwrap()).0[0],
+            Position3D::with(0f64, 1f64, 0f64)
+        );
+    }
+
+    /// A triangle scaled by two and moved by (1, 2, 3), which puts it at (1, 2, 3), (3, 2, 3)
+    /// and (1, 4, 3).
+    fn transformed_triangle() -> Geometry3D {
+        Geometry3D::triangle(
+            Position3D::with(0f64, 0f64, 0f64),
+            Position3D::with(1f64, 0f64, 0f64),
+            Position3D::with(0f64, 1f64, 0f64),
+        )
+        .material(Material::with(Color::with(255, 0, 0, 255)))
+        .scale(2f64, 2f64, 2f64)
+        .move_by(Vector3D::with(1f64, 2f64, 3f64))
+    }
+
+    fn transformed_positions() -> [Position3D; 3] {
+        [
+            Position3D::with(1f64, 2f64, 3f64),
+            Position3D::with(3f64, 2f64, 3f64),
+            Position3D::with(1f64, 4f64, 3f64),
+        ]
+    }
+
+    fn parse_numbers(line: &str) -> Vec<f64> {
+        line.split_whitespace()
+            .map(|number| number.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn written_obj_models_are_read_back_in_world_coordinates() {
+        let geometries = [
+            transformed_triangle(),
+            Geometry3D::mesh(
+                vec![
+                    Position3D::with(0f64, 0f64, 0f64),
+                    Position3D::with(0f64, 0f64, 1f64),
+                    Position3D::with(1f64, 0f64, 0f64),
+                ],
+                vec![[0, 1, 2]],
+            )
+            .material(Material::with(Color::with(0, 51, 255, 128)))
+            .move_by(Vector3D::with(0f64, -1f64, 0f64)),
+            Geometry3D::line(Position3D::zero(), Position3D::with(1f64, 1f64, 1f64)),
+            Geometry3D::point(Position3D::zero()).move_by(Vector3D::with(5f64, 0f64, 0f64)),
+        ];
+        let mut obj = Vec::new();
+        write_obj(&geometries, Some("model.mtl"), &mut obj).unwrap();
+        let mut mtl = Vec::new();
+        write_mtl(&geometries, &mut mtl).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+
+        assert!(obj.starts_with("mtllib model.mtl\n"));
+        assert!(obj.contains("\nv 5 0 0\np 9\n"));
+        let materials = read_mtl(&mut mtl.as_slice()).unwrap();
+        assert_eq!(materials.len(), 3);
+        let model = read_obj(&mut obj.as_bytes(), &materials).unwrap();
+        let meshes = match &model {
+            Geometry3D::Group(meshes) => meshes,
+            _ => panic!("expected a group"),
+        };
+        assert_eq!(meshes.len(), 2);
+
+        let (vertices, normals, triangles) = mesh_parts(&meshes[0]);
+        assert_eq!(vertices, transformed_positions());
+        assert_eq!(normals, [Vector3D::with(0f64, 0f64, 1f64); 3]);
+        assert_eq!(triangles, [[0, 1, 2]]);
+        assert_eq!(
+            mesh_material(&meshes[0]),
+            Material::with(Color::with(255, 0, 0, 255))
+        );
+
+        let (vertices, normals, _) = mesh_parts(&meshes[1]);
+        assert_eq!(
+            vertices,
+            [
+                Position3D::with(0f64, -1f64, 0f64),
+                Position3D::with(0f64, -1f64, 1f64),
+                Position3D::with(1f64, -1f64, 0f64)
+            ]
+        );
+        assert_eq!(normals, [Vector3D::with(0f64, 1f64, 0f64); 3]);
+        assert_eq!(
+            mesh_material(&meshes[1]).color,
+            Color::with(0, 51, 255, 128)
+        );
+    }
+
+    #[test]
+    fn written_ply_models_hold_all_vertices_faces_and_edges() {
+        let geometries = [
+            transformed_triangle(),
+            Geometry3D::line(Position3D::zero(), Position3D::with(1f64, 1f64, 1f64))
+                .color(Color::with(0, 0, 255, 255))
+                .move_by(Vector3D::with(0f64, 0f64, 1f64)),
+            Geometry3D::point(Position3D::zero()).color(Color::with(1, 2, 3, 4)),
+        ];
+        let mut ply = Vec::new();
+        write_ply(&geometries, &mut ply).unwrap();
+        let ply = String::from_utf8(ply).unwrap();
+        let (header, body) = ply.split_at(ply.find("end_header\n").unwrap());
+        let body: Vec<&str> = body.lines().skip(1).collect();
+
+        assert_eq!(
+            header,
+            "ply\nformat ascii 1.0\nelement vertex 6\n\
+             property double x\nproperty double y\nproperty double z\n\
+             property double nx\nproperty double ny\nproperty double nz\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             property uchar alpha\nelement face 1\nproperty list uchar int vertex_indices\n\
+             element edge 1\nproperty int vertex1\nproperty int vertex2\n"
+        );
+        assert_eq!(body.len(), 6 + 1 + 1);
+        for (line, position) in body.iter().zip(&transformed_positions()) {
+            assert_eq!(
+                parse_numbers(line),
+                [
+                    position.x, position.y, position.z, 0f64, 0f64, 1f64, 255f64, 0f64, 0f64,
+                    255f64
+                ]
+            );
+        }
+        assert_eq!(
+            parse_numbers(body[4]),
+            [1f64, 1f64, 2f64, 0f64, 0f64, 0f64, 0f64, 0f64, 255f64, 255f64]
+        );
+        assert_eq!(
+            parse_numbers(body[5]),
+            [0f64, 0f64, 0f64, 0f64, 0f64, 0f64, 1f64, 2f64, 3f64, 4f64]
+        );
+        assert_eq!(body[6], "3 0 1 2");
+        assert_eq!(body[7], "3 4");
+    }
+
+    #[test]
+    fn sequences_pad_the_frame_index() {
+        let directory =
+            std::env::temp_dir().join(format!("gymnarium_model_sequence_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let frames: Vec<Vec<Geometry3D>> = (0..11)
+            .map(|index| {
+                vec![Geometry3D::point(Position3D::with(
+                    index as f64,
+                    0f64,
+                    0f64,
+                ))]
+            })
+            .collect();
+        let path = |name: &str| directory.join(name).to_str().unwrap().to_string();
+
+        let saved = save_sequence(&frames, &path("frame_{}.ply"));
+        let names = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<std::collections::BTreeSet<_>>();
+        let seventh = std::fs::read_to_string(path("frame_07.ply"));
+        let without_placeholder = save_sequence(&frames, &path("frame.obj"));
+        let single = save_sequence(&frames[..1], &path("frame.obj"));
+        let single_names = (
+            std::path::Path::new(&path("frame.obj")).exists(),
+            std::path::Path::new(&path("frame.mtl")).exists(),
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        saved.unwrap();
+        assert_eq!(
+            names,
+            (0..11)
+                .map(|index| format!("frame_{:02}.ply", index))
+                .collect()
+        );
+        assert!(seventh.unwrap().contains("\n7 0 0 0 0 0 0 0 0 255\n"));
+        assert!(matches!(
+            without_placeholder,
+            Err(ModelError::Unsupported(_))
+        ));
+        single.unwrap();
+        assert_eq!(single_names, (true, true));
+    }
+
+    #[test]
+    fn unsupported_color_forms_are_ignored() {
+        let mtl = "newmtl red\nKd 1 0 0\nKd spectral red.rfl 1.0\nKs xyz 0.5 0.5 0.5\nd 0.5\n\
+                   newmtl plain\nKd xyz 1 1 1\n";
+        let materials = read_mtl(&mut mtl.as_bytes()).unwrap();
+        assert_eq!(materials["red"].color, Color::with(255, 0, 0, 128));
+        assert_eq!(materials["plain"], default_material());
+        assert!(read_mtl(&mut "newmtl broken\nKd 1 zero 0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn missing_material_library_falls_back_to_default_material() {
+        let directory =
+            std::env::temp_dir().join(format!("gymnarium_model_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("triangle.obj");
+        std::fs::write(
+            &path,
+            "mtllib missing.mtl\nusemtl red\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n",
+        )
+        .unwrap();
+        let geometry = load_obj(&path);
+
+        std::fs::write(directory.join("missing.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+        let with_library = load_obj(&path);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(mesh_material(&geometry.unwrap()), default_material());
+        assert_eq!(
+            mesh_material(&with_library.unwrap()).color,
+            Color::with(255, 0, 0, 255)
+        );
+    }
+}
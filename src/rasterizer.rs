@@ -0,0 +1,1089 @@
+//! CPU-only rasterization of two dimensional geometries into `PixelArray`s.
+//!
+//! Useful on machines without any GPU or display, e.g. for pixel based agents or recordings.
+
+use std::error::Error;
+
+use gymnarium_base::math::{Position2D, Size2D};
+
+use crate::tessellation::{
+    ellipse_contour, rectangle_contour, segments_for_radius, stroke_contours, Affine2D, Contour,
+};
+use crate::{
+    font, BlendMode, Color, CornerShape, Geometry2D, LineShape, Pixel, PixelArray,
+    TwoDimensionalDrawableEnvironment, Viewport2D, Viewport2DModification,
+};
+
+/* --- --- --- AntiAliasing --- --- --- */
+
+/// How the edges of geometries are smoothed while rasterizing.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AntiAliasing {
+    /// Every pixel is either fully covered or not at all, decided at its center.
+    ///
+    /// This is the fastest mode and well suited for observations.
+    #[default]
+    None,
+    /// Every pixel is sampled on a grid with the given number of samples per axis, limited to
+    /// `MAXIMUM_SAMPLES_PER_AXIS`.
+    Supersampling(u32),
+    /// The covered area of every pixel is approximated by 16 scanlines per pixel row.
+    ///
+    /// Only the horizontal coverage along every scanline is exact, vertically the coverage is
+    /// resolved in steps of a sixteenth of a pixel, like supersampling with 16 rows.
+    Analytic,
+}
+
+/// Highest number of samples per axis used by `AntiAliasing::Supersampling`.
+pub const MAXIMUM_SAMPLES_PER_AXIS: u32 = 16;
+
+/// Scanlines per pixel row of `AntiAliasing::Analytic`.
+const ANALYTIC_SCANLINES_PER_ROW: usize = 16;
+
+impl AntiAliasing {
+    fn samples_per_axis(&self) -> usize {
+        match self {
+            Self::None => 1,
+            Self::Supersampling(samples_per_axis) => {
+                (*samples_per_axis).clamp(1, MAXIMUM_SAMPLES_PER_AXIS) as usize
+            }
+            Self::Analytic => 4,
+        }
+    }
+}
+
+/* --- --- --- Rasterizer2D --- --- --- */
+
+/// Renders two dimensional geometries into a `PixelArray` without any GPU.
+///
+/// Without a viewport the geometries are expected to be given in pixel coordinates, with the
+/// origin in the upper left corner and the y axis pointing down. A viewport which flips none
+/// of its axes shares this orientation.
+///
+/// Borders are centered on the outline of their shape and drawn on top of the fill. Every fill,
+/// border and image is composited separately onto the pixels beneath it using the blend mode.
+/// `LineShape::Square` draws square caps and miter joins, `LineShape::Round` round caps and
+/// joins and `LineShape::Bevel` butt caps and bevel joins.
+///
+/// The rasterizer is cheap to clone, so settings like the anti-aliasing can be changed for every
+/// single render call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rasterizer2D {
+    pub width: usize,
+    pub height: usize,
+    pub viewport: Option<(Viewport2D, Viewport2DModification)>,
+    pub background_color: Color,
+    pub anti_aliasing: AntiAliasing,
+    pub blend_mode: BlendMode,
+}
+
+impl Rasterizer2D {
+    /// Creates a new rasterizer producing pixel arrays with the given resolution.
+    ///
+    /// Defaults are `viewport: None`, `background_color: Color::white()`,
+    /// `anti_aliasing: AntiAliasing::None` and `blend_mode: BlendMode::SourceOver`.
+    pub fn with(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            viewport: None,
+            background_color: Color::white(),
+            anti_aliasing: AntiAliasing::None,
+            blend_mode: BlendMode::SourceOver,
+        }
+    }
+
+    pub fn viewport(
+        self,
+        viewport: Viewport2D,
+        viewport_modification: Viewport2DModification,
+    ) -> Self {
+        Self {
+            viewport: Some((viewport, viewport_modification)),
+            ..self
+        }
+    }
+
+    pub fn background_color(self, background_color: Color) -> Self {
+        Self {
+            background_color,
+            ..self
+        }
+    }
+
+    pub fn anti_aliasing(self, anti_aliasing: AntiAliasing) -> Self {
+        Self {
+            anti_aliasing,
+            ..self
+        }
+    }
+
+    pub fn blend_mode(self, blend_mode: BlendMode) -> Self {
+        Self { blend_mode, ..self }
+    }
+
+    /// Draws the geometries onto a new pixel array filled with the background color.
+    pub fn rasterize(&self, geometries: &[Geometry2D]) -> PixelArray {
+        let mut pixel_array = PixelArray {
+            data: vec![opaque_pixel(&self.background_color); self.width * self.height],
+            width: self.width,
+            height: self.height,
+        };
+        self.rasterize_onto(geometries, &mut pixel_array);
+        pixel_array
+    }
+
+    /// Draws the geometries on top of the content of an existing pixel array.
+    ///
+    /// The viewport is fitted to the size of the given pixel array instead of the size of the
+    /// rasterizer.
+    pub fn rasterize_onto(&self, geometries: &[Geometry2D], pixel_array: &mut PixelArray) {
+        pixel_array.data.resize(
+            pixel_array.width * pixel_array.height,
+            opaque_pixel(&self.background_color),
+        );
+        let (pixel_transform, clip) = self.pixel_transform(pixel_array.width, pixel_array.height);
+        let mut canvas = Canvas {
+            pixel_array,
+            clip,
+            anti_aliasing: self.anti_aliasing,
+            blend_mode: self.blend_mode,
+        };
+        for geometry in geometries {
+            self.draw_geometry(geometry, &pixel_transform, &mut canvas);
+        }
+    }
+
+    /// Draws the geometries of the given environment.
+    ///
+    /// The preferred view and background color of the environment take precedence over the
+    /// ones of this rasterizer.
+    pub fn rasterize_environment<
+        EnvironmentError: Error,
+        DrawableEnvironment: TwoDimensionalDrawableEnvironment<EnvironmentError>,
+    >(
+        &self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<PixelArray, EnvironmentError> {
+        let geometries = drawable_environment.draw_two_dimensional()?;
+        let mut rasterizer = self.clone();
+        if let Some(preferred_view) = drawable_environment.preferred_view() {
+            rasterizer.viewport = Some(preferred_view);
+        }
+        if let Some(background_color) = drawable_environment.preferred_background_color() {
+            rasterizer.background_color = background_color;
+        }
+        Ok(rasterizer.rasterize(&geometries))
+    }
+
+    fn pixel_transform(&self, width: usize, height: usize) -> (Affine2D, ClipRect) {
+        let full = ClipRect {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        };
+        match &self.viewport {
+            Some((viewport, viewport_modification)) => {
+                let layout = viewport_modification
+                    .layout(viewport, &Size2D::with(width as f64, height as f64));
+                let clip = match layout.scissor {
+                    Some((top_left, size)) => ClipRect {
+                        left: top_left.x.round().max(0f64) as usize,
+                        top: top_left.y.round().max(0f64) as usize,
+                        right: (top_left.x + size.width).round().max(0f64) as usize,
+                        bottom: (top_left.y + size.height).round().max(0f64) as usize,
+                    }
+                    .intersect(&full),
+                    None => full,
+                };
+                (Affine2D::from_layout(&layout), clip)
+            }
+            None => (Affine2D::identity(), full),
+        }
+    }
+
+    fn draw_geometry(
+        &self,
+        geometry: &Geometry2D,
+        pixel_transform: &Affine2D,
+        canvas: &mut Canvas,
+    ) {
+        match geometry {
+            Geometry2D::Point {
+                position,
+                color,
+                transformations,
+            } => {
+                let transform =
+                    Affine2D::from_transformations(transformations).then(pixel_transform);
+                let pixel =
+                    rectangle_contour(&transform.apply(position), 1f64, 1f64, &CornerShape::Square);
+                canvas.fill(&[pixel], color);
+            }
+            Geometry2D::Line {
+                points,
+                line_color,
+                line_width,
+                line_shape,
+                transformations,
+            } => canvas.draw_stroke(
+                points,
+                line_color,
+                *line_width,
+                line_shape,
+                false,
+                &Affine2D::from_transformations(transformations).then(pixel_transform),
+            ),
+            Geometry2D::Polyline {
+                points,
+                line_color,
+                line_width,
+                line_shape,
+                transformations,
+            } => canvas.draw_stroke(
+                points,
+                line_color,
+                *line_width,
+                line_shape,
+                false,
+                &Affine2D::from_transformations(transformations).then(pixel_transform),
+            ),
+            Geometry2D::Triangle {
+                points,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => canvas.draw_shape(
+                points.to_vec(),
+                fill_color,
+                border_color,
+                *border_width,
+                &Affine2D::from_transformations(transformations).then(pixel_transform),
+            ),
+            Geometry2D::Square {
+                center_position,
+                edge_length,
+                fill_color,
+                border_color,
+                border_width,
+                corner_shape,
+                transformations,
+            } => canvas.draw_shape(
+                rectangle_contour(center_position, *edge_length, *edge_length, corner_shape),
+                fill_color,
+                border_color,
+                *border_width,
+                &Affine2D::from_transformations(transformations).then(pixel_transform),
+            ),
+            Geometry2D::Rectangle {
+                center_position,
+                size,
+                fill_color,
+                border_color,
+                border_width,
+                corner_shape,
+                transformations,
+            } => canvas.draw_shape(
+                rectangle_contour(center_position, size.width, size.height, corner_shape),
+                fill_color,
+                border_color,
+                *border_width,
+                &Affine2D::from_transformations(transformations).then(pixel_transform),
+            ),
+            Geometry2D::Polygon {
+                points,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => canvas.draw_shape(
+                points.clone(),
+                fill_color,
+                border_color,
+                *border_width,
+                &Affine2D::from_transformations(transformations).then(pixel_transform),
+            ),
+            Geometry2D::Circle {
+                center_position,
+                radius,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => {
+                let transform =
+                    Affine2D::from_transformations(transformations).then(pixel_transform);
+                let segments = segments_for_radius(radius * transform.maximum_scale());
+                canvas.draw_shape(
+                    ellipse_contour(center_position, *radius, *radius, segments),
+                    fill_color,
+                    border_color,
+                    *border_width,
+                    &transform,
+                )
+            }
+            Geometry2D::Ellipse {
+                center_position,
+                size,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => {
+                let transform =
+                    Affine2D::from_transformations(transformations).then(pixel_transform);
+                let segments = segments_for_radius(
+                    size.width.abs().max(size.height.abs()) / 2f64 * transform.maximum_scale(),
+                );
+                canvas.draw_shape(
+                    ellipse_contour(
+                        center_position,
+                        size.width / 2f64,
+                        size.height / 2f64,
+                        segments,
+                    ),
+                    fill_color,
+                    border_color,
+                    *border_width,
+                    &transform,
+                )
+            }
+            Geometry2D::Image {
+                center_position,
+                size,
+                texture_source,
+                source_rectangle,
+                fill_color,
+                transformations,
+            } => {
+                if let Some((data, width, height)) = texture_source.rgba_bytes() {
+                    canvas.draw_image(
+                        center_position,
+                        (size.width, size.height),
+                        (data, width as usize, height as usize),
+                        source_rectangle
+                            .map(|(position, size)| {
+                                (position.x, position.y, size.width, size.height)
+                            })
+                            .unwrap_or((0f64, 0f64, width as f64, height as f64)),
+                        fill_color,
+                        &Affine2D::from_transformations(transformations).then(pixel_transform),
+                    );
+                }
+            }
+            Geometry2D::Text {
+                position,
+                text,
+                font_size,
+                color,
+                horizontal_anchor,
+                vertical_anchor,
+                transformations,
+            } => {
+                let transform =
+                    Geometry2D::text_transform(position, transformations, pixel_transform);
+                let top_left = Geometry2D::text_corners(
+                    position,
+                    text,
+                    *font_size,
+                    horizontal_anchor,
+                    vertical_anchor,
+                )[0];
+                let dots: Vec<Contour> = font::dot_rectangles(text, *font_size, &top_left)
+                    .iter()
+                    .map(|(corner, size)| {
+                        apply(
+                            &transform,
+                            &[
+                                *corner,
+                                Position2D::with(corner.x + size.width, corner.y),
+                                Position2D::with(corner.x + size.width, corner.y + size.height),
+                                Position2D::with(corner.x, corner.y + size.height),
+                            ],
+                        )
+                    })
+                    .collect();
+                canvas.fill(&dots, color);
+            }
+            Geometry2D::Group(geometries) => {
+                for geometry in geometries {
+                    self.draw_geometry(geometry, pixel_transform, canvas);
+                }
+            }
+        }
+    }
+}
+
+/* --- --- --- Canvas --- --- --- */
+
+/// The pixel array currently drawn on together with the settings of the current render call.
+struct Canvas<'a> {
+    pixel_array: &'a mut PixelArray,
+    clip: ClipRect,
+    anti_aliasing: AntiAliasing,
+    blend_mode: BlendMode,
+}
+
+impl<'a> Canvas<'a> {
+    #[allow(clippy::too_many_arguments)]
+    fn draw_stroke(
+        &mut self,
+        points: &[Position2D],
+        line_color: &Color,
+        line_width: f64,
+        line_shape: &LineShape,
+        closed: bool,
+        transform: &Affine2D,
+    ) {
+        if line_color.alpha == 0 || line_width <= 0f64 {
+            return;
+        }
+        let round_segments = segments_for_radius(line_width / 2f64 * transform.maximum_scale());
+        let contours: Vec<Contour> =
+            stroke_contours(points, line_width, line_shape, closed, round_segments)
+                .into_iter()
+                .map(|contour| apply(transform, &contour))
+                .collect();
+        self.fill(&contours, line_color);
+    }
+
+    fn draw_shape(
+        &mut self,
+        outline: Contour,
+        fill_color: &Color,
+        border_color: &Color,
+        border_width: f64,
+        transform: &Affine2D,
+    ) {
+        self.fill(&[apply(transform, &outline)], fill_color);
+        self.draw_stroke(
+            &outline,
+            border_color,
+            border_width,
+            &LineShape::Square,
+            true,
+            transform,
+        );
+    }
+
+    /// Samples the texture with the nearest texel, averaging multiple samples per pixel when
+    /// anti-aliasing is enabled.
+    fn draw_image(
+        &mut self,
+        center_position: &Position2D,
+        (width, height): (f64, f64),
+        (texture, texture_width, texture_height): (&[u8], usize, usize),
+        (source_x, source_y, source_width, source_height): (f64, f64, f64, f64),
+        fill_color: &Option<Color>,
+        transform: &Affine2D,
+    ) {
+        if texture_width == 0 || texture_height == 0 {
+            return;
+        }
+        let inverse = match transform.inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+        let corners = apply(
+            transform,
+            &rectangle_contour(center_position, width, height, &CornerShape::Square),
+        );
+        let area = match ClipRect::around(&[corners]) {
+            Some(area) => area.intersect(&self.clip),
+            None => return,
+        };
+        let (left, top) = (
+            center_position.x - width / 2f64,
+            center_position.y - height / 2f64,
+        );
+        let samples = self.anti_aliasing.samples_per_axis();
+        let sample_weight = 1f64 / (samples * samples) as f64;
+        for y in area.top..area.bottom {
+            for x in area.left..area.right {
+                // Sums of the colors weighted by their alpha and the sum of all alphas.
+                let mut sums = [0f64; 4];
+                for sample in 0..samples * samples {
+                    let local = inverse.apply(&Position2D::with(
+                        x as f64 + ((sample % samples) as f64 + 0.5f64) / samples as f64,
+                        y as f64 + ((sample / samples) as f64 + 0.5f64) / samples as f64,
+                    ));
+                    let (u, v) = ((local.x - left) / width, (local.y - top) / height);
+                    if !(0f64..1f64).contains(&u) || !(0f64..1f64).contains(&v) {
+                        continue;
+                    }
+                    let texture_x = ((source_x + u * source_width).floor().max(0f64) as usize)
+                        .min(texture_width - 1);
+                    let texture_y = ((source_y + v * source_height).floor().max(0f64) as usize)
+                        .min(texture_height - 1);
+                    let index = (texture_y * texture_width + texture_x) * 4;
+                    let mut texel = [
+                        texture[index] as f64,
+                        texture[index + 1] as f64,
+                        texture[index + 2] as f64,
+                        texture[index + 3] as f64,
+                    ];
+                    if let Some(tint) = fill_color {
+                        texel[0] *= tint.red as f64 / 255f64;
+                        texel[1] *= tint.green as f64 / 255f64;
+                        texel[2] *= tint.blue as f64 / 255f64;
+                        texel[3] *= tint.alpha as f64 / 255f64;
+                    }
+                    sums[0] += texel[0] * texel[3];
+                    sums[1] += texel[1] * texel[3];
+                    sums[2] += texel[2] * texel[3];
+                    sums[3] += texel[3];
+                }
+                if sums[3] > 0f64 {
+                    let color = Color::with(
+                        (sums[0] / sums[3]).round() as u8,
+                        (sums[1] / sums[3]).round() as u8,
+                        (sums[2] / sums[3]).round() as u8,
+                        (sums[3] * sample_weight).round() as u8,
+                    );
+                    self.blend((x, y), &color, 1f32);
+                }
+            }
+        }
+    }
+
+    /// Composites the color, with its alpha scaled by the coverage, onto the pixel.
+    fn blend(&mut self, index: (usize, usize), color: &Color, coverage: f32) {
+        let [red, green, blue, alpha] = color.float_array();
+        let pixel = &mut self.pixel_array[index];
+        *pixel = pixel.blend_channels([red, green, blue], alpha * coverage, self.blend_mode);
+    }
+
+    /// Fills the contours, given in pixel coordinates, using the non-zero rule.
+    fn fill(&mut self, contours: &[Contour], color: &Color) {
+        if color.alpha == 0 {
+            return;
+        }
+        let coverage = match self.anti_aliasing {
+            AntiAliasing::None | AntiAliasing::Supersampling(_) => {
+                sampled_coverage(contours, &self.clip, self.anti_aliasing.samples_per_axis())
+            }
+            AntiAliasing::Analytic => analytic_coverage(contours, &self.clip),
+        };
+        if let Some(coverage) = coverage {
+            for row in 0..coverage.height {
+                for column in 0..coverage.width {
+                    let value = coverage.values[row * coverage.width + column];
+                    if value > 0f32 {
+                        self.blend((coverage.left + column, coverage.top + row), color, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn apply(transform: &Affine2D, contour: &[Position2D]) -> Contour {
+    contour
+        .iter()
+        .map(|position| transform.apply(position))
+        .collect()
+}
+
+fn opaque_pixel(color: &Color) -> Pixel {
+    Pixel::with(color.red, color.green, color.blue)
+}
+
+/* --- --- --- Coverage --- --- --- */
+
+/// Pixel area inside a pixel array, `right` and `bottom` are exclusive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct ClipRect {
+    left: usize,
+    top: usize,
+    right: usize,
+    bottom: usize,
+}
+
+impl ClipRect {
+    /// Returns the pixels touched by the bounding box of the contours.
+    fn around(contours: &[Contour]) -> Option<Self> {
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for position in contours.iter().flatten() {
+            if !position.x.is_finite() || !position.y.is_finite() {
+                return None;
+            }
+            min_x = min_x.min(position.x);
+            min_y = min_y.min(position.y);
+            max_x = max_x.max(position.x);
+            max_y = max_y.max(position.y);
+        }
+        if min_x > max_x || max_x < 0f64 || max_y < 0f64 {
+            return None;
+        }
+        Some(Self {
+            left: min_x.floor().max(0f64) as usize,
+            top: min_y.floor().max(0f64) as usize,
+            right: max_x.ceil() as usize + 1,
+            bottom: max_y.ceil() as usize + 1,
+        })
+    }
+
+    fn intersect(&self, other: &ClipRect) -> Self {
+        let left = self.left.max(other.left);
+        let top = self.top.max(other.top);
+        Self {
+            left,
+            top,
+            right: self.right.min(other.right).max(left),
+            bottom: self.bottom.min(other.bottom).max(top),
+        }
+    }
+}
+
+/// Covered fraction of every pixel within an area of a pixel array.
+struct Coverage {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+struct Edge {
+    top: f64,
+    bottom: f64,
+    x_at_top: f64,
+    slope: f64,
+    winding: i32,
+}
+
+/// Calls the function with the row, start and end of every span inside of the contours with
+/// the non-zero rule along `scanlines_per_row` equally spaced scanlines per pixel row.
+///
+/// All contours are scanned together, so overlapping contours cover every position only once.
+fn for_each_span<F: FnMut(usize, f64, f64)>(
+    contours: &[Contour],
+    area: &ClipRect,
+    scanlines_per_row: usize,
+    mut function: F,
+) {
+    let mut edges: Vec<Edge> = contours
+        .iter()
+        .filter(|contour| contour.len() > 2)
+        .flat_map(|contour| contour.iter().zip(contour.iter().cycle().skip(1)))
+        .filter(|(start, end)| start.y != end.y)
+        .map(|(start, end)| {
+            let (upper, lower, winding) = if start.y < end.y {
+                (start, end, 1)
+            } else {
+                (end, start, -1)
+            };
+            Edge {
+                top: upper.y,
+                bottom: lower.y,
+                x_at_top: upper.x,
+                slope: (lower.x - upper.x) / (lower.y - upper.y),
+                winding,
+            }
+        })
+        .collect();
+    edges.sort_by(|a, b| a.top.partial_cmp(&b.top).unwrap());
+
+    let mut active_edges: Vec<usize> = Vec::new();
+    let mut next_edge = 0;
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+    for row in area.top..area.bottom {
+        for scanline in 0..scanlines_per_row {
+            let y = row as f64 + (scanline as f64 + 0.5f64) / scanlines_per_row as f64;
+            while next_edge < edges.len() && edges[next_edge].top <= y {
+                active_edges.push(next_edge);
+                next_edge += 1;
+            }
+            active_edges.retain(|&index| edges[index].bottom > y);
+
+            crossings.clear();
+            crossings.extend(active_edges.iter().map(|&index| {
+                let edge = &edges[index];
+                (edge.x_at_top + (y - edge.top) * edge.slope, edge.winding)
+            }));
+            crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            let mut winding = 0;
+            let mut span_start = 0f64;
+            for &(x, edge_winding) in &crossings {
+                let previous_winding = winding;
+                winding += edge_winding;
+                if previous_winding == 0 && winding != 0 {
+                    span_start = x;
+                } else if previous_winding != 0 && winding == 0 {
+                    function(row, span_start, x);
+                }
+            }
+        }
+    }
+}
+
+/// Computes the coverage by testing a grid of `samples_per_axis` x `samples_per_axis` points
+/// inside each pixel against the contours with the non-zero rule.
+fn sampled_coverage(
+    contours: &[Contour],
+    clip: &ClipRect,
+    samples_per_axis: usize,
+) -> Option<Coverage> {
+    let area = ClipRect::around(contours)?.intersect(clip);
+    let (width, height) = (area.right - area.left, area.bottom - area.top);
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let samples = samples_per_axis.max(1);
+    let sample_weight = 1f32 / (samples * samples) as f32;
+    let (first_sample, last_sample) = (area.left * samples, area.right * samples);
+    let mut values = vec![0f32; width * height];
+    for_each_span(contours, &area, samples, |row, span_start, span_end| {
+        let start =
+            ((span_start * samples as f64 - 0.5f64).ceil().max(0f64) as usize).max(first_sample);
+        let end = ((span_end * samples as f64 - 0.5f64).ceil().max(0f64) as usize).min(last_sample);
+        let offset = (row - area.top) * width;
+        for sample in start..end.max(start) {
+            values[offset + sample / samples - area.left] += sample_weight;
+        }
+    });
+
+    Some(Coverage {
+        left: area.left,
+        top: area.top,
+        width,
+        height,
+        values,
+    })
+}
+
+/// Approximates the covered area of every pixel with the non-zero rule by the exact covered
+/// length of every one of `ANALYTIC_SCANLINES_PER_ROW` scanlines per pixel row.
+fn analytic_coverage(contours: &[Contour], clip: &ClipRect) -> Option<Coverage> {
+    let area = ClipRect::around(contours)?.intersect(clip);
+    let (width, height) = (area.right - area.left, area.bottom - area.top);
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let scanline_weight = 1f64 / ANALYTIC_SCANLINES_PER_ROW as f64;
+    let mut values = vec![0f32; width * height];
+    for_each_span(
+        contours,
+        &area,
+        ANALYTIC_SCANLINES_PER_ROW,
+        |row, span_start, span_end| {
+            let start = (span_start - area.left as f64).max(0f64);
+            let end = (span_end - area.left as f64).min(width as f64);
+            if end <= start {
+                return;
+            }
+            let row_values = &mut values[(row - area.top) * width..(row - area.top + 1) * width];
+            let (first_column, last_column) = (start.floor() as usize, end.floor() as usize);
+            if first_column == last_column {
+                row_values[first_column] += ((end - start) * scanline_weight) as f32;
+                return;
+            }
+            row_values[first_column] +=
+                ((first_column as f64 + 1f64 - start) * scanline_weight) as f32;
+            for value in &mut row_values[first_column + 1..last_column] {
+                *value += scanline_weight as f32;
+            }
+            if last_column < width {
+                row_values[last_column] += ((end - last_column as f64) * scanline_weight) as f32;
+            }
+        },
+    );
+    for value in &mut values {
+        *value = value.min(1f32);
+    }
+
+    Some(Coverage {
+        left: area.left,
+        top: area.top,
+        width,
+        height,
+        values,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use gymnarium_base::math::Size2D;
+
+    use super::*;
+    use crate::TextureSource;
+
+    #[test]
+    fn text_is_not_mirrored_by_flipped_viewports() {
+        let text = |x: f64, y: f64| {
+            vec![Geometry2D::text(
+                Position2D::with(x, y),
+                "Fg".to_string(),
+                10f64,
+            )]
+        };
+        let viewport = Viewport2D::with(Position2D::with(20f64, 10f64), Size2D::with(40f64, 20f64));
+        let rasterizer = Rasterizer2D::with(40, 20);
+        let expected = rasterizer
+            .clone()
+            .viewport(viewport, Viewport2DModification::LooseAspectRatio)
+            .rasterize(&text(2f64, 3f64));
+        assert!(expected.data.contains(&Pixel::black()));
+
+        for (flipped_x, flipped_y, x, y) in &[
+            (false, true, 2f64, 17f64),
+            (true, false, 38f64, 3f64),
+            (true, true, 38f64, 17f64),
+        ] {
+            let flipped = rasterizer
+                .clone()
+                .viewport(
+                    viewport
+                        .flipped_x_axis(*flipped_x)
+                        .flipped_y_axis(*flipped_y),
+                    Viewport2DModification::LooseAspectRatio,
+                )
+                .rasterize(&text(*x, *y));
+            assert_eq!(flipped, expected);
+        }
+    }
+
+    #[test]
+    fn overlapping_stroke_pieces_cover_pixels_once() {
+        let line = [
+            Geometry2D::line(Position2D::with(2f64, 5f64), Position2D::with(8f64, 5f64))
+                .line_shape(LineShape::Bevel),
+        ];
+        let doubled = [Geometry2D::polyline(vec![
+            Position2D::with(2f64, 5f64),
+            Position2D::with(8f64, 5f64),
+            Position2D::with(2f64, 5f64),
+            Position2D::with(8f64, 5f64),
+        ])
+        .line_shape(LineShape::Bevel)];
+        for anti_aliasing in &[
+            AntiAliasing::None,
+            AntiAliasing::Supersampling(4),
+            AntiAliasing::Analytic,
+        ] {
+            let rasterizer = Rasterizer2D::with(10, 10).anti_aliasing(*anti_aliasing);
+            let expected = rasterizer.rasterize(&line);
+            assert_eq!(rasterizer.rasterize(&doubled), expected);
+            if *anti_aliasing != AntiAliasing::None {
+                // The line covers the lower half of row 4 and the upper half of row 5.
+                assert_eq!(expected[(5, 4)], Pixel::with(128, 128, 128));
+                assert_eq!(expected[(5, 5)], Pixel::with(128, 128, 128));
+            }
+        }
+    }
+
+    #[test]
+    fn supersampling_is_limited() {
+        let circle = [Geometry2D::circle(Position2D::with(5f64, 5f64), 3.3f64)];
+        let rasterize = |samples_per_axis: u32| {
+            Rasterizer2D::with(10, 10)
+                .anti_aliasing(AntiAliasing::Supersampling(samples_per_axis))
+                .rasterize(&circle)
+        };
+        assert_eq!(rasterize(u32::MAX), rasterize(MAXIMUM_SAMPLES_PER_AXIS));
+        assert_eq!(rasterize(0), Rasterizer2D::with(10, 10).rasterize(&circle));
+    }
+
+    /// Draws the geometry onto ten by ten pixels showing the area from the origin to `(20, 20)`,
+    /// so every pixel is two units wide.
+    fn rasterize_scaled(geometry: Geometry2D) -> PixelArray {
+        Rasterizer2D::with(10, 10)
+            .viewport(
+                Viewport2D::with(Position2D::with(10f64, 10f64), Size2D::with(20f64, 20f64)),
+                Viewport2DModification::LooseAspectRatio,
+            )
+            .rasterize(&[geometry])
+    }
+
+    fn assert_pixels(geometry: Geometry2D, expected: impl Fn(usize, usize) -> Pixel) {
+        let pixel_array = rasterize_scaled(geometry);
+        for y in 0..10 {
+            for x in 0..10 {
+                assert_eq!(pixel_array[(x, y)], expected(x, y), "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    /// Asserts that exactly the pixels the predicate holds for are black, all others white.
+    fn assert_covered(geometry: Geometry2D, is_covered: impl Fn(usize, usize) -> bool) {
+        assert_pixels(geometry, |x, y| {
+            if is_covered(x, y) {
+                Pixel::black()
+            } else {
+                Pixel::white()
+            }
+        });
+    }
+
+    /// Returns the offset of the pixel center from the center of the pixel array.
+    fn centered(x: usize, y: usize) -> (f64, f64) {
+        (x as f64 + 0.5f64 - 5f64, y as f64 + 0.5f64 - 5f64)
+    }
+
+    #[test]
+    fn point_covers_its_pixel() {
+        assert_covered(Geometry2D::point(Position2D::with(5f64, 7f64)), |x, y| {
+            (x, y) == (2, 3)
+        });
+    }
+
+    #[test]
+    fn line_covers_its_pixels_with_every_cap() {
+        for (line_shape, start, end) in &[
+            (LineShape::Square, 3f64, 15f64),
+            (LineShape::Round, 3f64, 15f64),
+            (LineShape::Bevel, 2f64, 16f64),
+        ] {
+            let line =
+                Geometry2D::line(Position2D::with(*start, 9f64), Position2D::with(*end, 9f64))
+                    .line_or_border_width(2f64)
+                    .line_shape(line_shape.clone());
+            assert_covered(line, |x, y| y == 4 && (1..=7).contains(&x));
+        }
+    }
+
+    #[test]
+    fn polyline_covers_its_pixels() {
+        let polyline = Geometry2D::polyline(vec![
+            Position2D::with(3f64, 3f64),
+            Position2D::with(15f64, 3f64),
+            Position2D::with(15f64, 15f64),
+        ])
+        .line_or_border_width(2f64);
+        assert_covered(polyline, |x, y| {
+            (y == 1 && (1..=7).contains(&x)) || (x == 7 && (1..=7).contains(&y))
+        });
+    }
+
+    #[test]
+    fn triangle_covers_its_pixels() {
+        let triangle = Geometry2D::triangle(
+            Position2D::with(2.4f64, 2.4f64),
+            Position2D::with(16.8f64, 2.4f64),
+            Position2D::with(2.4f64, 16.8f64),
+        );
+        assert_covered(triangle, |x, y| x >= 1 && y >= 1 && x + y <= 8);
+    }
+
+    #[test]
+    fn square_and_rectangle_cover_their_pixels() {
+        assert_covered(
+            Geometry2D::square(Position2D::with(10f64, 10f64), 8f64),
+            |x, y| (3..=6).contains(&x) && (3..=6).contains(&y),
+        );
+        assert_covered(
+            Geometry2D::rectangle(Position2D::with(10f64, 10f64), Size2D::with(12f64, 4f64)),
+            |x, y| (2..=7).contains(&x) && (4..=5).contains(&y),
+        );
+    }
+
+    #[test]
+    fn border_is_centered_on_the_outline() {
+        let square = Geometry2D::square(Position2D::with(10f64, 10f64), 10f64)
+            .line_or_border_color(Color::with(255, 0, 0, 255))
+            .line_or_border_width(2f64);
+        assert_pixels(square, |x, y| {
+            if (3..=6).contains(&x) && (3..=6).contains(&y) {
+                Pixel::black()
+            } else if (2..=7).contains(&x) && (2..=7).contains(&y) {
+                Pixel::with(255, 0, 0)
+            } else {
+                Pixel::white()
+            }
+        });
+    }
+
+    #[test]
+    fn polygon_covers_its_pixels() {
+        let polygon = Geometry2D::polygon(vec![
+            Position2D::with(2f64, 2f64),
+            Position2D::with(18f64, 2f64),
+            Position2D::with(18f64, 8f64),
+            Position2D::with(8f64, 8f64),
+            Position2D::with(8f64, 18f64),
+            Position2D::with(2f64, 18f64),
+        ]);
+        assert_covered(polygon, |x, y| {
+            ((1..=8).contains(&x) && (1..=3).contains(&y))
+                || ((1..=3).contains(&x) && (1..=8).contains(&y))
+        });
+    }
+
+    #[test]
+    fn circle_and_ellipse_cover_their_pixels() {
+        assert_covered(
+            Geometry2D::circle(Position2D::with(10f64, 10f64), 6.4f64),
+            |x, y| {
+                let (dx, dy) = centered(x, y);
+                dx * dx + dy * dy < 3.2f64 * 3.2f64
+            },
+        );
+        assert_covered(
+            Geometry2D::ellipse(Position2D::with(10f64, 10f64), Size2D::with(16f64, 9.2f64)),
+            |x, y| {
+                let (dx, dy) = centered(x, y);
+                (dx / 4f64).powi(2) + (dy / 2.3f64).powi(2) < 1f64
+            },
+        );
+    }
+
+    #[test]
+    fn text_covers_the_dots_of_its_glyphs() {
+        // with a font size of ten pixels every dot is one pixel
+        let text = Geometry2D::text(Position2D::with(2f64, 0f64), "+".to_string(), 20f64);
+        assert_covered(text, |x, y| {
+            (y == 4 && (1..=5).contains(&x)) || (x == 3 && (2..=6).contains(&y))
+        });
+    }
+
+    #[test]
+    fn image_covers_its_pixels_with_the_nearest_texels() {
+        let texels = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 0]];
+        let data = texels
+            .iter()
+            .flat_map(|[red, green, blue]| vec![*red, *green, *blue, 255])
+            .collect();
+        let image = Geometry2D::image(
+            Position2D::with(10f64, 10f64),
+            Size2D::with(8f64, 8f64),
+            TextureSource::bytes(data, 2, 2),
+        );
+        assert_pixels(image, |x, y| {
+            if (3..=6).contains(&x) && (3..=6).contains(&y) {
+                let [red, green, blue] = texels[(y - 3) / 2 * 2 + (x - 3) / 2];
+                Pixel::with(red, green, blue)
+            } else {
+                Pixel::white()
+            }
+        });
+    }
+
+    #[test]
+    fn empty_images_are_not_drawn() {
+        for (width, height) in &[(0, 0), (0, 3), (3, 0)] {
+            let image = Geometry2D::image(
+                Position2D::with(10f64, 10f64),
+                Size2D::with(8f64, 8f64),
+                TextureSource::bytes(Vec::new(), *width, *height),
+            );
+            assert_covered(image, |_, _| false);
+        }
+    }
+
+    #[test]
+    fn group_draws_all_of_its_geometries() {
+        let group = Geometry2D::group(vec![
+            Geometry2D::point(Position2D::with(5f64, 7f64)),
+            Geometry2D::square(Position2D::with(10f64, 10f64), 8f64),
+        ]);
+        assert_covered(group, |x, y| {
+            (x, y) == (2, 3) || ((3..=6).contains(&x) && (3..=6).contains(&y))
+        });
+    }
+}
@@ -0,0 +1,706 @@
+//! CPU-only rasterization of three dimensional geometries into `PixelArray`s.
+//!
+//! Like the `Rasterizer2D` this works on machines without any GPU or display, e.g. for pixel
+//! based agents or recordings of three dimensional environments.
+
+use std::error::Error;
+
+use gymnarium_base::math::{Position2D, Size2D};
+
+use crate::math3d::{Position3D, Transformation3D, Vector3D};
+use crate::rasterizer::{AntiAliasing, Rasterizer2D};
+use crate::tessellation::{primitives_3d, Affine2D, Face};
+use crate::{
+    BlendMode, Color, Geometry2D, Geometry3D, Material, Pixel, PixelArray, Projection3D,
+    ThreeDimensionalDrawableEnvironment, Viewport2DModification, Viewport3D,
+};
+
+/* --- --- --- Shading --- --- --- */
+
+/// How the surfaces of three dimensional geometries are drawn.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Shading {
+    /// Every triangle is lit once at its center and drawn in a single color.
+    Flat,
+    /// Every triangle is lit at its points using their normals, the colors in between are
+    /// interpolated.
+    #[default]
+    Gouraud,
+    /// Only the edges of the triangles are drawn, in the unlit color of their material.
+    Wireframe,
+}
+
+/* --- --- --- DirectionalLight --- --- --- */
+
+/// Light falling in parallel rays from far away onto every geometry, like sunlight.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectionalLight {
+    /// The direction the light travels into.
+    pub direction: Vector3D,
+    pub color: Color,
+}
+
+impl DirectionalLight {
+    /// Creates a new light travelling into the given direction.
+    ///
+    /// Defaults are `color: Color::white()`.
+    pub fn with(direction: Vector3D) -> Self {
+        Self {
+            direction,
+            color: Color::white(),
+        }
+    }
+
+    pub fn color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
+}
+
+impl Default for DirectionalLight {
+    /// Light from the upper left behind the viewer of the default view.
+    fn default() -> Self {
+        Self::with(Vector3D::with(1f64, -2f64, -3f64))
+    }
+}
+
+/* --- --- --- Rasterizer3D --- --- --- */
+
+/// Renders three dimensional geometries into a `PixelArray` without any GPU.
+///
+/// The projection of the view is stretched to the aspect ratio of the pixel array. Geometries
+/// hide each other by their distance to the camera. Translucent faces are drawn after all opaque
+/// ones and composited onto them without hiding anything behind them. Points are drawn one pixel
+/// in size, lines with their line width in pixels. Back faces of triangles are either culled or
+/// lit from their back side.
+///
+/// Afterwards the two dimensional overlay is drawn on top by a `Rasterizer2D` in the space of
+/// `Viewport3D::viewport_2d`, using the anti-aliasing of this rasterizer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rasterizer3D {
+    pub width: usize,
+    pub height: usize,
+    pub view: Viewport3D,
+    pub background_color: Color,
+    pub light: DirectionalLight,
+    pub shading: Shading,
+    pub back_face_culling: bool,
+    pub anti_aliasing: AntiAliasing,
+}
+
+impl Rasterizer3D {
+    /// Creates a new rasterizer producing pixel arrays with the given resolution.
+    ///
+    /// Defaults are a perspective `view` with a field of view of 60 degrees from
+    /// `Position3D::with(0f64, 0f64, 10f64)` onto the origin, `background_color: Color::white()`,
+    /// `light: DirectionalLight::default()`, `shading: Shading::Gouraud`,
+    /// `back_face_culling: true` and `anti_aliasing: AntiAliasing::None`.
+    pub fn with(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            view: Viewport3D::perspective(
+                Position3D::with(0f64, 0f64, 10f64),
+                Position3D::zero(),
+                60f64,
+            ),
+            background_color: Color::white(),
+            light: DirectionalLight::default(),
+            shading: Shading::Gouraud,
+            back_face_culling: true,
+            anti_aliasing: AntiAliasing::None,
+        }
+    }
+
+    pub fn view(self, view: Viewport3D) -> Self {
+        Self { view, ..self }
+    }
+
+    pub fn background_color(self, background_color: Color) -> Self {
+        Self {
+            background_color,
+            ..self
+        }
+    }
+
+    pub fn light(self, light: DirectionalLight) -> Self {
+        Self { light, ..self }
+    }
+
+    pub fn shading(self, shading: Shading) -> Self {
+        Self { shading, ..self }
+    }
+
+    pub fn back_face_culling(self, back_face_culling: bool) -> Self {
+        Self {
+            back_face_culling,
+            ..self
+        }
+    }
+
+    pub fn anti_aliasing(self, anti_aliasing: AntiAliasing) -> Self {
+        Self {
+            anti_aliasing,
+            ..self
+        }
+    }
+
+    /// Draws the geometries and the overlay onto a new pixel array filled with the background
+    /// color.
+    pub fn rasterize(&self, geometries: &[Geometry3D], overlay: &[Geometry2D]) -> PixelArray {
+        let mut pixel_array = PixelArray {
+            data: vec![
+                Pixel::with(
+                    self.background_color.red,
+                    self.background_color.green,
+                    self.background_color.blue
+                );
+                self.width * self.height
+            ],
+            width: self.width,
+            height: self.height,
+        };
+        self.rasterize_onto(geometries, overlay, &mut pixel_array);
+        pixel_array
+    }
+
+    /// Draws the geometries and the overlay on top of the content of an existing pixel array.
+    ///
+    /// The projection is stretched to the size of the given pixel array instead of the size of
+    /// the rasterizer.
+    pub fn rasterize_onto(
+        &self,
+        geometries: &[Geometry3D],
+        overlay: &[Geometry2D],
+        pixel_array: &mut PixelArray,
+    ) {
+        let (width, height) = (pixel_array.width, pixel_array.height);
+        pixel_array.data.resize(
+            width * height,
+            Pixel::with(
+                self.background_color.red,
+                self.background_color.green,
+                self.background_color.blue,
+            ),
+        );
+        if width == 0 || height == 0 {
+            return;
+        }
+        let viewport = self.view.viewport_2d(width as f64 / height as f64);
+        let layout = Viewport2DModification::LooseAspectRatio
+            .layout(&viewport, &Size2D::with(width as f64, height as f64));
+        let mut target = DepthTarget {
+            view: &self.view,
+            pixel_transform: Affine2D::from_layout(&layout),
+            camera_transform: self.view.view_transformation(),
+            depths: vec![f64::INFINITY; width * height],
+            pixel_array,
+        };
+
+        // translucent faces do not hide anything, so they are drawn over all opaque ones
+        let primitives = primitives_3d(geometries);
+        let (opaque_faces, translucent_faces): (Vec<&Face>, Vec<&Face>) = primitives
+            .faces
+            .iter()
+            .partition(|face| face.material.color.alpha == 255);
+        for face in opaque_faces.into_iter().chain(translucent_faces) {
+            self.draw_face(face, &mut target);
+        }
+        for (points, color, line_width) in &primitives.lines {
+            target.draw_line(points, color, *line_width);
+        }
+        for (position, color) in &primitives.points {
+            target.draw_point(position, color);
+        }
+
+        Rasterizer2D::with(width, height)
+            .viewport(viewport, Viewport2DModification::LooseAspectRatio)
+            .anti_aliasing(self.anti_aliasing)
+            .rasterize_onto(overlay, pixel_array);
+    }
+
+    /// Draws the geometries and the overlay of the given environment.
+    ///
+    /// The preferred view and background color of the environment take precedence over the
+    /// ones of this rasterizer.
+    pub fn rasterize_environment<
+        EnvironmentError: Error,
+        DrawableEnvironment: ThreeDimensionalDrawableEnvironment<EnvironmentError>,
+    >(
+        &self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<PixelArray, EnvironmentError> {
+        let (geometries, overlay) = drawable_environment.draw_three_dimensional()?;
+        let mut rasterizer = self.clone();
+        if let Some(preferred_view) = drawable_environment.preferred_view_3d() {
+            rasterizer.view = preferred_view;
+        }
+        if let Some(background_color) = drawable_environment.preferred_background_color_3d() {
+            rasterizer.background_color = background_color;
+        }
+        Ok(rasterizer.rasterize(&geometries, &overlay))
+    }
+
+    fn draw_face(&self, face: &Face, target: &mut DepthTarget) {
+        let camera_positions = face
+            .positions
+            .map(|position| target.camera_transform.apply(&position));
+        let camera_normal = camera_positions[0]
+            .vector_to(&camera_positions[1])
+            .cross(&camera_positions[0].vector_to(&camera_positions[2]));
+        let is_front = match self.view.projection {
+            Projection3D::Perspective { .. } => {
+                camera_normal.dot(&Position3D::zero().vector_to(&camera_positions[0])) < 0f64
+            }
+            Projection3D::Orthographic { .. } => camera_normal.z > 0f64,
+        };
+        if !is_front && self.back_face_culling {
+            return;
+        }
+
+        if self.shading == Shading::Wireframe {
+            let color = face.material.color;
+            for (start, end) in [(0, 1), (1, 2), (2, 0)] {
+                target.draw_line(&[face.positions[start], face.positions[end]], &color, 1f64);
+            }
+            return;
+        }
+
+        let side = if is_front { 1f64 } else { -1f64 };
+        let colors = if self.shading == Shading::Flat {
+            let center = Position3D::with(
+                face.positions
+                    .iter()
+                    .map(|position| position.x)
+                    .sum::<f64>()
+                    / 3f64,
+                face.positions
+                    .iter()
+                    .map(|position| position.y)
+                    .sum::<f64>()
+                    / 3f64,
+                face.positions
+                    .iter()
+                    .map(|position| position.z)
+                    .sum::<f64>()
+                    / 3f64,
+            );
+            let normal = face.positions[0]
+                .vector_to(&face.positions[1])
+                .cross(&face.positions[0].vector_to(&face.positions[2]))
+                .normalized();
+            [self.lit_color(&face.material, &center, &(normal * side)); 3]
+        } else {
+            [0, 1, 2].map(|index| {
+                self.lit_color(
+                    &face.material,
+                    &face.positions[index],
+                    &(face.normals[index] * side),
+                )
+            })
+        };
+        target.fill_triangle(
+            &camera_positions,
+            &colors,
+            face.material.color.alpha as f32 / 255f32,
+        );
+    }
+
+    /// Returns the color of the material at the position, lit by the ambient light and the
+    /// directional light with the Phong reflection model.
+    fn lit_color(&self, material: &Material, position: &Position3D, normal: &Vector3D) -> [f64; 3] {
+        let to_light = -self.light.direction.normalized();
+        let to_camera = match self.view.projection {
+            Projection3D::Perspective { .. } => position.vector_to(&self.view.camera_position),
+            Projection3D::Orthographic { .. } => {
+                self.view.look_at.vector_to(&self.view.camera_position)
+            }
+        }
+        .normalized();
+        let diffuse = normal.dot(&to_light).max(0f64);
+        let specular = if diffuse > 0f64 && material.specular > 0f64 {
+            let reflection = *normal * (2f64 * normal.dot(&to_light)) - to_light;
+            reflection
+                .dot(&to_camera)
+                .max(0f64)
+                .powf(material.shininess)
+        } else {
+            0f64
+        };
+        let channel = |color: u8, light: u8| {
+            let (color, light) = (color as f64 / 255f64, light as f64 / 255f64);
+            color * (material.ambient + material.diffuse * diffuse * light)
+                + material.specular * specular * light
+        };
+        [
+            channel(material.color.red, self.light.color.red),
+            channel(material.color.green, self.light.color.green),
+            channel(material.color.blue, self.light.color.blue),
+        ]
+    }
+}
+
+/* --- --- --- DepthTarget --- --- --- */
+
+/// The pixel array currently drawn on together with the distance of every pixel to the camera.
+struct DepthTarget<'a> {
+    view: &'a Viewport3D,
+    pixel_transform: Affine2D,
+    camera_transform: Transformation3D,
+    depths: Vec<f64>,
+    pixel_array: &'a mut PixelArray,
+}
+
+/// A point in the space of the camera together with the color at it.
+#[derive(Debug, Copy, Clone)]
+struct CameraVertex {
+    position: Position3D,
+    color: [f64; 3],
+}
+
+/// A point projected onto the pixel array.
+///
+/// Depth and color are divided by `w`, which is the distance for perspective projections and
+/// one otherwise, so they can be interpolated linearly in pixel space.
+#[derive(Debug, Copy, Clone)]
+struct PixelVertex {
+    pixel: Position2D,
+    inverse_w: f64,
+    depth_over_w: f64,
+    color_over_w: [f64; 3],
+}
+
+impl<'a> DepthTarget<'a> {
+    fn project(&self, vertex: &CameraVertex) -> PixelVertex {
+        let distance = -vertex.position.z;
+        let scale = self.view.projection_scale(distance);
+        let inverse_w = match self.view.projection {
+            Projection3D::Perspective { .. } => 1f64 / distance,
+            Projection3D::Orthographic { .. } => 1f64,
+        };
+        PixelVertex {
+            pixel: self.pixel_transform.apply(&Position2D::with(
+                vertex.position.x * scale,
+                vertex.position.y * scale,
+            )),
+            inverse_w,
+            depth_over_w: distance * inverse_w,
+            color_over_w: vertex.color.map(|channel| channel * inverse_w),
+        }
+    }
+
+    /// Cuts away everything of the polygon in front of the near plane.
+    fn clip_near(&self, polygon: &[CameraVertex]) -> Vec<CameraVertex> {
+        let near = self.view.near;
+        let is_inside = |vertex: &CameraVertex| -vertex.position.z >= near;
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (index, current) in polygon.iter().enumerate() {
+            let next = &polygon[(index + 1) % polygon.len()];
+            if is_inside(current) {
+                clipped.push(*current);
+            }
+            if is_inside(current) != is_inside(next) {
+                let t = (-near - current.position.z) / (next.position.z - current.position.z);
+                let lerp = |a: f64, b: f64| a + (b - a) * t;
+                clipped.push(CameraVertex {
+                    position: Position3D::with(
+                        lerp(current.position.x, next.position.x),
+                        lerp(current.position.y, next.position.y),
+                        -near,
+                    ),
+                    color: [0, 1, 2]
+                        .map(|channel| lerp(current.color[channel], next.color[channel])),
+                });
+            }
+        }
+        clipped
+    }
+
+    /// Composites the color onto the pixel if nothing closer to the camera was drawn there.
+    ///
+    /// Only opaque colors hide what is drawn behind them afterwards, fully transparent colors do
+    /// not change the pixel at all.
+    fn set_pixel(&mut self, x: usize, y: usize, depth: f64, color: [f64; 3], alpha: f32) {
+        let index = y * self.pixel_array.width + x;
+        if alpha <= 0f32 || depth > self.view.far || depth >= self.depths[index] {
+            return;
+        }
+        if alpha >= 1f32 {
+            self.depths[index] = depth;
+        }
+        let pixel = &mut self.pixel_array.data[index];
+        *pixel = pixel.blend_channels(
+            color.map(|channel| channel as f32),
+            alpha,
+            BlendMode::SourceOver,
+        );
+    }
+
+    fn fill_triangle(&mut self, positions: &[Position3D; 3], colors: &[[f64; 3]; 3], alpha: f32) {
+        let polygon: Vec<CameraVertex> = (0..3)
+            .map(|index| CameraVertex {
+                position: positions[index],
+                color: colors[index],
+            })
+            .collect();
+        let projected: Vec<PixelVertex> = self
+            .clip_near(&polygon)
+            .iter()
+            .map(|vertex| self.project(vertex))
+            .collect();
+        for index in 2..projected.len() {
+            self.fill_projected_triangle(
+                [&projected[0], &projected[index - 1], &projected[index]],
+                alpha,
+            );
+        }
+    }
+
+    fn fill_projected_triangle(&mut self, vertices: [&PixelVertex; 3], alpha: f32) {
+        let edge = |a: &Position2D, b: &Position2D, c: &Position2D| {
+            (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+        };
+        let [a, b, c] = vertices.map(|vertex| vertex.pixel);
+        let area = edge(&a, &b, &c);
+        if area == 0f64 || !area.is_finite() {
+            return;
+        }
+        let (left, top, right, bottom) = match self.pixel_area(&[a, b, c], 0f64) {
+            Some(area) => area,
+            None => return,
+        };
+        for y in top..bottom {
+            for x in left..right {
+                let center = Position2D::with(x as f64 + 0.5f64, y as f64 + 0.5f64);
+                let weights = [
+                    edge(&b, &c, &center) / area,
+                    edge(&c, &a, &center) / area,
+                    edge(&a, &b, &center) / area,
+                ];
+                if weights.iter().any(|weight| *weight < 0f64) {
+                    continue;
+                }
+                let interpolate = |value: &dyn Fn(&PixelVertex) -> f64| -> f64 {
+                    (0..3)
+                        .map(|index| weights[index] * value(vertices[index]))
+                        .sum()
+                };
+                let inverse_w = interpolate(&|vertex| vertex.inverse_w);
+                let depth = interpolate(&|vertex| vertex.depth_over_w) / inverse_w;
+                let color = [0, 1, 2]
+                    .map(|channel| interpolate(&|vertex| vertex.color_over_w[channel]) / inverse_w);
+                self.set_pixel(x, y, depth, color, alpha);
+            }
+        }
+    }
+
+    fn draw_line(&mut self, points: &[Position3D; 2], color: &Color, line_width: f64) {
+        let polygon: Vec<CameraVertex> = points
+            .iter()
+            .map(|point| CameraVertex {
+                position: self.camera_transform.apply(point),
+                color: [0f64; 3],
+            })
+            .collect();
+        // clipping the segment like a polygon yields it twice, with one more point if clipped
+        let clipped = self.clip_near(&polygon);
+        if clipped.len() < 2 {
+            return;
+        }
+        let (start, end) = (self.project(&clipped[0]), self.project(&clipped[1]));
+        let half_width = line_width.abs() / 2f64;
+        let (left, top, right, bottom) =
+            match self.pixel_area(&[start.pixel, end.pixel], half_width) {
+                Some(area) => area,
+                None => return,
+            };
+        let direction = (end.pixel.x - start.pixel.x, end.pixel.y - start.pixel.y);
+        let length_squared = direction.0 * direction.0 + direction.1 * direction.1;
+        let channels = opaque_channels(color);
+        for y in top..bottom {
+            for x in left..right {
+                let (offset_x, offset_y) = (
+                    x as f64 + 0.5f64 - start.pixel.x,
+                    y as f64 + 0.5f64 - start.pixel.y,
+                );
+                let t = if length_squared > 0f64 {
+                    ((offset_x * direction.0 + offset_y * direction.1) / length_squared)
+                        .clamp(0f64, 1f64)
+                } else {
+                    0f64
+                };
+                let (distance_x, distance_y) =
+                    (offset_x - t * direction.0, offset_y - t * direction.1);
+                if distance_x * distance_x + distance_y * distance_y > half_width * half_width {
+                    continue;
+                }
+                let inverse_w = start.inverse_w + t * (end.inverse_w - start.inverse_w);
+                let depth =
+                    (start.depth_over_w + t * (end.depth_over_w - start.depth_over_w)) / inverse_w;
+                self.set_pixel(x, y, depth, channels, color.alpha as f32 / 255f32);
+            }
+        }
+    }
+
+    fn draw_point(&mut self, position: &Position3D, color: &Color) {
+        let vertex = CameraVertex {
+            position: self.camera_transform.apply(position),
+            color: [0f64; 3],
+        };
+        if -vertex.position.z < self.view.near {
+            return;
+        }
+        let projected = self.project(&vertex);
+        let (x, y) = (projected.pixel.x.floor(), projected.pixel.y.floor());
+        if x >= 0f64
+            && y >= 0f64
+            && x < self.pixel_array.width as f64
+            && y < self.pixel_array.height as f64
+        {
+            self.set_pixel(
+                x as usize,
+                y as usize,
+                -vertex.position.z,
+                opaque_channels(color),
+                color.alpha as f32 / 255f32,
+            );
+        }
+    }
+
+    /// Returns the pixels whose centers may lie within the margin around the positions as
+    /// `(left, top, right, bottom)`, with `right` and `bottom` being exclusive.
+    fn pixel_area(
+        &self,
+        positions: &[Position2D],
+        margin: f64,
+    ) -> Option<(usize, usize, usize, usize)> {
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for position in positions {
+            if !position.x.is_finite() || !position.y.is_finite() {
+                return None;
+            }
+            min_x = min_x.min(position.x - margin);
+            min_y = min_y.min(position.y - margin);
+            max_x = max_x.max(position.x + margin);
+            max_y = max_y.max(position.y + margin);
+        }
+        let (width, height) = (
+            self.pixel_array.width as f64,
+            self.pixel_array.height as f64,
+        );
+        if max_x < 0f64 || max_y < 0f64 || min_x > width || min_y > height {
+            return None;
+        }
+        Some((
+            min_x.floor().max(0f64) as usize,
+            min_y.floor().max(0f64) as usize,
+            max_x.ceil().min(width) as usize,
+            max_y.ceil().min(height) as usize,
+        ))
+    }
+}
+
+fn opaque_channels(color: &Color) -> [f64; 3] {
+    [
+        color.red as f64 / 255f64,
+        color.green as f64 / 255f64,
+        color.blue as f64 / 255f64,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawableEnvironment, TwoDimensionalDrawableEnvironment, Viewport2D};
+
+    fn square(z: f64, color: Color) -> Geometry3D {
+        Geometry3D::triangle(
+            Position3D::with(-4f64, -4f64, z),
+            Position3D::with(4f64, -4f64, z),
+            Position3D::with(0f64, 4f64, z),
+        )
+        .material(Material::with(color).ambient(1f64).diffuse(0f64))
+    }
+
+    fn center_pixel(geometries: &[Geometry3D]) -> Pixel {
+        let pixel_array = Rasterizer3D::with(8, 8)
+            .view(Viewport3D::orthographic(
+                Position3D::with(0f64, 0f64, 10f64),
+                Position3D::zero(),
+                2f64,
+            ))
+            .back_face_culling(false)
+            .shading(Shading::Flat)
+            .rasterize(geometries, &[]);
+        pixel_array.data[4 * 8 + 4]
+    }
+
+    #[test]
+    fn translucent_faces_do_not_hide_faces_behind_them() {
+        let opaque = square(0f64, Color::with(255, 0, 0, 255));
+        let translucent = square(1f64, Color::with(0, 0, 255, 128));
+        let blended = center_pixel(&[opaque.clone(), translucent.clone()]);
+        assert!(blended.red > 0 && blended.blue > 0 && blended.green == 0);
+        assert_eq!(center_pixel(&[translucent, opaque]), blended);
+    }
+
+    #[test]
+    fn opaque_faces_hide_faces_behind_them() {
+        let near = square(1f64, Color::with(0, 0, 255, 255));
+        let far = square(0f64, Color::with(255, 0, 0, 255));
+        assert_eq!(
+            center_pixel(&[near.clone(), far.clone()]),
+            Pixel::with(0, 0, 255)
+        );
+        assert_eq!(center_pixel(&[far, near]), Pixel::with(0, 0, 255));
+    }
+
+    struct BothEnvironments;
+
+    impl DrawableEnvironment for BothEnvironments {
+        fn suggested_rendered_steps_per_second() -> Option<f64> {
+            None
+        }
+    }
+
+    impl TwoDimensionalDrawableEnvironment<std::fmt::Error> for BothEnvironments {
+        fn draw_two_dimensional(&self) -> Result<Vec<Geometry2D>, std::fmt::Error> {
+            Ok(Vec::new())
+        }
+
+        fn preferred_view(&self) -> Option<(Viewport2D, Viewport2DModification)> {
+            None
+        }
+
+        fn preferred_background_color(&self) -> Option<Color> {
+            Some(Color::with(255, 0, 0, 255))
+        }
+    }
+
+    impl ThreeDimensionalDrawableEnvironment<std::fmt::Error> for BothEnvironments {
+        fn draw_three_dimensional(
+            &self,
+        ) -> Result<(Vec<Geometry3D>, Vec<Geometry2D>), std::fmt::Error> {
+            Ok((Vec::new(), Vec::new()))
+        }
+
+        fn preferred_background_color_3d(&self) -> Option<Color> {
+            Some(Color::with(0, 0, 255, 255))
+        }
+    }
+
+    #[test]
+    fn environments_can_be_drawn_in_two_and_three_dimensions() {
+        let environment = BothEnvironments;
+        assert_eq!(environment.preferred_view(), None);
+        assert_eq!(environment.preferred_view_3d(), None);
+        let two_dimensional = Rasterizer2D::with(1, 1)
+            .rasterize_environment(&environment)
+            .unwrap();
+        let three_dimensional = Rasterizer3D::with(1, 1)
+            .rasterize_environment(&environment)
+            .unwrap();
+        assert_eq!(two_dimensional.data, vec![Pixel::with(255, 0, 0)]);
+        assert_eq!(three_dimensional.data, vec![Pixel::with(0, 0, 255)]);
+    }
+}
@@ -0,0 +1,714 @@
+//! Visualisers recording rendered frames instead of showing them.
+//!
+//! The `AnimationRecorder` writes a single animation when it is closed, while the
+//! `FrameSequenceRecorder` writes every frame immediately, e.g. for an external video encoder.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::image::{check_size, save, write_animation, AnimationFormat, ImageError, ImageFormat};
+use crate::rasterizer::Rasterizer2D;
+use crate::{
+    PixelArray, PixelArrayDrawableEnvironment, PixelArrayVisualiser,
+    TwoDimensionalDrawableEnvironment, TwoDimensionalVisualiser, Visualiser,
+};
+
+/// Frame rate used if neither the recorder nor the environment specify one.
+pub const DEFAULT_FRAMES_PER_SECOND: f64 = 30f64;
+
+/* --- --- --- RecorderError --- --- --- */
+
+#[derive(Debug)]
+pub enum RecorderError<DrawableEnvironmentError: Error> {
+    /// The recorder has already been closed.
+    Closed,
+    Environment(DrawableEnvironmentError),
+    Image(ImageError),
+}
+
+impl<DrawableEnvironmentError: Error> Display for RecorderError<DrawableEnvironmentError> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Closed => write!(f, "The recorder has already been closed"),
+            Self::Environment(error) => write!(f, "Could not draw environment: {}", error),
+            Self::Image(error) => write!(f, "Could not write frame: {}", error),
+        }
+    }
+}
+
+impl<DrawableEnvironmentError: Error> Error for RecorderError<DrawableEnvironmentError> {}
+
+impl<DrawableEnvironmentError: Error> From<ImageError> for RecorderError<DrawableEnvironmentError> {
+    fn from(error: ImageError) -> Self {
+        Self::Image(error)
+    }
+}
+
+/* --- --- --- AnimationRecorder --- --- --- */
+
+/// Records one frame per render call and writes them as animated GIF or APNG on `close`.
+///
+/// Two dimensional environments are rasterized with the given `Rasterizer2D`. The frame rate
+/// is taken from `frames_per_second`, otherwise from
+/// `DrawableEnvironment::suggested_rendered_steps_per_second` of the last rendered environment
+/// and otherwise `DEFAULT_FRAMES_PER_SECOND` is used.
+///
+/// All frames are kept in memory until the recorder is closed. Nothing is written if the
+/// recorder is dropped without being closed or if no frame has been rendered. If writing the
+/// animation fails, the recorder stays open with all of its frames, so closing can be retried.
+#[derive(Debug, Clone)]
+pub struct AnimationRecorder {
+    path: PathBuf,
+    format: AnimationFormat,
+    rasterizer: Rasterizer2D,
+    frames_per_second: Option<f64>,
+    suggested_frames_per_second: Option<f64>,
+    frames: Vec<PixelArray>,
+    open: bool,
+}
+
+impl AnimationRecorder {
+    /// Creates a recorder writing to the given path, the format is chosen by its extension.
+    ///
+    /// Two dimensional environments are rasterized in the given size.
+    pub fn with<P: AsRef<Path>>(path: P, width: usize, height: usize) -> Result<Self, ImageError> {
+        let format = AnimationFormat::from_path(&path).ok_or_else(|| {
+            ImageError::Unsupported(format!(
+                "no animation format for extension of {}",
+                path.as_ref().display()
+            ))
+        })?;
+        Ok(Self::with_format(path, format, width, height))
+    }
+
+    /// Defaults are `rasterizer: Rasterizer2D::with(width, height)` and
+    /// `frames_per_second: None`.
+    pub fn with_format<P: AsRef<Path>>(
+        path: P,
+        format: AnimationFormat,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            format,
+            rasterizer: Rasterizer2D::with(width, height),
+            frames_per_second: None,
+            suggested_frames_per_second: None,
+            frames: Vec::new(),
+            open: true,
+        }
+    }
+
+    pub fn rasterizer(self, rasterizer: Rasterizer2D) -> Self {
+        Self { rasterizer, ..self }
+    }
+
+    /// Overrides the frame rate suggested by the environments.
+    pub fn frames_per_second(self, frames_per_second: f64) -> Self {
+        Self {
+            frames_per_second: Some(frames_per_second),
+            ..self
+        }
+    }
+
+    pub fn frames(&self) -> &[PixelArray] {
+        &self.frames
+    }
+
+    fn record<DrawableEnvironment: crate::DrawableEnvironment, DrawableEnvironmentError: Error>(
+        &mut self,
+        frame: Result<PixelArray, DrawableEnvironmentError>,
+    ) -> Result<(), RecorderError<DrawableEnvironmentError>> {
+        if !self.open {
+            return Err(RecorderError::Closed);
+        }
+        self.frames.push(frame.map_err(RecorderError::Environment)?);
+        if let Some(frames_per_second) = DrawableEnvironment::suggested_rendered_steps_per_second()
+        {
+            self.suggested_frames_per_second = Some(frames_per_second);
+        }
+        Ok(())
+    }
+}
+
+impl Visualiser<ImageError> for AnimationRecorder {
+    fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn close(&mut self) -> Result<(), ImageError> {
+        if !self.open {
+            return Ok(());
+        }
+        if !self.frames.is_empty() {
+            let frames_per_second = self
+                .frames_per_second
+                .or(self.suggested_frames_per_second)
+                .unwrap_or(DEFAULT_FRAMES_PER_SECOND);
+            let mut writer = BufWriter::new(File::create(&self.path)?);
+            write_animation(&self.frames, frames_per_second, self.format, &mut writer)?;
+            writer.flush()?;
+            self.frames = Vec::new();
+        }
+        self.open = false;
+        Ok(())
+    }
+}
+
+impl<DrawableEnvironmentError: Error>
+    PixelArrayVisualiser<
+        RecorderError<DrawableEnvironmentError>,
+        ImageError,
+        DrawableEnvironmentError,
+    > for AnimationRecorder
+{
+    fn render_pixel_array<
+        DrawableEnvironment: PixelArrayDrawableEnvironment<DrawableEnvironmentError>,
+    >(
+        &mut self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<(), RecorderError<DrawableEnvironmentError>> {
+        self.record::<DrawableEnvironment, _>(drawable_environment.draw_pixel_array())
+    }
+}
+
+impl<DrawableEnvironmentError: Error>
+    TwoDimensionalVisualiser<
+        RecorderError<DrawableEnvironmentError>,
+        ImageError,
+        DrawableEnvironmentError,
+    > for AnimationRecorder
+{
+    fn render_two_dimensional<
+        DrawableEnvironment: TwoDimensionalDrawableEnvironment<DrawableEnvironmentError>,
+    >(
+        &mut self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<(), RecorderError<DrawableEnvironmentError>> {
+        let frame = self.rasterizer.rasterize_environment(drawable_environment);
+        self.record::<DrawableEnvironment, _>(frame)
+    }
+}
+
+/* --- --- --- FrameOutput --- --- --- */
+
+/// Where and how the `FrameSequenceRecorder` writes its frames.
+#[derive(Debug)]
+pub enum FrameOutput<W: Write> {
+    /// One image per frame named `{prefix}{frame number:06}.{extension}` inside the directory,
+    /// which is created with the first frame if it does not exist.
+    Files {
+        directory: PathBuf,
+        prefix: String,
+        format: ImageFormat,
+    },
+    /// A YUV4MPEG2 stream with full resolution chroma (`C444`), readable by ffmpeg as is.
+    Y4m(W),
+    /// Headerless rgb24 pixels, ffmpeg needs to be told the size and rate
+    /// (`-f rawvideo -pix_fmt rgb24 -s WIDTHxHEIGHT -r RATE`).
+    RawRgb24(W),
+}
+
+/* --- --- --- FrameSequenceRecorder --- --- --- */
+
+/// Writes every rendered frame immediately, as numbered image files or into a video stream.
+///
+/// Two dimensional environments are rasterized with the given `Rasterizer2D`. Only every
+/// `frame_interval`-th render call is recorded and render calls after `maximum_frames` recorded
+/// frames are ignored, so long runs do not fill up the disk.
+///
+/// Streams require all frames to have the same size. The frame rate of a Y4M stream is taken
+/// from `frames_per_second`, otherwise from
+/// `DrawableEnvironment::suggested_rendered_steps_per_second` divided by the frame interval and
+/// otherwise `DEFAULT_FRAMES_PER_SECOND` is used.
+#[derive(Debug)]
+pub struct FrameSequenceRecorder<W: Write> {
+    output: FrameOutput<W>,
+    rasterizer: Rasterizer2D,
+    frame_interval: usize,
+    maximum_frames: Option<usize>,
+    frames_per_second: Option<f64>,
+    frame_size: Option<(usize, usize)>,
+    rendered_frames: usize,
+    recorded_frames: usize,
+    open: bool,
+}
+
+impl FrameSequenceRecorder<std::io::Sink> {
+    /// Creates a recorder writing one image file per frame into the directory.
+    pub fn files<P: AsRef<Path>>(
+        directory: P,
+        prefix: String,
+        format: ImageFormat,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        Self::with(
+            FrameOutput::Files {
+                directory: directory.as_ref().to_path_buf(),
+                prefix,
+                format,
+            },
+            width,
+            height,
+        )
+    }
+}
+
+impl<W: Write> FrameSequenceRecorder<W> {
+    /// Two dimensional environments are rasterized in the given size.
+    ///
+    /// Defaults are `rasterizer: Rasterizer2D::with(width, height)`, `frame_interval: 1`,
+    /// `maximum_frames: None` and `frames_per_second: None`.
+    pub fn with(output: FrameOutput<W>, width: usize, height: usize) -> Self {
+        Self {
+            output,
+            rasterizer: Rasterizer2D::with(width, height),
+            frame_interval: 1,
+            maximum_frames: None,
+            frames_per_second: None,
+            frame_size: None,
+            rendered_frames: 0,
+            recorded_frames: 0,
+            open: true,
+        }
+    }
+
+    pub fn rasterizer(self, rasterizer: Rasterizer2D) -> Self {
+        Self { rasterizer, ..self }
+    }
+
+    /// Records only every n-th render call, starting with the first one.
+    pub fn frame_interval(self, frame_interval: usize) -> Self {
+        Self {
+            frame_interval: frame_interval.max(1),
+            ..self
+        }
+    }
+
+    pub fn maximum_frames(self, maximum_frames: usize) -> Self {
+        Self {
+            maximum_frames: Some(maximum_frames),
+            ..self
+        }
+    }
+
+    /// Overrides the frame rate suggested by the environments.
+    pub fn frames_per_second(self, frames_per_second: f64) -> Self {
+        Self {
+            frames_per_second: Some(frames_per_second),
+            ..self
+        }
+    }
+
+    pub fn recorded_frames(&self) -> usize {
+        self.recorded_frames
+    }
+
+    /// Returns whether the next render call is going to be recorded.
+    fn wants_frame(&mut self) -> bool {
+        let wanted = self.rendered_frames % self.frame_interval == 0
+            && self
+                .maximum_frames
+                .map(|maximum_frames| self.recorded_frames < maximum_frames)
+                .unwrap_or(true);
+        self.rendered_frames += 1;
+        wanted
+    }
+
+    fn record<DrawableEnvironment: crate::DrawableEnvironment, DrawableEnvironmentError: Error>(
+        &mut self,
+        frame: Result<PixelArray, DrawableEnvironmentError>,
+    ) -> Result<(), RecorderError<DrawableEnvironmentError>> {
+        let frame = frame.map_err(RecorderError::Environment)?;
+        check_size(&frame)?;
+        if let FrameOutput::Y4m(_) | FrameOutput::RawRgb24(_) = self.output {
+            match self.frame_size {
+                Some(frame_size) if frame_size != (frame.width, frame.height) => {
+                    return Err(RecorderError::Image(ImageError::Unsupported(format!(
+                        "frame of size {}x{} in stream of size {}x{}",
+                        frame.width, frame.height, frame_size.0, frame_size.1
+                    ))))
+                }
+                _ => {}
+            }
+        }
+
+        let frames_per_second = self
+            .frames_per_second
+            .or_else(|| {
+                DrawableEnvironment::suggested_rendered_steps_per_second()
+                    .map(|steps_per_second| steps_per_second / self.frame_interval as f64)
+            })
+            .unwrap_or(DEFAULT_FRAMES_PER_SECOND);
+        match &mut self.output {
+            FrameOutput::Files {
+                directory,
+                prefix,
+                format,
+            } => {
+                if self.recorded_frames == 0 {
+                    std::fs::create_dir_all(&directory).map_err(ImageError::from)?;
+                }
+                save(
+                    &frame,
+                    directory.join(format!(
+                        "{}{:06}.{}",
+                        prefix,
+                        self.recorded_frames,
+                        format.extension()
+                    )),
+                )?
+            }
+            FrameOutput::Y4m(writer) => {
+                if self.frame_size.is_none() {
+                    writeln!(
+                        writer,
+                        "YUV4MPEG2 W{} H{} F{}:1000 Ip A1:1 C444",
+                        frame.width,
+                        frame.height,
+                        (frames_per_second * 1000f64).round().max(1f64) as u64
+                    )
+                    .map_err(ImageError::from)?;
+                }
+                writer.write_all(b"FRAME\n").map_err(ImageError::from)?;
+                writer
+                    .write_all(&y4m_planes(&frame))
+                    .map_err(ImageError::from)?;
+            }
+            FrameOutput::RawRgb24(writer) => {
+                let rgb: Vec<u8> = frame
+                    .data
+                    .iter()
+                    .flat_map(|pixel| vec![pixel.red, pixel.green, pixel.blue])
+                    .collect();
+                writer.write_all(&rgb).map_err(ImageError::from)?;
+            }
+        }
+        self.frame_size = Some((frame.width, frame.height));
+        self.recorded_frames += 1;
+        Ok(())
+    }
+}
+
+/// Converts the pixels into the Y, Cb and Cr planes of limited range BT.601.
+fn y4m_planes(frame: &PixelArray) -> Vec<u8> {
+    let pixel_count = frame.data.len();
+    let mut planes = vec![0u8; pixel_count * 3];
+    for (index, pixel) in frame.data.iter().enumerate() {
+        let (red, green, blue) = (pixel.red as f64, pixel.green as f64, pixel.blue as f64);
+        planes[index] =
+            (16f64 + (65.481 * red + 128.553 * green + 24.966 * blue) / 255f64).round() as u8;
+        planes[pixel_count + index] =
+            (128f64 + (-37.797 * red - 74.203 * green + 112.0 * blue) / 255f64).round() as u8;
+        planes[2 * pixel_count + index] =
+            (128f64 + (112.0 * red - 93.786 * green - 18.214 * blue) / 255f64).round() as u8;
+    }
+    planes
+}
+
+impl<W: Write> Visualiser<ImageError> for FrameSequenceRecorder<W> {
+    fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn close(&mut self) -> Result<(), ImageError> {
+        if self.open {
+            self.open = false;
+            if let FrameOutput::Y4m(writer) | FrameOutput::RawRgb24(writer) = &mut self.output {
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write, DrawableEnvironmentError: Error>
+    PixelArrayVisualiser<
+        RecorderError<DrawableEnvironmentError>,
+        ImageError,
+        DrawableEnvironmentError,
+    > for FrameSequenceRecorder<W>
+{
+    fn render_pixel_array<
+        DrawableEnvironment: PixelArrayDrawableEnvironment<DrawableEnvironmentError>,
+    >(
+        &mut self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<(), RecorderError<DrawableEnvironmentError>> {
+        if !self.open {
+            return Err(RecorderError::Closed);
+        }
+        if !self.wants_frame() {
+            return Ok(());
+        }
+        self.record::<DrawableEnvironment, _>(drawable_environment.draw_pixel_array())
+    }
+}
+
+impl<W: Write, DrawableEnvironmentError: Error>
+    TwoDimensionalVisualiser<
+        RecorderError<DrawableEnvironmentError>,
+        ImageError,
+        DrawableEnvironmentError,
+    > for FrameSequenceRecorder<W>
+{
+    fn render_two_dimensional<
+        DrawableEnvironment: TwoDimensionalDrawableEnvironment<DrawableEnvironmentError>,
+    >(
+        &mut self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<(), RecorderError<DrawableEnvironmentError>> {
+        if !self.open {
+            return Err(RecorderError::Closed);
+        }
+        if !self.wants_frame() {
+            return Ok(());
+        }
+        let frame = self.rasterizer.rasterize_environment(drawable_environment);
+        self.record::<DrawableEnvironment, _>(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::read_png;
+    use crate::image::tests::decode_gif;
+    use crate::{DrawableEnvironment, Pixel};
+
+    /// An environment drawing two by two pixels with the given gray value.
+    struct Frame(u8);
+
+    impl DrawableEnvironment for Frame {
+        fn suggested_rendered_steps_per_second() -> Option<f64> {
+            Some(10f64)
+        }
+    }
+
+    impl PixelArrayDrawableEnvironment<std::fmt::Error> for Frame {
+        fn draw_pixel_array(&self) -> Result<PixelArray, std::fmt::Error> {
+            Ok(PixelArray {
+                data: vec![Pixel::with(self.0, self.0, self.0); 4],
+                width: 2,
+                height: 2,
+            })
+        }
+    }
+
+    /// An environment drawing the given pixel array.
+    struct Image(PixelArray);
+
+    impl DrawableEnvironment for Image {
+        fn suggested_rendered_steps_per_second() -> Option<f64> {
+            None
+        }
+    }
+
+    impl PixelArrayDrawableEnvironment<std::fmt::Error> for Image {
+        fn draw_pixel_array(&self) -> Result<PixelArray, std::fmt::Error> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn image() -> PixelArray {
+        PixelArray {
+            data: vec![
+                Pixel::with(1, 2, 3),
+                Pixel::with(4, 5, 6),
+                Pixel::with(7, 8, 9),
+                Pixel::with(10, 11, 12),
+                Pixel::white(),
+                Pixel::black(),
+            ],
+            width: 3,
+            height: 2,
+        }
+    }
+
+    fn directory(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "gymnarium_recorder_{}_{}",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn record_animation(path: &Path, format: AnimationFormat) -> AnimationRecorder {
+        let mut recorder = AnimationRecorder::with_format(path, format, 2, 2);
+        for value in &[0, 100, 200] {
+            recorder.render_pixel_array(&Frame(*value)).unwrap();
+        }
+        recorder
+    }
+
+    #[test]
+    fn gif_frames_round_trip() {
+        let directory = directory("gif");
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("animation.gif");
+        let mut recorder = record_animation(&path, AnimationFormat::Gif);
+        recorder.close().unwrap();
+        assert!(!recorder.is_open());
+        assert!(recorder.frames().is_empty());
+
+        let frames = decode_gif(&std::fs::read(&path).unwrap());
+        std::fs::remove_dir_all(&directory).unwrap();
+        let expected: Vec<(u16, Vec<Pixel>)> = [0, 100, 200]
+            .iter()
+            .map(|value| (10, vec![Pixel::with(*value, *value, *value); 4]))
+            .collect();
+        assert_eq!(frames, expected);
+    }
+
+    #[test]
+    fn apng_frames_round_trip() {
+        let directory = directory("apng");
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("animation.png");
+        record_animation(&path, AnimationFormat::Apng)
+            .frames_per_second(4f64)
+            .close()
+            .unwrap();
+
+        let apng = std::fs::read(&path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        let first_frame = read_png(&mut apng.as_slice()).unwrap();
+        assert_eq!(first_frame.data, vec![Pixel::black(); 4]);
+        let animation_control = apng
+            .windows(4)
+            .position(|window| window == b"acTL")
+            .unwrap();
+        assert_eq!(
+            &apng[animation_control + 4..animation_control + 8],
+            &3u32.to_be_bytes()
+        );
+        // the delay of 250/1000 s of the first frame control chunk
+        let frame_control = apng
+            .windows(4)
+            .position(|window| window == b"fcTL")
+            .unwrap();
+        assert_eq!(
+            &apng[frame_control + 24..frame_control + 28],
+            &[0, 250, 3, 232]
+        );
+    }
+
+    #[test]
+    fn failed_close_keeps_the_frames() {
+        let directory = directory("retry");
+        let path = directory.join("animation.gif");
+        let mut recorder = record_animation(&path, AnimationFormat::Gif);
+        assert!(recorder.close().is_err());
+        assert!(recorder.is_open());
+        assert_eq!(recorder.frames().len(), 3);
+
+        std::fs::create_dir_all(&directory).unwrap();
+        recorder.close().unwrap();
+        assert_eq!(decode_gif(&std::fs::read(&path).unwrap()).len(), 3);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    fn raw_frames(recorder: FrameSequenceRecorder<Vec<u8>>) -> Vec<u8> {
+        match recorder.output {
+            FrameOutput::RawRgb24(data) => data.chunks(12).map(|frame| frame[0]).collect(),
+            _ => panic!("expected raw output"),
+        }
+    }
+
+    #[test]
+    fn frame_interval_and_maximum_frames_limit_the_recorded_frames() {
+        let mut recorder =
+            FrameSequenceRecorder::with(FrameOutput::RawRgb24(Vec::new()), 2, 2).frame_interval(3);
+        for value in 0..10 {
+            recorder.render_pixel_array(&Frame(value)).unwrap();
+        }
+        assert_eq!(recorder.recorded_frames(), 4);
+        assert_eq!(raw_frames(recorder), vec![0, 3, 6, 9]);
+
+        let mut recorder = FrameSequenceRecorder::with(FrameOutput::RawRgb24(Vec::new()), 2, 2)
+            .frame_interval(2)
+            .maximum_frames(3);
+        for value in 0..10 {
+            recorder.render_pixel_array(&Frame(value)).unwrap();
+        }
+        assert_eq!(recorder.recorded_frames(), 3);
+        assert_eq!(raw_frames(recorder), vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn files_are_numbered_inside_a_created_directory() {
+        let directory = directory("files");
+        let frames = directory.join("nested");
+        let mut recorder =
+            FrameSequenceRecorder::files(&frames, "step_".to_string(), ImageFormat::Ppm, 3, 2)
+                .frame_interval(2);
+        for _ in 0..4 {
+            recorder.render_pixel_array(&Image(image())).unwrap();
+        }
+
+        let mut names: Vec<String> = std::fs::read_dir(&frames)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        let loaded = crate::image::load(frames.join("step_000001.ppm")).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(names, vec!["step_000000.ppm", "step_000001.ppm"]);
+        assert_eq!(loaded, image());
+    }
+
+    #[test]
+    fn y4m_streams_start_with_their_header() {
+        let mut recorder = FrameSequenceRecorder::with(FrameOutput::Y4m(Vec::new()), 3, 2);
+        recorder.render_pixel_array(&Image(image())).unwrap();
+        recorder.render_pixel_array(&Image(image())).unwrap();
+        let stream = match recorder.output {
+            FrameOutput::Y4m(stream) => stream,
+            _ => panic!("expected a Y4M stream"),
+        };
+
+        let header = b"YUV4MPEG2 W3 H2 F30000:1000 Ip A1:1 C444\n";
+        let frame_length = b"FRAME\n".len() + 3 * 2 * 3;
+        assert!(stream.starts_with(header));
+        assert_eq!(stream.len(), header.len() + 2 * frame_length);
+        let frame = &stream[header.len()..header.len() + frame_length];
+        assert!(frame.starts_with(b"FRAME\n"));
+        // the luma plane of white and black pixels uses the limited range
+        assert_eq!(&frame[6 + 4..6 + 6], &[235, 16]);
+        // the chroma of gray pixels is neutral
+        assert_eq!(&frame[6 + 6 + 4..6 + 6 + 6], &[128, 128]);
+        assert_eq!(&frame[6 + 12 + 4..6 + 12 + 6], &[128, 128]);
+    }
+
+    #[test]
+    fn y4m_streams_use_the_frame_rate_of_the_environment() {
+        let mut recorder =
+            FrameSequenceRecorder::with(FrameOutput::Y4m(Vec::new()), 2, 2).frame_interval(4);
+        recorder.render_pixel_array(&Frame(0)).unwrap();
+        match recorder.output {
+            FrameOutput::Y4m(stream) => {
+                assert!(stream.starts_with(b"YUV4MPEG2 W2 H2 F2500:1000 "))
+            }
+            _ => panic!("expected a Y4M stream"),
+        }
+    }
+
+    #[test]
+    fn raw_streams_hold_rgb24_rows() {
+        let mut recorder = FrameSequenceRecorder::with(FrameOutput::RawRgb24(Vec::new()), 3, 2);
+        recorder.render_pixel_array(&Image(image())).unwrap();
+        let bytes = match recorder.output {
+            FrameOutput::RawRgb24(bytes) => bytes,
+            _ => panic!("expected a raw stream"),
+        };
+        assert_eq!(
+            bytes,
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 255, 255, 255, 0, 0, 0]
+        );
+    }
+}
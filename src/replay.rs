@@ -0,0 +1,314 @@
+//! Replaying recorded scenes with any `TwoDimensionalVisualiser`.
+//!
+//! The `ScenePlayer` holds the recorded frames and can be controlled directly or with input
+//! events:
+//!
+//! | Key          | Action                                   |
+//! |--------------|------------------------------------------|
+//! | `Space`      | toggle between playing and pausing       |
+//! | `Right`      | pause and step one frame forward         |
+//! | `Left`       | pause and step one frame backward        |
+//! | `Up`         | double the playback speed                |
+//! | `Down`       | halve the playback speed                 |
+//! | `Home`       | jump to the first frame                  |
+//! | `End`        | jump to the last frame                   |
+
+use std::convert::Infallible;
+use std::error::Error;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+use crate::input::{Button, ButtonArgs, ButtonState, Input, Key};
+use crate::recorder::DEFAULT_FRAMES_PER_SECOND;
+use crate::scene::Scene;
+use crate::scene_stream::{SceneStreamError, SceneStreamReader};
+use crate::{InputProvider, TwoDimensionalVisualiser};
+
+/// Slowest playback speed accepted by `speed` and reachable with `slower`.
+pub const MINIMUM_SPEED: f64 = 1f64 / 16f64;
+/// Fastest playback speed accepted by `speed` and reachable with `faster`.
+pub const MAXIMUM_SPEED: f64 = 16f64;
+/// Lowest frame rate accepted by `frames_per_second`.
+pub const MINIMUM_FRAMES_PER_SECOND: f64 = 1f64 / 60f64;
+/// Highest frame rate accepted by `frames_per_second`.
+pub const MAXIMUM_FRAMES_PER_SECOND: f64 = 1000f64;
+/// Longest time `run` waits before handling inputs again.
+const MAXIMUM_SLEEP: Duration = Duration::from_millis(100);
+
+/* --- --- --- ScenePlayer --- --- --- */
+
+/// Plays back recorded scenes with a fixed frame rate and an adjustable speed.
+///
+/// Playback stops at the last frame, playing again afterwards starts over from the first one.
+#[derive(Debug, Clone)]
+pub struct ScenePlayer {
+    scenes: Vec<Scene>,
+    frame: usize,
+    playing: bool,
+    speed: f64,
+    frames_per_second: f64,
+    /// Frames elapsed since the current frame was shown, always below one.
+    elapsed_frames: f64,
+}
+
+impl ScenePlayer {
+    /// Defaults are `frames_per_second: DEFAULT_FRAMES_PER_SECOND` and `speed: 1`, the player
+    /// starts playing from the first frame.
+    pub fn with(scenes: Vec<Scene>) -> Self {
+        Self {
+            scenes,
+            frame: 0,
+            playing: true,
+            speed: 1f64,
+            frames_per_second: DEFAULT_FRAMES_PER_SECOND,
+            elapsed_frames: 0f64,
+        }
+    }
+
+    /// Reads all scenes of a scene stream.
+    pub fn from_stream<R: Read>(reader: R) -> Result<Self, SceneStreamError> {
+        Ok(Self::with(SceneStreamReader::new(reader)?.read_all()?))
+    }
+
+    /// Sets the frame rate the scenes were recorded with, limited to
+    /// `MINIMUM_FRAMES_PER_SECOND` and `MAXIMUM_FRAMES_PER_SECOND`.
+    pub fn frames_per_second(self, frames_per_second: f64) -> Self {
+        Self {
+            frames_per_second: if frames_per_second.is_nan() {
+                MINIMUM_FRAMES_PER_SECOND
+            } else {
+                frames_per_second.clamp(MINIMUM_FRAMES_PER_SECOND, MAXIMUM_FRAMES_PER_SECOND)
+            },
+            ..self
+        }
+    }
+
+    /// Sets the playback speed, `1` plays in real time, limited to `MINIMUM_SPEED` and
+    /// `MAXIMUM_SPEED`. NaN keeps the current speed.
+    pub fn speed(self, speed: f64) -> Self {
+        Self {
+            speed: if speed.is_nan() {
+                self.speed
+            } else {
+                speed.clamp(MINIMUM_SPEED, MAXIMUM_SPEED)
+            },
+            ..self
+        }
+    }
+
+    pub fn scenes(&self) -> &[Scene] {
+        &self.scenes
+    }
+
+    /// Returns the index of the current frame.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn current_scene(&self) -> Option<&Scene> {
+        self.scenes.get(self.frame)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn current_speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Returns whether the last frame is shown.
+    pub fn is_finished(&self) -> bool {
+        self.frame + 1 >= self.scenes.len()
+    }
+
+    /// Starts playing, from the first frame if the last one is shown.
+    pub fn play(&mut self) {
+        if self.is_finished() {
+            self.seek(0);
+        }
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.playing {
+            self.pause();
+        } else {
+            self.play();
+        }
+    }
+
+    /// Pauses and shows the next frame.
+    pub fn step_forward(&mut self) {
+        self.pause();
+        self.seek(self.frame + 1);
+    }
+
+    /// Pauses and shows the previous frame.
+    pub fn step_backward(&mut self) {
+        self.pause();
+        self.seek(self.frame.saturating_sub(1));
+    }
+
+    /// Doubles the playback speed up to `MAXIMUM_SPEED`.
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2f64).clamp(MINIMUM_SPEED, MAXIMUM_SPEED);
+    }
+
+    /// Halves the playback speed down to `MINIMUM_SPEED`.
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2f64).clamp(MINIMUM_SPEED, MAXIMUM_SPEED);
+    }
+
+    /// Shows the frame with the given index, limited to the last frame.
+    pub fn seek(&mut self, frame: usize) {
+        self.frame = frame.min(self.scenes.len().saturating_sub(1));
+        self.elapsed_frames = 0f64;
+    }
+
+    /// Applies the input if it is one of the controls and returns whether it was.
+    ///
+    /// Only key presses are used, releases are ignored.
+    pub fn handle_input(&mut self, input: &Input) -> bool {
+        let key = match input {
+            Input::Button(ButtonArgs {
+                state: ButtonState::Press,
+                button: Button::Keyboard(key),
+                ..
+            }) => key,
+            _ => return false,
+        };
+        match key {
+            Key::Space => self.toggle_pause(),
+            Key::Right => self.step_forward(),
+            Key::Left => self.step_backward(),
+            Key::Up => self.faster(),
+            Key::Down => self.slower(),
+            Key::Home => self.seek(0),
+            Key::End => self.seek(self.scenes.len()),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Applies all inputs currently available from the input provider.
+    pub fn handle_inputs<IP: InputProvider>(&mut self, input_provider: &mut IP) {
+        for input in input_provider.pop_all() {
+            self.handle_input(&input);
+        }
+    }
+
+    /// Moves the playback forward by the given real time, pausing at the last frame.
+    pub fn advance(&mut self, duration: Duration) {
+        if !self.playing {
+            return;
+        }
+        self.elapsed_frames += duration.as_secs_f64() * self.speed * self.frames_per_second;
+        let frames = self.elapsed_frames.floor();
+        self.elapsed_frames -= frames;
+        self.frame = self
+            .frame
+            .saturating_add(frames as usize)
+            .min(self.scenes.len().saturating_sub(1));
+        if self.is_finished() {
+            self.playing = false;
+            self.elapsed_frames = 0f64;
+        }
+    }
+
+    /// Renders the current frame, nothing is rendered without frames.
+    pub fn render<
+        TwoDimensionalVisualiserError: Error,
+        VisualiserError: Error,
+        Visualiser: TwoDimensionalVisualiser<TwoDimensionalVisualiserError, VisualiserError, Infallible>,
+    >(
+        &self,
+        visualiser: &mut Visualiser,
+    ) -> Result<(), TwoDimensionalVisualiserError> {
+        match self.current_scene() {
+            Some(scene) => visualiser.render_two_dimensional(scene),
+            None => Ok(()),
+        }
+    }
+
+    /// Plays back until the visualiser is closed, controlled by the inputs of the provider.
+    pub fn run<
+        TwoDimensionalVisualiserError: Error,
+        VisualiserError: Error,
+        Visualiser: TwoDimensionalVisualiser<TwoDimensionalVisualiserError, VisualiserError, Infallible>,
+        IP: InputProvider,
+    >(
+        &mut self,
+        visualiser: &mut Visualiser,
+        input_provider: &mut IP,
+    ) -> Result<(), TwoDimensionalVisualiserError> {
+        let mut last_update = Instant::now();
+        while visualiser.is_open() {
+            self.handle_inputs(input_provider);
+            let now = Instant::now();
+            self.advance(now.duration_since(last_update));
+            last_update = now;
+            self.render(visualiser)?;
+            std::thread::sleep(
+                Duration::from_secs_f64(1f64 / (self.frames_per_second * self.speed.max(1f64)))
+                    .min(MAXIMUM_SLEEP),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(frames: usize) -> ScenePlayer {
+        ScenePlayer::with(vec![Scene::with(Vec::new()); frames])
+    }
+
+    #[test]
+    fn advance_follows_frame_rate_and_speed() {
+        let mut player = player(10).frames_per_second(10.0);
+        player.advance(Duration::from_millis(250));
+        assert_eq!(player.frame(), 2);
+        player.faster();
+        player.advance(Duration::from_millis(250));
+        assert_eq!(player.frame(), 7);
+        player.advance(Duration::from_secs(10));
+        assert_eq!(player.frame(), 9);
+        assert!(!player.is_playing());
+    }
+
+    #[test]
+    fn speed_is_limited() {
+        for (speed, expected) in &[
+            (f64::INFINITY, MAXIMUM_SPEED),
+            (1e9, MAXIMUM_SPEED),
+            (0.0, MINIMUM_SPEED),
+            (-2.0, MINIMUM_SPEED),
+            (f64::NAN, 1.0),
+            (2.0, 2.0),
+        ] {
+            let mut player = player(1000).frames_per_second(10.0).speed(*speed);
+            assert_eq!(player.current_speed(), *expected);
+            player.advance(Duration::from_secs(1));
+            assert_eq!(player.frame(), (10.0 * expected) as usize);
+        }
+    }
+
+    #[test]
+    fn frame_rate_is_limited() {
+        for frames_per_second in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let player = player(1).frames_per_second(*frames_per_second);
+            assert!(player.frames_per_second >= MINIMUM_FRAMES_PER_SECOND);
+            assert!(player.frames_per_second <= MAXIMUM_FRAMES_PER_SECOND);
+            assert!(
+                Duration::from_secs_f64(1f64 / player.frames_per_second) <= Duration::from_secs(60)
+            );
+        }
+    }
+}
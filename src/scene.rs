@@ -0,0 +1,364 @@
+//! A serialisable snapshot of everything a two dimensional environment draws.
+//!
+//! Scenes are serialised together with `SCENE_VERSION`, so stored scenes can be recognised and
+//! loaded back by later versions of this crate. The representation of a version never changes:
+//! every field and variant is written with its name as in this crate, enums externally tagged.
+
+use std::convert::{Infallible, TryFrom};
+use std::error::Error;
+
+use gymnarium_base::math::Position2D;
+
+use crate::{
+    Color, DrawableEnvironment, Geometry2D, TwoDimensionalDrawableEnvironment, Viewport2D,
+    Viewport2DModification,
+};
+
+/// Version of the serialised representation of `Scene` written by this crate.
+pub const SCENE_VERSION: u32 = 1;
+
+/* --- --- --- Scene --- --- --- */
+
+/// The geometries of one frame together with the preferred view and background color.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "VersionedScene", into = "VersionedScene")]
+pub struct Scene {
+    pub geometries: Vec<Geometry2D>,
+    pub viewport: Option<(Viewport2D, Viewport2DModification)>,
+    pub background_color: Option<Color>,
+}
+
+impl Scene {
+    /// Defaults are `viewport: None` and `background_color: None`.
+    pub fn with(geometries: Vec<Geometry2D>) -> Self {
+        Self {
+            geometries,
+            viewport: None,
+            background_color: None,
+        }
+    }
+
+    pub fn viewport(self, viewport: Viewport2D, modification: Viewport2DModification) -> Self {
+        Self {
+            viewport: Some((viewport, modification)),
+            ..self
+        }
+    }
+
+    pub fn background_color(self, background_color: Color) -> Self {
+        Self {
+            background_color: Some(background_color),
+            ..self
+        }
+    }
+
+    /// Captures what the environment currently draws.
+    pub fn from_environment<
+        EnvironmentError: Error,
+        DrawableEnvironment: TwoDimensionalDrawableEnvironment<EnvironmentError>,
+    >(
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<Self, EnvironmentError> {
+        Ok(Self {
+            geometries: drawable_environment.draw_two_dimensional()?,
+            viewport: drawable_environment.preferred_view(),
+            background_color: drawable_environment.preferred_background_color(),
+        })
+    }
+
+    /// Returns the paths of all geometries of this scene containing the position.
+    ///
+    /// See `geometries_at`.
+    pub fn geometries_at(&self, position: &Position2D) -> Vec<Vec<usize>> {
+        geometries_at(&self.geometries, position)
+    }
+
+    /// Returns the geometry at the path, see `geometries_at`.
+    pub fn geometry(&self, path: &[usize]) -> Option<&Geometry2D> {
+        let (first, rest) = path.split_first()?;
+        rest.iter().try_fold(
+            self.geometries.get(*first)?,
+            |geometry, index| match geometry {
+                Geometry2D::Group(geometries) => geometries.get(*index),
+                _ => None,
+            },
+        )
+    }
+}
+
+/// Returns the paths of all geometries containing the position, the topmost first.
+///
+/// A path starts with the index of a geometry and continues with the index inside of every
+/// nested group, so only the members of groups are returned and never the groups themselves.
+/// Whether a geometry contains the position is decided by `Geometry2D::contains`.
+pub fn geometries_at(geometries: &[Geometry2D], position: &Position2D) -> Vec<Vec<usize>> {
+    let mut paths = Vec::new();
+    collect_geometries_at(geometries, position, &mut Vec::new(), &mut paths);
+    paths.reverse();
+    paths
+}
+
+fn collect_geometries_at(
+    geometries: &[Geometry2D],
+    position: &Position2D,
+    path: &mut Vec<usize>,
+    paths: &mut Vec<Vec<usize>>,
+) {
+    for (index, geometry) in geometries.iter().enumerate() {
+        path.push(index);
+        match geometry {
+            Geometry2D::Group(members) => collect_geometries_at(members, position, path, paths),
+            geometry if geometry.contains(position) => paths.push(path.clone()),
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+/// Draws the scene as it is, e.g. to show recorded scenes with any `TwoDimensionalVisualiser`.
+impl DrawableEnvironment for Scene {
+    fn suggested_rendered_steps_per_second() -> Option<f64> {
+        None
+    }
+}
+
+impl TwoDimensionalDrawableEnvironment<Infallible> for Scene {
+    fn draw_two_dimensional(&self) -> Result<Vec<Geometry2D>, Infallible> {
+        Ok(self.geometries.clone())
+    }
+
+    fn preferred_view(&self) -> Option<(Viewport2D, Viewport2DModification)> {
+        self.viewport
+    }
+
+    fn preferred_background_color(&self) -> Option<Color> {
+        self.background_color
+    }
+}
+
+/// The serialised form of `Scene`.
+#[derive(Serialize, Deserialize)]
+struct VersionedScene {
+    version: u32,
+    geometries: Vec<Geometry2D>,
+    viewport: Option<(Viewport2D, Viewport2DModification)>,
+    background_color: Option<Color>,
+}
+
+impl TryFrom<VersionedScene> for Scene {
+    type Error = String;
+
+    fn try_from(versioned_scene: VersionedScene) -> Result<Self, Self::Error> {
+        if versioned_scene.version != SCENE_VERSION {
+            return Err(format!(
+                "unsupported scene version {}, expected {}",
+                versioned_scene.version, SCENE_VERSION
+            ));
+        }
+        Ok(Self {
+            geometries: versioned_scene.geometries,
+            viewport: versioned_scene.viewport,
+            background_color: versioned_scene.background_color,
+        })
+    }
+}
+
+impl From<Scene> for VersionedScene {
+    fn from(scene: Scene) -> Self {
+        Self {
+            version: SCENE_VERSION,
+            geometries: scene.geometries,
+            viewport: scene.viewport,
+            background_color: scene.background_color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use gymnarium_base::math::{Size2D, Transformation2D, Vector2D};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    use super::*;
+    use crate::{
+        CornerShape, HorizontalAnchor, LineShape, Pixel, PixelArray, TextureSource, VerticalAnchor,
+    };
+
+    fn assert_round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T) {
+        let json = serde_json::to_string(value).unwrap();
+        assert_eq!(&serde_json::from_str::<T>(&json).unwrap(), value);
+        let binary = bincode::serialize(value).unwrap();
+        assert_eq!(&bincode::deserialize::<T>(&binary).unwrap(), value);
+    }
+
+    fn geometries() -> Vec<Geometry2D> {
+        let translation = Transformation2D::translation(Vector2D::with(1.5, -2.0));
+        vec![
+            Geometry2D::point(Position2D::with(1.0, 2.0)).append_transformation(translation),
+            Geometry2D::line(Position2D::with(0.0, 0.0), Position2D::with(3.0, 4.0))
+                .line_shape(LineShape::Round)
+                .line_or_border_width(2.5),
+            Geometry2D::polyline(vec![
+                Position2D::with(0.0, 0.0),
+                Position2D::with(1.0, 1.0),
+                Position2D::with(2.0, 0.0),
+            ])
+            .line_shape(LineShape::Bevel),
+            Geometry2D::triangle(
+                Position2D::with(0.0, 0.0),
+                Position2D::with(1.0, 0.0),
+                Position2D::with(0.0, 1.0),
+            )
+            .fill_color(Color::with(10, 20, 30, 40)),
+            Geometry2D::square(Position2D::with(5.0, 5.0), 2.0)
+                .corner_shape(CornerShape::Round(0.5, 4)),
+            Geometry2D::rectangle(Position2D::with(-1.0, 1.0), Size2D::with(4.0, 2.0))
+                .corner_shape(CornerShape::Bevel(0.25))
+                .line_or_border_color(Color::white())
+                .line_or_border_width(0.5),
+            Geometry2D::polygon(vec![
+                Position2D::with(0.0, 0.0),
+                Position2D::with(2.0, 0.0),
+                Position2D::with(2.0, 2.0),
+                Position2D::with(0.0, 2.0),
+            ]),
+            Geometry2D::circle(Position2D::with(0.0, 0.0), 3.0)
+                .append_transformation(Transformation2D::rotation(30.0)),
+            Geometry2D::ellipse(Position2D::with(1.0, 1.0), Size2D::with(3.0, 1.0)),
+            Geometry2D::Text {
+                position: Position2D::with(0.0, 0.0),
+                text: "Step 1\nReward 0.5".to_string(),
+                font_size: 12.0,
+                color: Color::black(),
+                horizontal_anchor: HorizontalAnchor::Center,
+                vertical_anchor: VerticalAnchor::Baseline,
+                transformations: Default::default(),
+            },
+            Geometry2D::image(
+                Position2D::with(0.0, 0.0),
+                Size2D::with(2.0, 1.0),
+                TextureSource::bytes(vec![255, 0, 0, 255, 0, 0, 255, 128], 2, 1),
+            ),
+            Geometry2D::Image {
+                center_position: Position2D::with(1.0, 2.0),
+                size: Size2D::with(1.0, 1.0),
+                texture_source: TextureSource::path("textures/agent.png".to_string()),
+                source_rectangle: Some((Position2D::with(0.0, 0.0), Size2D::with(16.0, 16.0))),
+                fill_color: Some(Color::transparent()),
+                transformations: Default::default(),
+            },
+            Geometry2D::group(vec![
+                Geometry2D::point(Position2D::with(0.0, 0.0)),
+                Geometry2D::group(vec![Geometry2D::circle(Position2D::with(0.0, 0.0), 1.0)]),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn geometries_round_trip() {
+        for geometry in geometries() {
+            assert_round_trip(&geometry);
+        }
+    }
+
+    #[test]
+    fn types_round_trip() {
+        assert_round_trip(&Color::with(1, 2, 3, 4));
+        assert_round_trip(&Pixel::with(5, 6, 7));
+        assert_round_trip(&PixelArray {
+            data: vec![Pixel::white(), Pixel::black(), Pixel::with(1, 2, 3)],
+            width: 3,
+            height: 1,
+        });
+        for line_shape in &[LineShape::Square, LineShape::Round, LineShape::Bevel] {
+            assert_round_trip(line_shape);
+        }
+        for corner_shape in &[
+            CornerShape::Square,
+            CornerShape::Round(1.5, 8),
+            CornerShape::Bevel(0.5),
+        ] {
+            assert_round_trip(corner_shape);
+        }
+        assert_round_trip(&TextureSource::path("a.png".to_string()));
+        assert_round_trip(&TextureSource::bytes(vec![1, 2, 3, 4], 1, 1));
+        assert_round_trip(
+            &Viewport2D::with(Position2D::with(1.0, -1.0), Size2D::with(4.0, 3.0))
+                .flipped_y_axis(true),
+        );
+        for modification in &[
+            Viewport2DModification::LooseAspectRatio,
+            Viewport2DModification::KeepAspectRatio,
+            Viewport2DModification::KeepAspectRatioAndScissorRemains,
+        ] {
+            assert_round_trip(modification);
+        }
+    }
+
+    #[test]
+    fn scene_round_trip() {
+        assert_round_trip(
+            &Scene::with(geometries())
+                .viewport(
+                    Viewport2D::with(Position2D::zero(), Size2D::with(10.0, 10.0)),
+                    Viewport2DModification::KeepAspectRatio,
+                )
+                .background_color(Color::white()),
+        );
+    }
+
+    #[test]
+    fn scene_with_other_version_is_rejected() {
+        let scene = Scene::with(geometries());
+
+        let mut json = serde_json::to_value(&scene).unwrap();
+        assert_eq!(json["version"], SCENE_VERSION);
+        json["version"] = (SCENE_VERSION + 1).into();
+        let error = serde_json::from_value::<Scene>(json).unwrap_err();
+        assert!(error.to_string().contains("unsupported scene version"));
+
+        let mut binary = bincode::serialize(&scene).unwrap();
+        binary[..4].copy_from_slice(&(SCENE_VERSION + 1).to_le_bytes());
+        assert!(bincode::deserialize::<Scene>(&binary).is_err());
+    }
+
+    #[test]
+    fn geometries_at_returns_member_paths_topmost_first() {
+        let background = Geometry2D::rectangle(Position2D::zero(), Size2D::with(10.0, 10.0));
+        let ring = Geometry2D::circle(Position2D::zero(), 2.0)
+            .fill_color(Color::transparent())
+            .line_or_border_color(Color::white())
+            .line_or_border_width(1.0);
+        let marker = Geometry2D::point(Position2D::with(2.0, 0.0))
+            .append_transformation(Transformation2D::translation(Vector2D::with(1.0, 0.0)));
+        let scene = Scene::with(vec![
+            background,
+            Geometry2D::group(vec![
+                ring,
+                Geometry2D::group(vec![marker, Geometry2D::group(Vec::new())]),
+            ]),
+        ]);
+
+        assert_eq!(scene.geometries_at(&Position2D::zero()), vec![vec![0]]);
+        assert_eq!(
+            scene.geometries_at(&Position2D::with(2.0, 0.0)),
+            vec![vec![1, 0], vec![0]]
+        );
+        assert_eq!(
+            scene.geometries_at(&Position2D::with(3.0, 0.0)),
+            vec![vec![1, 1, 0], vec![0]]
+        );
+        assert!(scene.geometries_at(&Position2D::with(20.0, 0.0)).is_empty());
+
+        assert!(matches!(
+            scene.geometry(&[1, 1, 0]),
+            Some(Geometry2D::Point { .. })
+        ));
+        assert_eq!(scene.geometry(&[1, 1, 2]), None);
+        assert_eq!(scene.geometry(&[0, 0]), None);
+        assert_eq!(scene.geometry(&[]), None);
+    }
+}
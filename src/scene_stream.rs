@@ -0,0 +1,712 @@
+//! A compact binary container for recorded sequences of scenes.
+//!
+//! A stream starts with the magic bytes `GYMSCENE` and the little endian `u32`
+//! `SCENE_STREAM_VERSION`, followed by records. Every record consists of its kind as `u8`, the
+//! little endian `u32` length of its payload and the payload, which is zlib compressed bincode.
+//!
+//! Frames only store the geometries which differ from the geometry at the same index in the
+//! previous frame. The data of `TextureSource::Bytes` is stored once in a texture record, which
+//! fills one of `MAXIMUM_TEXTURES` slots, and referenced by its slot from every later frame using
+//! it. Once all slots are taken, the least recently used texture is replaced, so writers and
+//! readers only keep a bounded amount of texture data in memory.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::io::{ErrorKind, Read, Write};
+
+use crate::scene::Scene;
+use crate::{
+    Color, Geometry2D, TextureSource, TwoDimensionalDrawableEnvironment, TwoDimensionalVisualiser,
+    Viewport2D, Viewport2DModification, Visualiser,
+};
+
+/// Version of the stream container written by this crate.
+pub const SCENE_STREAM_VERSION: u32 = 1;
+/// Largest uncompressed payload of a record which is written or read.
+pub const MAXIMUM_RECORD_SIZE: usize = 1 << 26;
+/// Number of texture slots, which is also the most textures a single frame can use.
+pub const MAXIMUM_TEXTURES: usize = 256;
+
+const MAGIC: &[u8; 8] = b"GYMSCENE";
+const TEXTURE_RECORD: u8 = 1;
+const FRAME_RECORD: u8 = 2;
+
+/* --- --- --- SceneStreamError --- --- --- */
+
+#[derive(Debug)]
+pub enum SceneStreamError {
+    Io(std::io::Error),
+    /// The data is not a valid scene stream.
+    Malformed(String),
+    /// The stream is valid, but written by an unsupported version.
+    Unsupported(String),
+}
+
+impl Display for SceneStreamError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not read or write scene stream: {}", error),
+            Self::Malformed(reason) => write!(f, "Malformed scene stream: {}", reason),
+            Self::Unsupported(reason) => write!(f, "Unsupported scene stream: {}", reason),
+        }
+    }
+}
+
+impl Error for SceneStreamError {}
+
+impl From<std::io::Error> for SceneStreamError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/* --- --- --- SceneStreamRecorderError --- --- --- */
+
+#[derive(Debug)]
+pub enum SceneStreamRecorderError<DrawableEnvironmentError: Error> {
+    /// The writer has already been closed.
+    Closed,
+    Environment(DrawableEnvironmentError),
+    Stream(SceneStreamError),
+}
+
+impl<DrawableEnvironmentError: Error> Display
+    for SceneStreamRecorderError<DrawableEnvironmentError>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Closed => write!(f, "The scene stream writer has already been closed"),
+            Self::Environment(error) => write!(f, "Could not draw environment: {}", error),
+            Self::Stream(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl<DrawableEnvironmentError: Error> Error for SceneStreamRecorderError<DrawableEnvironmentError> {}
+
+impl<DrawableEnvironmentError: Error> From<SceneStreamError>
+    for SceneStreamRecorderError<DrawableEnvironmentError>
+{
+    fn from(error: SceneStreamError) -> Self {
+        Self::Stream(error)
+    }
+}
+
+/* --- --- --- Records --- --- --- */
+
+#[derive(Serialize, Deserialize)]
+struct FrameRecord {
+    viewport: Option<(Viewport2D, Viewport2DModification)>,
+    background_color: Option<Color>,
+    geometries: Vec<GeometryEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum GeometryEntry {
+    /// The next geometries are the same as the ones at the same indices in the previous frame.
+    Unchanged(u32),
+    /// A geometry whose `TextureSource::Bytes` have no data, it is referenced in traversal
+    /// order by the texture slots.
+    Changed(Geometry2D, Vec<u32>),
+}
+
+/// The payload of a texture record is the slot followed by the data.
+type TextureRecord = (u32, Vec<u8>);
+
+/// Calls the function for every texture source inside the geometry, depth first.
+fn for_each_texture_source<F: FnMut(&mut TextureSource)>(
+    geometry: &mut Geometry2D,
+    function: &mut F,
+) {
+    match geometry {
+        Geometry2D::Image { texture_source, .. } => function(texture_source),
+        Geometry2D::Group(geometries) => {
+            for geometry in geometries {
+                for_each_texture_source(geometry, function);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn write_record<W: Write, T: serde::Serialize>(
+    writer: &mut W,
+    kind: u8,
+    value: &T,
+) -> Result<(), SceneStreamError> {
+    let serialized = bincode::serialize(value)
+        .map_err(|error| SceneStreamError::Malformed(error.to_string()))?;
+    if serialized.len() > MAXIMUM_RECORD_SIZE {
+        return Err(SceneStreamError::Malformed(format!(
+            "record of {} bytes exceeds the maximum record size",
+            serialized.len()
+        )));
+    }
+    let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&serialized, 6);
+    writer.write_all(&[kind])?;
+    writer.write_all(&(compressed.len() as u32).to_le_bytes())?;
+    writer.write_all(&compressed)?;
+    Ok(())
+}
+
+/* --- --- --- SceneStreamWriter --- --- --- */
+
+#[derive(Debug)]
+struct CachedTexture {
+    data: Vec<u8>,
+    hash: u64,
+    last_used_frame: usize,
+}
+
+/// Appends scenes to a scene stream.
+///
+/// As `TwoDimensionalVisualiser` it records one scene per render call, `close` flushes the
+/// underlying writer.
+#[derive(Debug)]
+pub struct SceneStreamWriter<W: Write> {
+    writer: W,
+    previous_geometries: Vec<Geometry2D>,
+    /// Slots of the cached textures by the hash of their data.
+    texture_slots: HashMap<u64, Vec<u32>>,
+    textures: Vec<CachedTexture>,
+    frame_count: usize,
+    open: bool,
+}
+
+impl<W: Write> SceneStreamWriter<W> {
+    /// Creates a new stream by writing its header into the writer.
+    pub fn new(mut writer: W) -> Result<Self, SceneStreamError> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&SCENE_STREAM_VERSION.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            previous_geometries: Vec::new(),
+            texture_slots: HashMap::new(),
+            textures: Vec::new(),
+            frame_count: 0,
+            open: true,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn write_scene(&mut self, scene: &Scene) -> Result<(), SceneStreamError> {
+        let mut entries = Vec::new();
+        for (index, geometry) in scene.geometries.iter().enumerate() {
+            if self.previous_geometries.get(index) == Some(geometry) {
+                match entries.last_mut() {
+                    Some(GeometryEntry::Unchanged(count)) => *count += 1,
+                    _ => entries.push(GeometryEntry::Unchanged(1)),
+                }
+                continue;
+            }
+            let mut stripped_geometry = geometry.clone();
+            let mut texture_slots = Vec::new();
+            let mut result = Ok(());
+            for_each_texture_source(&mut stripped_geometry, &mut |texture_source| {
+                if let TextureSource::Bytes { data, .. } = texture_source {
+                    match self.texture_slot(std::mem::take(data)) {
+                        Ok(slot) => texture_slots.push(slot),
+                        Err(error) => result = Err(error),
+                    }
+                }
+            });
+            result?;
+            entries.push(GeometryEntry::Changed(stripped_geometry, texture_slots));
+        }
+
+        write_record(
+            &mut self.writer,
+            FRAME_RECORD,
+            &FrameRecord {
+                viewport: scene.viewport,
+                background_color: scene.background_color,
+                geometries: entries,
+            },
+        )?;
+        self.previous_geometries = scene.geometries.clone();
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    /// Consumes the stream writer and returns the underlying writer after flushing it.
+    pub fn into_inner(mut self) -> Result<W, SceneStreamError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Returns the slot holding the texture data, writing a texture record into the least
+    /// recently used slot for data not held by any slot.
+    fn texture_slot(&mut self, data: Vec<u8>) -> Result<u32, SceneStreamError> {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let hash = hasher.finish();
+        let frame = self.frame_count;
+        if let Some(slots) = self.texture_slots.get(&hash) {
+            if let Some(&slot) = slots
+                .iter()
+                .find(|slot| self.textures[**slot as usize].data == data)
+            {
+                self.textures[slot as usize].last_used_frame = frame;
+                return Ok(slot);
+            }
+        }
+
+        let slot = if self.textures.len() < MAXIMUM_TEXTURES {
+            self.textures.len()
+        } else {
+            match self
+                .textures
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, texture)| texture.last_used_frame)
+            {
+                Some((slot, texture)) if texture.last_used_frame < frame => slot,
+                _ => {
+                    return Err(SceneStreamError::Malformed(format!(
+                        "frame uses more than {} textures",
+                        MAXIMUM_TEXTURES
+                    )))
+                }
+            }
+        };
+        write_record(&mut self.writer, TEXTURE_RECORD, &(slot as u32, &data))?;
+
+        let texture = CachedTexture {
+            data,
+            hash,
+            last_used_frame: frame,
+        };
+        if slot < self.textures.len() {
+            let replaced = std::mem::replace(&mut self.textures[slot], texture);
+            if let Some(slots) = self.texture_slots.get_mut(&replaced.hash) {
+                slots.retain(|cached_slot| *cached_slot as usize != slot);
+                if slots.is_empty() {
+                    self.texture_slots.remove(&replaced.hash);
+                }
+            }
+        } else {
+            self.textures.push(texture);
+        }
+        self.texture_slots
+            .entry(hash)
+            .or_default()
+            .push(slot as u32);
+        Ok(slot as u32)
+    }
+}
+
+impl<W: Write> Visualiser<SceneStreamError> for SceneStreamWriter<W> {
+    fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn close(&mut self) -> Result<(), SceneStreamError> {
+        if self.open {
+            self.open = false;
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write, DrawableEnvironmentError: Error>
+    TwoDimensionalVisualiser<
+        SceneStreamRecorderError<DrawableEnvironmentError>,
+        SceneStreamError,
+        DrawableEnvironmentError,
+    > for SceneStreamWriter<W>
+{
+    fn render_two_dimensional<
+        DrawableEnvironment: TwoDimensionalDrawableEnvironment<DrawableEnvironmentError>,
+    >(
+        &mut self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<(), SceneStreamRecorderError<DrawableEnvironmentError>> {
+        if !self.open {
+            return Err(SceneStreamRecorderError::Closed);
+        }
+        let scene = Scene::from_environment(drawable_environment)
+            .map_err(SceneStreamRecorderError::Environment)?;
+        Ok(self.write_scene(&scene)?)
+    }
+}
+
+/* --- --- --- SceneStreamReader --- --- --- */
+
+/// Reads the scenes of a scene stream one after another.
+#[derive(Debug)]
+pub struct SceneStreamReader<R: Read> {
+    reader: R,
+    previous_geometries: Vec<Geometry2D>,
+    textures: HashMap<u32, Vec<u8>>,
+    failed: bool,
+}
+
+impl<R: Read> SceneStreamReader<R> {
+    /// Reads and checks the header of the stream.
+    pub fn new(mut reader: R) -> Result<Self, SceneStreamError> {
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(SceneStreamError::Malformed(
+                "missing scene stream magic bytes".to_string(),
+            ));
+        }
+        let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        if version != SCENE_STREAM_VERSION {
+            return Err(SceneStreamError::Unsupported(format!(
+                "stream version {}, expected {}",
+                version, SCENE_STREAM_VERSION
+            )));
+        }
+        Ok(Self {
+            reader,
+            previous_geometries: Vec::new(),
+            textures: HashMap::new(),
+            failed: false,
+        })
+    }
+
+    /// Reads the next scene or returns `None` at the end of the stream.
+    pub fn read_scene(&mut self) -> Result<Option<Scene>, SceneStreamError> {
+        loop {
+            let mut kind = [0u8; 1];
+            match self.reader.read_exact(&mut kind) {
+                Ok(()) => {}
+                Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                Err(error) => return Err(error.into()),
+            }
+            let mut length = [0u8; 4];
+            self.reader.read_exact(&mut length)?;
+            let length = u32::from_le_bytes(length) as u64;
+            let mut compressed = Vec::new();
+            (&mut self.reader)
+                .take(length)
+                .read_to_end(&mut compressed)?;
+            if compressed.len() as u64 != length {
+                return Err(SceneStreamError::Malformed(
+                    "record exceeds the stream".to_string(),
+                ));
+            }
+            let payload = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(
+                &compressed,
+                MAXIMUM_RECORD_SIZE,
+            )
+            .map_err(|_| {
+                SceneStreamError::Malformed("invalid or too large compressed record".to_string())
+            })?;
+            let malformed = |error: bincode::Error| SceneStreamError::Malformed(error.to_string());
+
+            match kind[0] {
+                TEXTURE_RECORD => {
+                    let (slot, data): TextureRecord =
+                        bincode::deserialize(&payload).map_err(malformed)?;
+                    if slot as usize >= MAXIMUM_TEXTURES {
+                        return Err(SceneStreamError::Malformed(format!(
+                            "texture slot {} out of range",
+                            slot
+                        )));
+                    }
+                    self.textures.insert(slot, data);
+                }
+                FRAME_RECORD => {
+                    let frame: FrameRecord = bincode::deserialize(&payload).map_err(malformed)?;
+                    let geometries = self.restore_geometries(frame.geometries)?;
+                    self.previous_geometries = geometries.clone();
+                    return Ok(Some(Scene {
+                        geometries,
+                        viewport: frame.viewport,
+                        background_color: frame.background_color,
+                    }));
+                }
+                kind => {
+                    return Err(SceneStreamError::Malformed(format!(
+                        "unknown record kind {}",
+                        kind
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Reads all remaining scenes.
+    pub fn read_all(&mut self) -> Result<Vec<Scene>, SceneStreamError> {
+        let mut scenes = Vec::new();
+        while let Some(scene) = self.read_scene()? {
+            scenes.push(scene);
+        }
+        Ok(scenes)
+    }
+
+    fn restore_geometries(
+        &self,
+        entries: Vec<GeometryEntry>,
+    ) -> Result<Vec<Geometry2D>, SceneStreamError> {
+        let mut geometries = Vec::new();
+        for entry in entries {
+            match entry {
+                GeometryEntry::Unchanged(count) => {
+                    let start = geometries.len();
+                    let unchanged = self
+                        .previous_geometries
+                        .get(start..start + count as usize)
+                        .ok_or_else(|| {
+                            SceneStreamError::Malformed(
+                                "unchanged geometry missing in previous frame".to_string(),
+                            )
+                        })?;
+                    geometries.extend_from_slice(unchanged);
+                }
+                GeometryEntry::Changed(mut geometry, texture_slots) => {
+                    let mut texture_slots = texture_slots.into_iter();
+                    let mut result = Ok(());
+                    for_each_texture_source(&mut geometry, &mut |texture_source| {
+                        if let TextureSource::Bytes { data, .. } = texture_source {
+                            match texture_slots
+                                .next()
+                                .and_then(|slot| self.textures.get(&slot))
+                            {
+                                Some(texture) => *data = texture.clone(),
+                                None => {
+                                    result = Err(SceneStreamError::Malformed(
+                                        "reference to unknown texture".to_string(),
+                                    ))
+                                }
+                            }
+                        }
+                    });
+                    result?;
+                    geometries.push(geometry);
+                }
+            }
+        }
+        Ok(geometries)
+    }
+}
+
+impl<R: Read> Iterator for SceneStreamReader<R> {
+    type Item = Result<Scene, SceneStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let scene = self.read_scene();
+        self.failed = scene.is_err();
+        scene.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gymnarium_base::math::{Position2D, Size2D};
+
+    use super::*;
+
+    fn texture(value: u8) -> TextureSource {
+        TextureSource::bytes(vec![value; 4 * 4 * 4], 4, 4)
+    }
+
+    fn image(x: f64, texture_source: TextureSource) -> Geometry2D {
+        Geometry2D::image(
+            Position2D::with(x, 0.0),
+            Size2D::with(1.0, 1.0),
+            texture_source,
+        )
+    }
+
+    fn scenes() -> Vec<Scene> {
+        let background = Geometry2D::rectangle(Position2D::zero(), Size2D::with(10.0, 10.0));
+        let agent = image(0.0, texture(1));
+        vec![
+            Scene::with(vec![
+                background.clone(),
+                agent.clone(),
+                Geometry2D::circle(Position2D::zero(), 1.0),
+            ])
+            .background_color(Color::white()),
+            Scene::with(vec![
+                background.clone(),
+                agent.clone(),
+                Geometry2D::circle(Position2D::with(1.0, 0.0), 1.0),
+            ]),
+            Scene::with(vec![
+                background,
+                image(2.0, texture(1)),
+                Geometry2D::circle(Position2D::with(1.0, 0.0), 1.0),
+                Geometry2D::group(vec![agent, image(3.0, texture(2))]),
+            ])
+            .viewport(
+                Viewport2D::with(Position2D::zero(), Size2D::with(10.0, 10.0)),
+                Viewport2DModification::KeepAspectRatio,
+            ),
+        ]
+    }
+
+    /// Returns the kind and the uncompressed payload of every record.
+    fn records(stream: &[u8]) -> Vec<(u8, Vec<u8>)> {
+        let mut records = Vec::new();
+        let mut position = 12;
+        while position < stream.len() {
+            let length = u32::from_le_bytes([
+                stream[position + 1],
+                stream[position + 2],
+                stream[position + 3],
+                stream[position + 4],
+            ]) as usize;
+            let compressed = &stream[position + 5..position + 5 + length];
+            records.push((
+                stream[position],
+                miniz_oxide::inflate::decompress_to_vec_zlib(compressed).unwrap(),
+            ));
+            position += 5 + length;
+        }
+        records
+    }
+
+    fn write(scenes: &[Scene]) -> Vec<u8> {
+        let mut writer = SceneStreamWriter::new(Vec::new()).unwrap();
+        for scene in scenes {
+            writer.write_scene(scene).unwrap();
+        }
+        assert_eq!(writer.frame_count(), scenes.len());
+        writer.into_inner().unwrap()
+    }
+
+    #[test]
+    fn scenes_round_trip() {
+        let stream = write(&scenes());
+        let mut reader = SceneStreamReader::new(stream.as_slice()).unwrap();
+        let read_scenes = reader.read_all().unwrap();
+        assert_eq!(read_scenes, scenes());
+        assert!(reader.read_scene().unwrap().is_none());
+    }
+
+    #[test]
+    fn unchanged_geometries_and_textures_are_stored_once() {
+        let stream = write(&scenes());
+        let records = records(&stream);
+        let kinds: Vec<u8> = records.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TEXTURE_RECORD,
+                FRAME_RECORD,
+                FRAME_RECORD,
+                TEXTURE_RECORD,
+                FRAME_RECORD
+            ]
+        );
+
+        let frames: Vec<FrameRecord> = records
+            .iter()
+            .filter(|(kind, _)| *kind == FRAME_RECORD)
+            .map(|(_, payload)| bincode::deserialize(payload).unwrap())
+            .collect();
+        assert!(matches!(
+            frames[1].geometries.as_slice(),
+            [GeometryEntry::Unchanged(2), GeometryEntry::Changed(..)]
+        ));
+        let entries = frames[2].geometries.as_slice();
+        assert_eq!(entries.len(), 4);
+        assert!(matches!(entries[0], GeometryEntry::Unchanged(1)));
+        assert!(matches!(entries[2], GeometryEntry::Unchanged(1)));
+        match (&entries[1], &entries[3]) {
+            (
+                GeometryEntry::Changed(moved, moved_slots),
+                GeometryEntry::Changed(_, group_slots),
+            ) => {
+                assert_eq!(moved_slots, &vec![0]);
+                assert_eq!(group_slots, &vec![0, 1]);
+                assert!(matches!(
+                    moved,
+                    Geometry2D::Image {
+                        texture_source: TextureSource::Bytes { data, .. },
+                        ..
+                    } if data.is_empty()
+                ));
+            }
+            _ => panic!("expected changed geometries"),
+        }
+    }
+
+    #[test]
+    fn records_exceeding_the_stream_are_rejected() {
+        let mut stream = write(&scenes()[..1]);
+        stream.truncate(stream.len() - 1);
+        let mut reader = SceneStreamReader::new(stream.as_slice()).unwrap();
+        assert!(matches!(
+            reader.read_all(),
+            Err(SceneStreamError::Malformed(_))
+        ));
+
+        let mut stream = MAGIC.to_vec();
+        stream.extend_from_slice(&SCENE_STREAM_VERSION.to_le_bytes());
+        stream.push(FRAME_RECORD);
+        stream.extend_from_slice(&u32::MAX.to_le_bytes());
+        stream.extend_from_slice(&[0x78, 0x9C]);
+        let mut reader = SceneStreamReader::new(stream.as_slice()).unwrap();
+        assert!(matches!(
+            reader.read_scene(),
+            Err(SceneStreamError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn least_recently_used_textures_are_replaced() {
+        let texture =
+            |index: usize| TextureSource::bytes((index as u32).to_le_bytes().to_vec(), 1, 1);
+        // the second image moves, so its texture is used by every frame
+        let mut scenes: Vec<Scene> = (0..=MAXIMUM_TEXTURES)
+            .map(|index| {
+                Scene::with(vec![
+                    image(0.0, texture(index)),
+                    image(index as f64, texture(0)),
+                ])
+            })
+            .collect();
+        scenes.push(Scene::with(vec![image(0.0, texture(1))]));
+
+        let mut writer = SceneStreamWriter::new(Vec::new()).unwrap();
+        for scene in &scenes {
+            writer.write_scene(scene).unwrap();
+        }
+        assert_eq!(writer.textures.len(), MAXIMUM_TEXTURES);
+        let stream = writer.into_inner().unwrap();
+
+        // texture 1 is replaced by the last new texture and has to be written again
+        let texture_records = records(&stream)
+            .iter()
+            .filter(|(kind, _)| *kind == TEXTURE_RECORD)
+            .count();
+        assert_eq!(texture_records, MAXIMUM_TEXTURES + 2);
+        let mut reader = SceneStreamReader::new(stream.as_slice()).unwrap();
+        assert_eq!(reader.read_all().unwrap(), scenes);
+        assert_eq!(reader.textures.len(), MAXIMUM_TEXTURES);
+    }
+
+    #[test]
+    fn frames_with_too_many_textures_are_rejected() {
+        let scene = Scene::with(
+            (0..=MAXIMUM_TEXTURES)
+                .map(|index| {
+                    image(
+                        0.0,
+                        TextureSource::bytes((index as u32).to_le_bytes().to_vec(), 1, 1),
+                    )
+                })
+                .collect(),
+        );
+        let mut writer = SceneStreamWriter::new(Vec::new()).unwrap();
+        assert!(matches!(
+            writer.write_scene(&scene),
+            Err(SceneStreamError::Malformed(_))
+        ));
+    }
+}
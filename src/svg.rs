@@ -0,0 +1,698 @@
+//! Export of two dimensional geometries into standalone SVG documents.
+//!
+//! The documents show the same picture as the `Rasterizer2D` would, but in vector quality and
+//! viewable in any browser.
+
+use std::error::Error;
+use std::fmt::Write as FmtWrite;
+use std::io::Write;
+
+use gymnarium_base::math::{Position2D, Size2D};
+
+use crate::image::encode_png;
+use crate::tessellation::{rectangle_contour, Affine2D};
+use crate::{
+    Color, CornerShape, Geometry2D, LineShape, TextureSource, TwoDimensionalDrawableEnvironment,
+    Viewport2D, Viewport2DModification,
+};
+
+/* --- --- --- SvgExporter --- --- --- */
+
+/// Serialises two dimensional geometries into SVG documents of a fixed size.
+///
+/// Viewports are mapped exactly like in the `Rasterizer2D`. Images given as
+/// `TextureSource::Bytes` are embedded as PNG data URIs, while `TextureSource::Path` is
+/// referenced as is. Like in the rasterizer, images whose data does not match their size are
+/// skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgExporter {
+    pub width: usize,
+    pub height: usize,
+    pub viewport: Option<(Viewport2D, Viewport2DModification)>,
+    pub background_color: Color,
+}
+
+impl SvgExporter {
+    /// Creates a new exporter producing documents with the given size in pixels.
+    ///
+    /// Defaults are `viewport: None` and `background_color: Color::white()`.
+    pub fn with(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            viewport: None,
+            background_color: Color::white(),
+        }
+    }
+
+    pub fn viewport(
+        self,
+        viewport: Viewport2D,
+        viewport_modification: Viewport2DModification,
+    ) -> Self {
+        Self {
+            viewport: Some((viewport, viewport_modification)),
+            ..self
+        }
+    }
+
+    pub fn background_color(self, background_color: Color) -> Self {
+        Self {
+            background_color,
+            ..self
+        }
+    }
+
+    /// Returns the geometries as SVG document.
+    pub fn export(&self, geometries: &[Geometry2D]) -> String {
+        let (width, height) = (self.width as f64, self.height as f64);
+        let (pixel_transform, scissor) = match &self.viewport {
+            Some((viewport, viewport_modification)) => {
+                let layout = viewport_modification.layout(viewport, &Size2D::with(width, height));
+                (Affine2D::from_layout(&layout), layout.scissor)
+            }
+            None => (Affine2D::identity(), None),
+        };
+
+        let mut document = SvgDocument::default();
+        for geometry in geometries {
+            document.write_geometry(geometry, &pixel_transform, 1);
+        }
+
+        let mut svg = String::new();
+        let _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            self.width, self.height
+        );
+        let mut definitions = document.definitions;
+        if let Some((top_left, size)) = scissor {
+            let _ = writeln!(
+                definitions,
+                r#"<clipPath id="scissor"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+                number(top_left.x),
+                number(top_left.y),
+                number(size.width),
+                number(size.height)
+            );
+        }
+        if !definitions.is_empty() {
+            let _ = write!(svg, "<defs>\n{}</defs>\n", definitions);
+        }
+        let _ = writeln!(
+            svg,
+            r#"<rect width="100%" height="100%" fill="{}"/>"#,
+            rgb(&self.background_color)
+        );
+        if scissor.is_some() {
+            let _ = write!(
+                svg,
+                "<g clip-path=\"url(#scissor)\">\n{}</g>\n",
+                document.body
+            );
+        } else {
+            svg.push_str(&document.body);
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Writes the geometries as SVG document into the writer.
+    pub fn write<W: Write>(
+        &self,
+        geometries: &[Geometry2D],
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        writer.write_all(self.export(geometries).as_bytes())
+    }
+
+    /// Returns the geometries of the given environment as SVG document.
+    ///
+    /// The preferred view and background color of the environment take precedence over the
+    /// ones of this exporter.
+    pub fn export_environment<
+        EnvironmentError: Error,
+        DrawableEnvironment: TwoDimensionalDrawableEnvironment<EnvironmentError>,
+    >(
+        &self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<String, EnvironmentError> {
+        let geometries = drawable_environment.draw_two_dimensional()?;
+        let mut exporter = self.clone();
+        if let Some(preferred_view) = drawable_environment.preferred_view() {
+            exporter.viewport = Some(preferred_view);
+        }
+        if let Some(background_color) = drawable_environment.preferred_background_color() {
+            exporter.background_color = background_color;
+        }
+        Ok(exporter.export(&geometries))
+    }
+}
+
+/* --- --- --- SvgDocument --- --- --- */
+
+#[derive(Default)]
+struct SvgDocument {
+    definitions: String,
+    body: String,
+    next_id: usize,
+}
+
+impl SvgDocument {
+    fn write_geometry(&mut self, geometry: &Geometry2D, pixel_transform: &Affine2D, depth: usize) {
+        let indentation = "  ".repeat(depth);
+        let element = match geometry {
+            Geometry2D::Point {
+                position,
+                color,
+                transformations,
+            } => {
+                // Points are always exactly one pixel big, no matter the transformations.
+                let position = Affine2D::from_transformations(transformations)
+                    .then(pixel_transform)
+                    .apply(position);
+                format!(
+                    r#"<rect x="{}" y="{}" width="1" height="1"{}/>"#,
+                    number(position.x - 0.5f64),
+                    number(position.y - 0.5f64),
+                    fill(color)
+                )
+            }
+            Geometry2D::Line {
+                points,
+                line_color,
+                line_width,
+                line_shape,
+                transformations,
+            } => format!(
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{}{}/>"#,
+                number(points[0].x),
+                number(points[0].y),
+                number(points[1].x),
+                number(points[1].y),
+                stroke(line_color, *line_width, line_shape),
+                transform(&Affine2D::from_transformations(transformations).then(pixel_transform))
+            ),
+            Geometry2D::Polyline {
+                points,
+                line_color,
+                line_width,
+                line_shape,
+                transformations,
+            } => format!(
+                r#"<polyline points="{}" fill="none"{}{}/>"#,
+                point_list(points),
+                stroke(line_color, *line_width, line_shape),
+                transform(&Affine2D::from_transformations(transformations).then(pixel_transform))
+            ),
+            Geometry2D::Triangle {
+                points,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => format!(
+                r#"<polygon points="{}"{}{}{}/>"#,
+                point_list(points),
+                fill(fill_color),
+                stroke(border_color, *border_width, &LineShape::Square),
+                transform(&Affine2D::from_transformations(transformations).then(pixel_transform))
+            ),
+            Geometry2D::Square {
+                center_position,
+                edge_length,
+                fill_color,
+                border_color,
+                border_width,
+                corner_shape,
+                transformations,
+            } => rectangle(
+                center_position,
+                (*edge_length, *edge_length),
+                corner_shape,
+                &(fill(fill_color) + &stroke(border_color, *border_width, &LineShape::Square)),
+                &Affine2D::from_transformations(transformations).then(pixel_transform),
+            ),
+            Geometry2D::Rectangle {
+                center_position,
+                size,
+                fill_color,
+                border_color,
+                border_width,
+                corner_shape,
+                transformations,
+            } => rectangle(
+                center_position,
+                (size.width, size.height),
+                corner_shape,
+                &(fill(fill_color) + &stroke(border_color, *border_width, &LineShape::Square)),
+                &Affine2D::from_transformations(transformations).then(pixel_transform),
+            ),
+            Geometry2D::Polygon {
+                points,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => format!(
+                r#"<polygon points="{}"{}{}{}/>"#,
+                point_list(points),
+                fill(fill_color),
+                stroke(border_color, *border_width, &LineShape::Square),
+                transform(&Affine2D::from_transformations(transformations).then(pixel_transform))
+            ),
+            Geometry2D::Circle {
+                center_position,
+                radius,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => format!(
+                r#"<circle cx="{}" cy="{}" r="{}"{}{}{}/>"#,
+                number(center_position.x),
+                number(center_position.y),
+                number(radius.abs()),
+                fill(fill_color),
+                stroke(border_color, *border_width, &LineShape::Square),
+                transform(&Affine2D::from_transformations(transformations).then(pixel_transform))
+            ),
+            Geometry2D::Ellipse {
+                center_position,
+                size,
+                fill_color,
+                border_color,
+                border_width,
+                transformations,
+            } => format!(
+                r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}{}{}/>"#,
+                number(center_position.x),
+                number(center_position.y),
+                number(size.width.abs() / 2f64),
+                number(size.height.abs() / 2f64),
+                fill(fill_color),
+                stroke(border_color, *border_width, &LineShape::Square),
+                transform(&Affine2D::from_transformations(transformations).then(pixel_transform))
+            ),
+            Geometry2D::Text {
+                position,
+                text,
+                font_size,
+                color,
+                horizontal_anchor,
+                vertical_anchor,
+                transformations,
+            } => {
+                // Every line is positioned explicitly to match the layout of the other renderers.
+                let top_left = Geometry2D::text_corners(
+                    position,
+                    text,
+                    *font_size,
+                    horizontal_anchor,
+                    vertical_anchor,
+                )[0];
+                let lines: String = text
+                    .split('\n')
+                    .enumerate()
+                    .filter(|(_, line)| !line.is_empty())
+                    .map(|(index, line)| {
+                        format!(
+                            r#"<text x="{}" y="{}" textLength="{}" lengthAdjust="spacingAndGlyphs">{}</text>"#,
+                            number(top_left.x),
+                            number(top_left.y + (index as f64 + 0.8f64) * font_size),
+                            number(line.chars().count() as f64 * font_size * 0.6f64),
+                            escape(line)
+                        )
+                    })
+                    .collect();
+                format!(
+                    r#"<g font-family="monospace" font-size="{}" xml:space="preserve"{}{}>{}</g>"#,
+                    number(*font_size),
+                    fill(color),
+                    transform(&Geometry2D::text_transform(
+                        position,
+                        transformations,
+                        pixel_transform
+                    )),
+                    lines
+                )
+            }
+            Geometry2D::Image {
+                center_position,
+                size,
+                texture_source,
+                source_rectangle,
+                fill_color,
+                transformations,
+            } => {
+                let (href, texture_size) = match texture_source {
+                    TextureSource::Path(path) => (escape(path), None),
+                    TextureSource::Bytes { .. } => match texture_source.rgba_bytes() {
+                        Some((data, width, height)) if width > 0 && height > 0 => (
+                            format!(
+                                "data:image/png;base64,{}",
+                                base64(&encode_png(data, width, height, true))
+                            ),
+                            Some((width as f64, height as f64)),
+                        ),
+                        _ => return,
+                    },
+                };
+                let filter = match fill_color {
+                    Some(tint) => {
+                        let [red, green, blue, alpha] = tint.float_array();
+                        let id = self.next_id();
+                        let _ = writeln!(
+                            self.definitions,
+                            r#"<filter id="tint{}"><feColorMatrix type="matrix" values="{} 0 0 0 0 0 {} 0 0 0 0 0 {} 0 0 0 0 0 {} 0"/></filter>"#,
+                            id, red, green, blue, alpha
+                        );
+                        format!(r#" filter="url(#tint{})""#, id)
+                    }
+                    None => String::new(),
+                };
+                let (x, y) = (
+                    center_position.x - size.width / 2f64,
+                    center_position.y - size.height / 2f64,
+                );
+                let image = match (source_rectangle, texture_size) {
+                    (Some((source_position, source_size)), Some((width, height))) => format!(
+                        r#"<svg x="{}" y="{}" width="{}" height="{}" viewBox="{} {} {} {}" preserveAspectRatio="none"><image width="{}" height="{}" preserveAspectRatio="none" style="image-rendering:pixelated" href="{}"/></svg>"#,
+                        number(x),
+                        number(y),
+                        number(size.width),
+                        number(size.height),
+                        number(source_position.x),
+                        number(source_position.y),
+                        number(source_size.width),
+                        number(source_size.height),
+                        number(width),
+                        number(height),
+                        href
+                    ),
+                    _ => format!(
+                        r#"<image x="{}" y="{}" width="{}" height="{}" preserveAspectRatio="none" style="image-rendering:pixelated" href="{}"/>"#,
+                        number(x),
+                        number(y),
+                        number(size.width),
+                        number(size.height),
+                        href
+                    ),
+                };
+                format!(
+                    "<g{}{}>{}</g>",
+                    filter,
+                    transform(
+                        &Affine2D::from_transformations(transformations).then(pixel_transform)
+                    ),
+                    image
+                )
+            }
+            Geometry2D::Group(geometries) => {
+                let _ = writeln!(self.body, "{}<g>", indentation);
+                for geometry in geometries {
+                    self.write_geometry(geometry, pixel_transform, depth + 1);
+                }
+                let _ = writeln!(self.body, "{}</g>", indentation);
+                return;
+            }
+        };
+        let _ = writeln!(self.body, "{}{}", indentation, element);
+    }
+
+    fn next_id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+fn rectangle(
+    center_position: &Position2D,
+    (width, height): (f64, f64),
+    corner_shape: &CornerShape,
+    style: &str,
+    affine: &Affine2D,
+) -> String {
+    match corner_shape {
+        CornerShape::Bevel(_) => format!(
+            r#"<polygon points="{}"{}{}/>"#,
+            point_list(&rectangle_contour(
+                center_position,
+                width,
+                height,
+                corner_shape
+            )),
+            style,
+            transform(affine)
+        ),
+        _ => {
+            let corner_radius = match corner_shape {
+                CornerShape::Round(radius, _) => format!(
+                    r#" rx="{0}" ry="{0}""#,
+                    number(
+                        radius
+                            .max(0f64)
+                            .min(width.abs() / 2f64)
+                            .min(height.abs() / 2f64)
+                    )
+                ),
+                _ => String::new(),
+            };
+            format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}"{}{}{}/>"#,
+                number(center_position.x - width.abs() / 2f64),
+                number(center_position.y - height.abs() / 2f64),
+                number(width.abs()),
+                number(height.abs()),
+                corner_radius,
+                style,
+                transform(affine)
+            )
+        }
+    }
+}
+
+/* --- --- --- Attributes --- --- --- */
+
+fn fill(color: &Color) -> String {
+    if color.alpha == 0 {
+        r#" fill="none""#.to_string()
+    } else if color.alpha == 255 {
+        format!(r#" fill="{}""#, rgb(color))
+    } else {
+        format!(
+            r#" fill="{}" fill-opacity="{}""#,
+            rgb(color),
+            number(color.alpha as f64 / 255f64)
+        )
+    }
+}
+
+fn stroke(color: &Color, width: f64, line_shape: &LineShape) -> String {
+    if color.alpha == 0 || width <= 0f64 {
+        return String::new();
+    }
+    let (line_cap, line_join) = match line_shape {
+        LineShape::Square => ("square", "miter"),
+        LineShape::Round => ("round", "round"),
+        LineShape::Bevel => ("butt", "bevel"),
+    };
+    let opacity = if color.alpha == 255 {
+        String::new()
+    } else {
+        format!(
+            r#" stroke-opacity="{}""#,
+            number(color.alpha as f64 / 255f64)
+        )
+    };
+    format!(
+        r#" stroke="{}"{} stroke-width="{}" stroke-linecap="{}" stroke-linejoin="{}" stroke-miterlimit="4""#,
+        rgb(color),
+        opacity,
+        number(width),
+        line_cap,
+        line_join
+    )
+}
+
+fn transform(affine: &Affine2D) -> String {
+    if *affine == Affine2D::identity() {
+        return String::new();
+    }
+    let [a, b, c, d, e, f] = affine.0;
+    format!(
+        r#" transform="matrix({} {} {} {} {} {})""#,
+        number(a),
+        number(d),
+        number(b),
+        number(e),
+        number(c),
+        number(f)
+    )
+}
+
+fn rgb(color: &Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+}
+
+fn point_list(points: &[Position2D]) -> String {
+    points
+        .iter()
+        .map(|point| format!("{},{}", number(point.x), number(point.y)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Formats the number with at most six decimal places and without trailing zeros.
+fn number(value: f64) -> String {
+    let formatted = format!("{:.6}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/* --- --- --- Data URIs --- --- --- */
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let triple = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - 6 * index) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(svg: &str) -> Vec<&str> {
+        svg.lines()
+            .skip_while(|line| !line.starts_with("<rect width=\"100%\""))
+            .skip(1)
+            .filter(|line| *line != "</svg>")
+            .collect()
+    }
+
+    #[test]
+    fn shapes_become_elements_with_their_style() {
+        let svg = SvgExporter::with(10, 10)
+            .background_color(Color::with(0, 0, 255, 255))
+            .export(&[Geometry2D::rectangle(
+                Position2D::with(5f64, 5f64),
+                Size2D::with(4f64, 2f64),
+            )
+            .fill_color(Color::with(255, 0, 0, 128))
+            .line_or_border_color(Color::black())
+            .line_or_border_width(0.5f64)]);
+        assert!(svg.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(svg.contains(r#"width="10" height="10" viewBox="0 0 10 10""#));
+        assert!(svg.contains(r##"<rect width="100%" height="100%" fill="#0000ff"/>"##));
+        assert_eq!(
+            body(&svg),
+            vec![
+                r##"  <rect x="3" y="4" width="4" height="2" fill="#ff0000" fill-opacity="0.501961" stroke="#000000" stroke-width="0.5" stroke-linecap="square" stroke-linejoin="miter" stroke-miterlimit="4"/>"##
+            ]
+        );
+    }
+
+    #[test]
+    fn viewports_become_transformations_and_scissors() {
+        let viewport = Viewport2D::with(Position2D::zero(), Size2D::with(20f64, 10f64));
+        let circle = [Geometry2D::circle(Position2D::with(1f64, 2f64), 3f64)];
+        let svg = SvgExporter::with(100, 100)
+            .viewport(
+                viewport.flipped_y_axis(true),
+                Viewport2DModification::KeepAspectRatioAndScissorRemains,
+            )
+            .export(&circle);
+        assert!(svg.contains(
+            r#"<clipPath id="scissor"><rect x="0" y="25" width="100" height="50"/></clipPath>"#
+        ));
+        assert!(svg.contains(
+            r##"  <circle cx="1" cy="2" r="3" fill="#000000" transform="matrix(5 0 0 -5 50 50)"/>"##
+        ));
+        assert!(svg.contains(r#"<g clip-path="url(#scissor)">"#));
+
+        let svg = SvgExporter::with(100, 100)
+            .viewport(viewport, Viewport2DModification::LooseAspectRatio)
+            .export(&circle);
+        assert!(!svg.contains("clipPath"));
+        assert!(svg.contains(r#"transform="matrix(5 0 0 10 50 50)""#));
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let svg = SvgExporter::with(100, 100).export(&[Geometry2D::text(
+            Position2D::zero(),
+            r#"a<b & "c">"#.to_string(),
+            10f64,
+        )]);
+        assert!(svg
+            .contains(r#"lengthAdjust="spacingAndGlyphs">a&lt;b &amp; &quot;c&quot;&gt;</text>"#));
+    }
+
+    #[test]
+    fn images_are_embedded_as_png() {
+        let data = vec![255, 0, 0, 255, 0, 0, 255, 128];
+        let svg = SvgExporter::with(10, 10).export(&[Geometry2D::image(
+            Position2D::with(5f64, 5f64),
+            Size2D::with(4f64, 2f64),
+            TextureSource::bytes(data.clone(), 2, 1),
+        )]);
+        assert_eq!(
+            body(&svg),
+            vec![format!(
+                r#"  <g><image x="3" y="4" width="4" height="2" preserveAspectRatio="none" style="image-rendering:pixelated" href="data:image/png;base64,{}"/></g>"#,
+                base64(&encode_png(&data, 2, 1, true))
+            )]
+        );
+    }
+
+    #[test]
+    fn images_with_invalid_data_are_skipped() {
+        for texture_source in [
+            TextureSource::bytes(vec![0; 4], 100_000, 100_000),
+            TextureSource::bytes(vec![0; 4], u32::MAX, u32::MAX),
+            TextureSource::bytes(Vec::new(), 0, 3),
+        ] {
+            let svg = SvgExporter::with(10, 10).export(&[Geometry2D::image(
+                Position2D::with(5f64, 5f64),
+                Size2D::with(4f64, 2f64),
+                texture_source,
+            )]);
+            assert!(body(&svg).is_empty());
+        }
+    }
+
+    #[test]
+    fn base64_pads_incomplete_triples() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b""), "");
+    }
+}
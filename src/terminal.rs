@@ -0,0 +1,366 @@
+//! Visualiser drawing frames directly into a terminal.
+//!
+//! Every character cell shows two pixels with the upper half block `▀`, using the 24-bit ANSI
+//! escape codes for its foreground and background color. This works over SSH in every terminal
+//! supporting truecolor.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Stdout, Write};
+
+use crate::rasterizer::Rasterizer2D;
+use crate::{
+    Pixel, PixelArray, PixelArrayDrawableEnvironment, PixelArrayVisualiser,
+    TextDrawableEnvironment, TextVisualiser, TwoDimensionalDrawableEnvironment,
+    TwoDimensionalVisualiser, Visualiser,
+};
+
+const CURSOR_HOME: &str = "\x1b[H";
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const CLEAR_TO_END_OF_SCREEN: &str = "\x1b[J";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+const RESET_COLORS: &str = "\x1b[0m";
+
+/* --- --- --- TerminalVisualiserError --- --- --- */
+
+#[derive(Debug)]
+pub enum TerminalVisualiserError<DrawableEnvironmentError: Error> {
+    /// The visualiser has already been closed.
+    Closed,
+    Environment(DrawableEnvironmentError),
+    Io(std::io::Error),
+}
+
+impl<DrawableEnvironmentError: Error> Display
+    for TerminalVisualiserError<DrawableEnvironmentError>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Closed => write!(f, "The terminal visualiser has already been closed"),
+            Self::Environment(error) => write!(f, "Could not draw environment: {}", error),
+            Self::Io(error) => write!(f, "Could not write to terminal: {}", error),
+        }
+    }
+}
+
+impl<DrawableEnvironmentError: Error> Error for TerminalVisualiserError<DrawableEnvironmentError> {}
+
+impl<DrawableEnvironmentError: Error> From<std::io::Error>
+    for TerminalVisualiserError<DrawableEnvironmentError>
+{
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/* --- --- --- TerminalVisualiser --- --- --- */
+
+/// Draws every rendered frame into an area of the terminal, replacing the previous one.
+///
+/// The area is `columns` characters wide and `rows` characters high, which are
+/// `columns`x`2 * rows` pixels. Two dimensional environments are rasterized in this size, pixel
+/// arrays are scaled to fit into it while keeping their aspect ratio. Texts are printed as is.
+///
+/// The cursor is hidden while the visualiser is open and shown again on `close`, which is also
+/// called when the visualiser is dropped.
+#[derive(Debug)]
+pub struct TerminalVisualiser<W: Write> {
+    writer: W,
+    columns: usize,
+    rows: usize,
+    rasterizer: Rasterizer2D,
+    screen_prepared: bool,
+    open: bool,
+}
+
+impl TerminalVisualiser<Stdout> {
+    /// Creates a visualiser drawing onto the standard output.
+    pub fn with(columns: usize, rows: usize) -> Self {
+        Self::with_writer(std::io::stdout(), columns, rows)
+    }
+}
+
+impl<W: Write> TerminalVisualiser<W> {
+    /// Defaults are `rasterizer: Rasterizer2D::with(columns, 2 * rows)`.
+    pub fn with_writer(writer: W, columns: usize, rows: usize) -> Self {
+        Self {
+            writer,
+            columns,
+            rows,
+            rasterizer: Rasterizer2D::with(columns, 2 * rows),
+            screen_prepared: false,
+            open: true,
+        }
+    }
+
+    /// Sets the rasterizer used for two dimensional environments, its size is kept at
+    /// `columns`x`2 * rows`.
+    pub fn rasterizer(mut self, rasterizer: Rasterizer2D) -> Self {
+        // Struct update syntax cannot move out of a type implementing `Drop`.
+        self.rasterizer = Rasterizer2D {
+            width: self.columns,
+            height: 2 * self.rows,
+            ..rasterizer
+        };
+        self
+    }
+
+    /// Writes the pixel array as half blocks, scaled to fit into the area of this visualiser.
+    pub fn draw_pixel_array(&mut self, pixel_array: &PixelArray) -> std::io::Result<()> {
+        let pixel_array = fit(pixel_array, self.columns, 2 * self.rows);
+        let mut output = String::new();
+        self.prepare_screen(&mut output);
+        output.push_str(CURSOR_HOME);
+        for row in 0..pixel_array.height.div_ceil(2) {
+            let mut colors: Option<(Pixel, Option<Pixel>)> = None;
+            for column in 0..pixel_array.width {
+                let upper = pixel_array.data[2 * row * pixel_array.width + column];
+                let lower = if 2 * row + 1 < pixel_array.height {
+                    Some(pixel_array.data[(2 * row + 1) * pixel_array.width + column])
+                } else {
+                    None
+                };
+                if colors != Some((upper, lower)) {
+                    output.push_str(&format!(
+                        "\x1b[38;2;{};{};{}m",
+                        upper.red, upper.green, upper.blue
+                    ));
+                    match lower {
+                        Some(lower) => output.push_str(&format!(
+                            "\x1b[48;2;{};{};{}m",
+                            lower.red, lower.green, lower.blue
+                        )),
+                        None => output.push_str("\x1b[49m"),
+                    }
+                    colors = Some((upper, lower));
+                }
+                output.push('▀');
+            }
+            output.push_str(RESET_COLORS);
+            output.push_str("\x1b[K\n");
+        }
+        // Removes the rows left over from a previous, higher frame.
+        output.push_str(CLEAR_TO_END_OF_SCREEN);
+        self.writer.write_all(output.as_bytes())?;
+        self.writer.flush()
+    }
+
+    /// Writes the text, replacing everything previously drawn.
+    pub fn draw_text(&mut self, text: &str) -> std::io::Result<()> {
+        let mut output = String::new();
+        self.prepare_screen(&mut output);
+        output.push_str(CURSOR_HOME);
+        output.push_str(CLEAR_SCREEN);
+        for line in text.lines() {
+            output.push_str(line);
+            output.push('\n');
+        }
+        self.writer.write_all(output.as_bytes())?;
+        self.writer.flush()
+    }
+
+    fn prepare_screen(&mut self, output: &mut String) {
+        if !self.screen_prepared {
+            output.push_str(HIDE_CURSOR);
+            output.push_str(CLEAR_SCREEN);
+            self.screen_prepared = true;
+        }
+    }
+}
+
+/// Scales the pixel array to fit into the given size while keeping its aspect ratio.
+///
+/// Each target pixel is the average of all source pixels it covers.
+fn fit(pixel_array: &PixelArray, width: usize, height: usize) -> PixelArray {
+    if pixel_array.width == 0
+        || pixel_array.height == 0
+        || pixel_array.data.len() != pixel_array.width * pixel_array.height
+    {
+        return PixelArray {
+            data: Vec::new(),
+            width: 0,
+            height: 0,
+        };
+    }
+    if pixel_array.width == width && pixel_array.height <= height
+        || pixel_array.height == height && pixel_array.width <= width
+    {
+        return pixel_array.clone();
+    }
+    let scale =
+        (width as f64 / pixel_array.width as f64).min(height as f64 / pixel_array.height as f64);
+    let target_width = ((pixel_array.width as f64 * scale).round() as usize).clamp(1, width.max(1));
+    let target_height =
+        ((pixel_array.height as f64 * scale).round() as usize).clamp(1, height.max(1));
+    let source_range = |target: usize, target_length: usize, source_length: usize| {
+        let start = target * source_length / target_length;
+        let end = ((target + 1) * source_length).div_ceil(target_length);
+        start..end.max(start + 1).min(source_length)
+    };
+
+    let mut data = Vec::with_capacity(target_width * target_height);
+    for y in 0..target_height {
+        let rows = source_range(y, target_height, pixel_array.height);
+        for x in 0..target_width {
+            let columns = source_range(x, target_width, pixel_array.width);
+            let mut sum = [0usize; 3];
+            let mut count = 0;
+            for source_y in rows.clone() {
+                for source_x in columns.clone() {
+                    let pixel = &pixel_array.data[source_y * pixel_array.width + source_x];
+                    sum[0] += pixel.red as usize;
+                    sum[1] += pixel.green as usize;
+                    sum[2] += pixel.blue as usize;
+                    count += 1;
+                }
+            }
+            data.push(Pixel::with(
+                (sum[0] / count) as u8,
+                (sum[1] / count) as u8,
+                (sum[2] / count) as u8,
+            ));
+        }
+    }
+    PixelArray {
+        data,
+        width: target_width,
+        height: target_height,
+    }
+}
+
+impl<W: Write> Visualiser<std::io::Error> for TerminalVisualiser<W> {
+    fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn close(&mut self) -> Result<(), std::io::Error> {
+        if self.open {
+            self.open = false;
+            if self.screen_prepared {
+                write!(self.writer, "{}{}", RESET_COLORS, SHOW_CURSOR)?;
+                self.writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for TerminalVisualiser<W> {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+impl<W: Write, DrawableEnvironmentError: Error>
+    PixelArrayVisualiser<
+        TerminalVisualiserError<DrawableEnvironmentError>,
+        std::io::Error,
+        DrawableEnvironmentError,
+    > for TerminalVisualiser<W>
+{
+    fn render_pixel_array<
+        DrawableEnvironment: PixelArrayDrawableEnvironment<DrawableEnvironmentError>,
+    >(
+        &mut self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<(), TerminalVisualiserError<DrawableEnvironmentError>> {
+        if !self.open {
+            return Err(TerminalVisualiserError::Closed);
+        }
+        let pixel_array = drawable_environment
+            .draw_pixel_array()
+            .map_err(TerminalVisualiserError::Environment)?;
+        Ok(self.draw_pixel_array(&pixel_array)?)
+    }
+}
+
+impl<W: Write, DrawableEnvironmentError: Error>
+    TwoDimensionalVisualiser<
+        TerminalVisualiserError<DrawableEnvironmentError>,
+        std::io::Error,
+        DrawableEnvironmentError,
+    > for TerminalVisualiser<W>
+{
+    fn render_two_dimensional<
+        DrawableEnvironment: TwoDimensionalDrawableEnvironment<DrawableEnvironmentError>,
+    >(
+        &mut self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<(), TerminalVisualiserError<DrawableEnvironmentError>> {
+        if !self.open {
+            return Err(TerminalVisualiserError::Closed);
+        }
+        let pixel_array = self
+            .rasterizer
+            .rasterize_environment(drawable_environment)
+            .map_err(TerminalVisualiserError::Environment)?;
+        Ok(self.draw_pixel_array(&pixel_array)?)
+    }
+}
+
+impl<W: Write, DrawableEnvironmentError: Error>
+    TextVisualiser<
+        TerminalVisualiserError<DrawableEnvironmentError>,
+        std::io::Error,
+        DrawableEnvironmentError,
+    > for TerminalVisualiser<W>
+{
+    fn render_text<DrawableEnvironment: TextDrawableEnvironment<DrawableEnvironmentError>>(
+        &mut self,
+        drawable_environment: &DrawableEnvironment,
+    ) -> Result<(), TerminalVisualiserError<DrawableEnvironmentError>> {
+        if !self.open {
+            return Err(TerminalVisualiserError::Closed);
+        }
+        let text = drawable_environment
+            .draw_text()
+            .map_err(TerminalVisualiserError::Environment)?;
+        Ok(self.draw_text(&text)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(visualiser: &TerminalVisualiser<Vec<u8>>) -> String {
+        String::from_utf8(visualiser.writer.clone()).unwrap()
+    }
+
+    #[test]
+    fn rows_of_higher_frames_are_cleared() {
+        let mut visualiser = TerminalVisualiser::with_writer(Vec::new(), 4, 4);
+        visualiser
+            .draw_pixel_array(&PixelArray {
+                data: vec![Pixel::white(); 4 * 8],
+                width: 4,
+                height: 8,
+            })
+            .unwrap();
+        visualiser.writer.clear();
+        visualiser
+            .draw_pixel_array(&PixelArray {
+                data: vec![Pixel::black(); 4 * 2],
+                width: 4,
+                height: 2,
+            })
+            .unwrap();
+        let output = output(&visualiser);
+        assert!(output.starts_with(CURSOR_HOME));
+        assert_eq!(output.matches('▀').count(), 4);
+        assert!(output.ends_with(&format!("\n{}", CLEAR_TO_END_OF_SCREEN)));
+    }
+
+    #[test]
+    fn dropping_shows_the_cursor() {
+        let mut writer = Vec::new();
+        {
+            let mut visualiser = TerminalVisualiser::with_writer(&mut writer, 2, 1);
+            visualiser.draw_text("text").unwrap();
+        }
+        let output = String::from_utf8(writer).unwrap();
+        assert!(output.contains(HIDE_CURSOR));
+        assert!(output.ends_with(SHOW_CURSOR));
+    }
+}
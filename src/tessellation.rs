@@ -0,0 +1,1096 @@
+//! Helpers turning geometries into flat polygon outlines and triangles, shared by the built-in
+//! renderers.
+
+use gymnarium_base::math::{Position2D, Transformations2D};
+
+use crate::math3d::{Position3D, Transformation3D, Vector3D};
+use crate::{Color, CornerShape, Geometry3D, LineShape, Material, Viewport2DLayout};
+
+/// A closed polygon outline.
+pub(crate) type Contour = Vec<Position2D>;
+
+/// Miter joins longer than this multiple of the half line width are drawn as bevel joins.
+const MITER_LIMIT: f64 = 4f64;
+
+/* --- --- --- Affine2D --- --- --- */
+
+/// Row major 2x3 matrix describing an affine transformation.
+///
+/// `x' = m[0] * x + m[1] * y + m[2]` and `y' = m[3] * x + m[4] * y + m[5]`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Affine2D(pub [f64; 6]);
+
+impl Affine2D {
+    pub fn identity() -> Self {
+        Self([1f64, 0f64, 0f64, 0f64, 1f64, 0f64])
+    }
+
+    pub fn scale_and_translate(scale_x: f64, scale_y: f64, offset_x: f64, offset_y: f64) -> Self {
+        Self([scale_x, 0f64, offset_x, 0f64, scale_y, offset_y])
+    }
+
+    /// Recovers the matrix by transforming the origin and both unit vectors.
+    ///
+    /// This is exact, because every `Transformation2D` is affine.
+    pub fn from_transformations(transformations: &Transformations2D) -> Self {
+        if transformations.transformations.is_empty() {
+            return Self::identity();
+        }
+        let origin = Position2D::zero().transform(transformations);
+        let unit_x = Position2D::with(1f64, 0f64).transform(transformations);
+        let unit_y = Position2D::with(0f64, 1f64).transform(transformations);
+        Self([
+            unit_x.x - origin.x,
+            unit_y.x - origin.x,
+            origin.x,
+            unit_x.y - origin.y,
+            unit_y.y - origin.y,
+            origin.y,
+        ])
+    }
+
+    /// Returns the transformation applying `self` first and `next` afterwards.
+    pub fn then(&self, next: &Affine2D) -> Self {
+        let [a, b, c, d, e, f] = self.0;
+        let [na, nb, nc, nd, ne, nf] = next.0;
+        Self([
+            na * a + nb * d,
+            na * b + nb * e,
+            na * c + nb * f + nc,
+            nd * a + ne * d,
+            nd * b + ne * e,
+            nd * c + ne * f + nf,
+        ])
+    }
+
+    pub fn apply(&self, position: &Position2D) -> Position2D {
+        let [a, b, c, d, e, f] = self.0;
+        Position2D::with(
+            a * position.x + b * position.y + c,
+            d * position.x + e * position.y + f,
+        )
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.0[0] * self.0[4] - self.0[1] * self.0[3]
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant.abs() < f64::EPSILON {
+            return None;
+        }
+        let [a, b, c, d, e, f] = self.0;
+        Some(Self([
+            e / determinant,
+            -b / determinant,
+            (b * f - c * e) / determinant,
+            -d / determinant,
+            a / determinant,
+            (c * d - a * f) / determinant,
+        ]))
+    }
+
+    /// Returns the transformation from world into pixel coordinates of the layout.
+    pub fn from_layout(layout: &Viewport2DLayout) -> Self {
+        Self::scale_and_translate(
+            layout.scale.0,
+            layout.scale.1,
+            layout.offset.x,
+            layout.offset.y,
+        )
+    }
+
+    /// The largest factor a length can be stretched by this transformation.
+    pub fn maximum_scale(&self) -> f64 {
+        let [a, b, _, d, e, _] = self.0;
+        (a * a + d * d).sqrt().max((b * b + e * e).sqrt())
+    }
+}
+
+/* --- --- --- Outlines --- --- --- */
+
+/// Number of segments needed to approximate a full circle with the given radius in pixels.
+pub(crate) fn segments_for_radius(radius_in_pixels: f64) -> usize {
+    ((std::f64::consts::PI * 2f64 * radius_in_pixels.abs()).ceil() as usize).clamp(8, 4096)
+}
+
+pub(crate) fn ellipse_contour(
+    center_position: &Position2D,
+    radius_x: f64,
+    radius_y: f64,
+    segments: usize,
+) -> Contour {
+    (0..segments)
+        .map(|index| {
+            let angle = std::f64::consts::PI * 2f64 * index as f64 / segments as f64;
+            Position2D::with(
+                center_position.x + radius_x * angle.cos(),
+                center_position.y + radius_y * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn rectangle_contour(
+    center_position: &Position2D,
+    width: f64,
+    height: f64,
+    corner_shape: &CornerShape,
+) -> Contour {
+    let half_width = width.abs() / 2f64;
+    let half_height = height.abs() / 2f64;
+    let (left, right) = (
+        center_position.x - half_width,
+        center_position.x + half_width,
+    );
+    let (top, bottom) = (
+        center_position.y - half_height,
+        center_position.y + half_height,
+    );
+    match corner_shape {
+        CornerShape::Square => vec![
+            Position2D::with(left, top),
+            Position2D::with(right, top),
+            Position2D::with(right, bottom),
+            Position2D::with(left, bottom),
+        ],
+        CornerShape::Bevel(size) => {
+            let size = size.max(0f64).min(half_width).min(half_height);
+            vec![
+                Position2D::with(left + size, top),
+                Position2D::with(right - size, top),
+                Position2D::with(right, top + size),
+                Position2D::with(right, bottom - size),
+                Position2D::with(right - size, bottom),
+                Position2D::with(left + size, bottom),
+                Position2D::with(left, bottom - size),
+                Position2D::with(left, top + size),
+            ]
+        }
+        CornerShape::Round(radius, resolution) => {
+            let radius = radius.max(0f64).min(half_width).min(half_height);
+            let resolution = (*resolution).max(1) as usize;
+            [
+                (right - radius, top + radius, -90f64),
+                (right - radius, bottom - radius, 0f64),
+                (left + radius, bottom - radius, 90f64),
+                (left + radius, top + radius, 180f64),
+            ]
+            .iter()
+            .flat_map(|&(x, y, start_angle)| {
+                (0..=resolution).map(move |step| {
+                    let angle =
+                        (start_angle + 90f64 * step as f64 / resolution as f64).to_radians();
+                    Position2D::with(x + radius * angle.cos(), y + radius * angle.sin())
+                })
+            })
+            .collect()
+        }
+    }
+}
+
+/// Returns the pieces which together cover the stroke of the given line.
+///
+/// Every piece is oriented the same way, so filling all of them with the non-zero rule yields
+/// their union. `LineShape::Square` results in square caps and miter joins,
+/// `LineShape::Round` in round caps and joins and `LineShape::Bevel` in butt caps and bevel
+/// joins.
+pub(crate) fn stroke_contours(
+    points: &[Position2D],
+    line_width: f64,
+    line_shape: &LineShape,
+    closed: bool,
+    round_segments: usize,
+) -> Vec<Contour> {
+    let half_width = line_width.abs() / 2f64;
+    if half_width <= 0f64 || points.is_empty() {
+        return Vec::new();
+    }
+
+    let mut points: Vec<Position2D> = points.to_vec();
+    points.dedup();
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+
+    let mut contours = Vec::new();
+    if points.len() == 1 {
+        let point = points[0];
+        match line_shape {
+            LineShape::Square => contours.push(rectangle_contour(
+                &point,
+                line_width,
+                line_width,
+                &CornerShape::Square,
+            )),
+            LineShape::Round => contours.push(ellipse_contour(
+                &point,
+                half_width,
+                half_width,
+                round_segments,
+            )),
+            LineShape::Bevel => {}
+        }
+        return oriented(contours);
+    }
+
+    let segment_count = if closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+    let directions: Vec<(f64, f64)> = (0..segment_count)
+        .map(|index| direction(&points[index], &points[(index + 1) % points.len()]))
+        .collect();
+
+    for (index, (dx, dy)) in directions.iter().enumerate() {
+        let start = points[index];
+        let end = points[(index + 1) % points.len()];
+        let (nx, ny) = (-dy * half_width, dx * half_width);
+        contours.push(vec![
+            Position2D::with(start.x + nx, start.y + ny),
+            Position2D::with(end.x + nx, end.y + ny),
+            Position2D::with(end.x - nx, end.y - ny),
+            Position2D::with(start.x - nx, start.y - ny),
+        ]);
+    }
+
+    let joins = if closed {
+        (0..points.len()).collect::<Vec<_>>()
+    } else {
+        (1..points.len() - 1).collect::<Vec<_>>()
+    };
+    for index in joins {
+        let incoming = directions[(index + segment_count - 1) % segment_count];
+        let outgoing = directions[index % segment_count];
+        if let Some(contour) = join_contour(
+            &points[index],
+            incoming,
+            outgoing,
+            half_width,
+            line_shape,
+            round_segments,
+        ) {
+            contours.push(contour);
+        }
+    }
+
+    if !closed {
+        let first_direction = directions[0];
+        let last_direction = directions[segment_count - 1];
+        contours.extend(cap_contour(
+            &points[0],
+            (-first_direction.0, -first_direction.1),
+            half_width,
+            line_shape,
+            round_segments,
+        ));
+        contours.extend(cap_contour(
+            &points[points.len() - 1],
+            last_direction,
+            half_width,
+            line_shape,
+            round_segments,
+        ));
+    }
+
+    oriented(contours)
+}
+
+fn direction(from: &Position2D, to: &Position2D) -> (f64, f64) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let length = (dx * dx + dy * dy).sqrt();
+    (dx / length, dy / length)
+}
+
+fn join_contour(
+    position: &Position2D,
+    incoming: (f64, f64),
+    outgoing: (f64, f64),
+    half_width: f64,
+    line_shape: &LineShape,
+    round_segments: usize,
+) -> Option<Contour> {
+    let cross = incoming.0 * outgoing.1 - incoming.1 * outgoing.0;
+    if cross.abs() < 1e-12 && incoming.0 * outgoing.0 + incoming.1 * outgoing.1 > 0f64 {
+        return None;
+    }
+    // The outer side of the corner lies opposite to the turning direction.
+    let side = if cross > 0f64 { -1f64 } else { 1f64 };
+    let incoming_normal = (-incoming.1 * side, incoming.0 * side);
+    let outgoing_normal = (-outgoing.1 * side, outgoing.0 * side);
+    let incoming_corner = Position2D::with(
+        position.x + incoming_normal.0 * half_width,
+        position.y + incoming_normal.1 * half_width,
+    );
+    let outgoing_corner = Position2D::with(
+        position.x + outgoing_normal.0 * half_width,
+        position.y + outgoing_normal.1 * half_width,
+    );
+    match line_shape {
+        LineShape::Round => Some(ellipse_contour(
+            position,
+            half_width,
+            half_width,
+            round_segments,
+        )),
+        LineShape::Bevel => Some(vec![*position, incoming_corner, outgoing_corner]),
+        LineShape::Square => {
+            let (sum_x, sum_y) = (
+                incoming_normal.0 + outgoing_normal.0,
+                incoming_normal.1 + outgoing_normal.1,
+            );
+            let sum_length = (sum_x * sum_x + sum_y * sum_y).sqrt();
+            let cosine =
+                (sum_x * incoming_normal.0 + sum_y * incoming_normal.1) / sum_length.max(1e-12);
+            if sum_length < 1e-12 || 1f64 / cosine > MITER_LIMIT {
+                Some(vec![*position, incoming_corner, outgoing_corner])
+            } else {
+                let miter_length = half_width / cosine;
+                Some(vec![
+                    *position,
+                    incoming_corner,
+                    Position2D::with(
+                        position.x + sum_x / sum_length * miter_length,
+                        position.y + sum_y / sum_length * miter_length,
+                    ),
+                    outgoing_corner,
+                ])
+            }
+        }
+    }
+}
+
+fn cap_contour(
+    position: &Position2D,
+    outward: (f64, f64),
+    half_width: f64,
+    line_shape: &LineShape,
+    round_segments: usize,
+) -> Option<Contour> {
+    let (nx, ny) = (-outward.1 * half_width, outward.0 * half_width);
+    let (ox, oy) = (outward.0 * half_width, outward.1 * half_width);
+    match line_shape {
+        LineShape::Square => Some(vec![
+            Position2D::with(position.x + nx, position.y + ny),
+            Position2D::with(position.x + nx + ox, position.y + ny + oy),
+            Position2D::with(position.x - nx + ox, position.y - ny + oy),
+            Position2D::with(position.x - nx, position.y - ny),
+        ]),
+        LineShape::Round => Some(ellipse_contour(
+            position,
+            half_width,
+            half_width,
+            round_segments,
+        )),
+        LineShape::Bevel => None,
+    }
+}
+
+pub(crate) fn signed_area(contour: &[Position2D]) -> f64 {
+    contour
+        .iter()
+        .zip(contour.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f64>()
+        / 2f64
+}
+
+/// Makes every contour wind in the same direction.
+fn oriented(contours: Vec<Contour>) -> Vec<Contour> {
+    contours
+        .into_iter()
+        .map(|mut contour| {
+            if signed_area(&contour) < 0f64 {
+                contour.reverse();
+            }
+            contour
+        })
+        .collect()
+}
+
+/* --- --- --- Bounds --- --- --- */
+
+/// An axis aligned box given by its smallest and largest position.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Bounds {
+    pub minimum: Position2D,
+    pub maximum: Position2D,
+}
+
+impl Bounds {
+    pub fn union(&self, other: &Bounds) -> Self {
+        Self {
+            minimum: Position2D::with(
+                self.minimum.x.min(other.minimum.x),
+                self.minimum.y.min(other.minimum.y),
+            ),
+            maximum: Position2D::with(
+                self.maximum.x.max(other.maximum.x),
+                self.maximum.y.max(other.maximum.y),
+            ),
+        }
+    }
+}
+
+/// Returns the bounds of the transformed points, which are exact for polygons.
+pub(crate) fn polygon_bounds(points: &[Position2D], transform: &Affine2D) -> Option<Bounds> {
+    points
+        .iter()
+        .map(|point| {
+            let point = transform.apply(point);
+            Bounds {
+                minimum: point,
+                maximum: point,
+            }
+        })
+        .reduce(|a, b| a.union(&b))
+}
+
+/// Returns the exact bounds of the transformed ellipse, grown by `margin` in every direction
+/// before transforming.
+///
+/// A transformed ellipse reaches `sqrt((m[0] * radius_x)^2 + (m[1] * radius_y)^2)` away from
+/// its center along x and the same with `m[3]` and `m[4]` along y, the margin grows it like a
+/// transformed circle with that radius does.
+pub(crate) fn ellipse_bounds(
+    center_position: &Position2D,
+    radius_x: f64,
+    radius_y: f64,
+    margin: f64,
+    transform: &Affine2D,
+) -> Bounds {
+    let [a, b, _, d, e, _] = transform.0;
+    let center = transform.apply(center_position);
+    let extent_x = (a * radius_x).hypot(b * radius_y) + margin.abs() * a.hypot(b);
+    let extent_y = (d * radius_x).hypot(e * radius_y) + margin.abs() * d.hypot(e);
+    Bounds {
+        minimum: Position2D::with(center.x - extent_x, center.y - extent_y),
+        maximum: Position2D::with(center.x + extent_x, center.y + extent_y),
+    }
+}
+
+/// Returns the exact bounds of the transformed stroke drawn by `stroke_contours`, including
+/// the points themselves.
+pub(crate) fn stroke_bounds(
+    points: &[Position2D],
+    line_width: f64,
+    line_shape: &LineShape,
+    closed: bool,
+    transform: &Affine2D,
+) -> Option<Bounds> {
+    let point_bounds = polygon_bounds(points, transform)?;
+    let stroke_bounds = match line_shape {
+        // round caps and joins never reach beyond the circles around the points
+        LineShape::Round => points
+            .iter()
+            .map(|point| ellipse_bounds(point, 0f64, 0f64, line_width / 2f64, transform))
+            .reduce(|a, b| a.union(&b)),
+        LineShape::Square | LineShape::Bevel => {
+            let vertices: Vec<Position2D> =
+                stroke_contours(points, line_width, line_shape, closed, 8)
+                    .into_iter()
+                    .flatten()
+                    .collect();
+            polygon_bounds(&vertices, transform)
+        }
+    };
+    Some(match stroke_bounds {
+        Some(stroke_bounds) => point_bounds.union(&stroke_bounds),
+        None => point_bounds,
+    })
+}
+
+/* --- --- --- Hit testing --- --- --- */
+
+/// Returns whether the position lies inside the contours using the non-zero winding rule, the
+/// same rule the rasterizer fills them with.
+pub(crate) fn contours_contain(contours: &[Contour], position: &Position2D) -> bool {
+    let mut winding = 0i32;
+    for contour in contours {
+        for (start, end) in contour.iter().zip(contour.iter().cycle().skip(1)) {
+            let side = (end.x - start.x) * (position.y - start.y)
+                - (position.x - start.x) * (end.y - start.y);
+            if start.y <= position.y {
+                if end.y > position.y && side > 0f64 {
+                    winding += 1;
+                }
+            } else if end.y <= position.y && side < 0f64 {
+                winding -= 1;
+            }
+        }
+    }
+    winding != 0
+}
+
+/// Returns whether the position lies on the stroke drawn by `stroke_contours`.
+///
+/// Round strokes are tested exactly instead of using their approximated outline.
+pub(crate) fn stroke_contains(
+    points: &[Position2D],
+    line_width: f64,
+    line_shape: &LineShape,
+    closed: bool,
+    position: &Position2D,
+) -> bool {
+    let half_width = line_width.abs() / 2f64;
+    if half_width <= 0f64 || points.is_empty() {
+        return false;
+    }
+    match line_shape {
+        LineShape::Round => {
+            let segment_count = if closed || points.len() == 1 {
+                points.len()
+            } else {
+                points.len() - 1
+            };
+            (0..segment_count).any(|index| {
+                distance_to_segment(
+                    position,
+                    &points[index],
+                    &points[(index + 1) % points.len()],
+                ) <= half_width
+            })
+        }
+        LineShape::Square | LineShape::Bevel => contours_contain(
+            &stroke_contours(points, line_width, line_shape, closed, 8),
+            position,
+        ),
+    }
+}
+
+fn distance_to_segment(position: &Position2D, start: &Position2D, end: &Position2D) -> f64 {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0f64 {
+        (((position.x - start.x) * dx + (position.y - start.y) * dy) / length_squared)
+            .clamp(0f64, 1f64)
+    } else {
+        0f64
+    };
+    (position.x - start.x - t * dx).hypot(position.y - start.y - t * dy)
+}
+
+/// Returns the distance of the position to the outline of the axis aligned ellipse.
+///
+/// The closest point on the outline is found iteratively using the evolute of the ellipse,
+/// a few iterations are accurate far below pixel size.
+pub(crate) fn distance_to_ellipse(
+    center_position: &Position2D,
+    radius_x: f64,
+    radius_y: f64,
+    position: &Position2D,
+) -> f64 {
+    let (a, b) = (radius_x.abs(), radius_y.abs());
+    let (px, py) = (
+        (position.x - center_position.x).abs(),
+        (position.y - center_position.y).abs(),
+    );
+    if a <= 0f64 || b <= 0f64 {
+        return distance_to_segment(
+            &Position2D::with(px, py),
+            &Position2D::with(-a, -b),
+            &Position2D::with(a, b),
+        );
+    }
+    let (mut tx, mut ty) = (
+        std::f64::consts::FRAC_1_SQRT_2,
+        std::f64::consts::FRAC_1_SQRT_2,
+    );
+    for _ in 0..4 {
+        let (x, y) = (a * tx, b * ty);
+        let ex = (a * a - b * b) * tx.powi(3) / a;
+        let ey = (b * b - a * a) * ty.powi(3) / b;
+        let r = (x - ex).hypot(y - ey);
+        let q = (px - ex).hypot(py - ey);
+        if q <= 0f64 {
+            break;
+        }
+        tx = (((px - ex) * r / q + ex) / a).clamp(0f64, 1f64);
+        ty = (((py - ey) * r / q + ey) / b).clamp(0f64, 1f64);
+        let t = tx.hypot(ty);
+        tx /= t;
+        ty /= t;
+    }
+    (px - a * tx).hypot(py - b * ty)
+}
+
+/* --- --- --- Solids --- --- --- */
+
+/// A triangle of a three dimensional geometry, its points counterclockwise seen from its front.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Face {
+    pub positions: [Position3D; 3],
+    /// The normals at the positions, pointing away from the front.
+    pub normals: [Vector3D; 3],
+    pub material: Material,
+}
+
+/// The primitives of three dimensional geometries with all their transformations applied.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Primitives3D {
+    pub points: Vec<(Position3D, Color)>,
+    /// The end points, the color and the line width of every line.
+    pub lines: Vec<([Position3D; 2], Color, f64)>,
+    pub faces: Vec<Face>,
+}
+
+/// Tessellates the geometries into points, lines and triangles.
+///
+/// Curved surfaces get the normals of the exact surface at their vertices, flat surfaces the
+/// normal of the surface. Triangles with degenerated points are left out.
+pub(crate) fn primitives_3d(geometries: &[Geometry3D]) -> Primitives3D {
+    let mut primitives = Primitives3D::default();
+    for geometry in geometries {
+        primitives.add(geometry);
+    }
+    primitives
+}
+
+impl Primitives3D {
+    fn add(&mut self, geometry: &Geometry3D) {
+        match geometry {
+            Geometry3D::Point {
+                position,
+                color,
+                transformations,
+            } => self
+                .points
+                .push((transformations.combined().apply(position), *color)),
+            Geometry3D::Line {
+                points,
+                color,
+                line_width,
+                transformations,
+            } => {
+                let transform = transformations.combined();
+                self.lines.push((
+                    [transform.apply(&points[0]), transform.apply(&points[1])],
+                    *color,
+                    *line_width,
+                ));
+            }
+            Geometry3D::Triangle {
+                points,
+                material,
+                transformations,
+            } => {
+                let normal = face_normal(points);
+                self.add_face(&transformations.combined(), *points, [normal; 3], material);
+            }
+            Geometry3D::Mesh {
+                vertices,
+                normals,
+                triangles,
+                material,
+                transformations,
+            } => {
+                let transform = transformations.combined();
+                for triangle in triangles {
+                    let positions = match (
+                        vertices.get(triangle[0]),
+                        vertices.get(triangle[1]),
+                        vertices.get(triangle[2]),
+                    ) {
+                        (Some(a), Some(b), Some(c)) => [*a, *b, *c],
+                        _ => continue,
+                    };
+                    let vertex_normals = if normals.len() == vertices.len() {
+                        [
+                            normals[triangle[0]],
+                            normals[triangle[1]],
+                            normals[triangle[2]],
+                        ]
+                    } else {
+                        [face_normal(&positions); 3]
+                    };
+                    self.add_face(&transform, positions, vertex_normals, material);
+                }
+            }
+            Geometry3D::Box {
+                center_position,
+                size,
+                material,
+                transformations,
+            } => {
+                let transform = transformations.combined();
+                let x = Vector3D::with(size.width / 2f64, 0f64, 0f64);
+                let y = Vector3D::with(0f64, size.height / 2f64, 0f64);
+                let z = Vector3D::with(0f64, 0f64, size.depth / 2f64);
+                for (normal, u, v) in [(x, y, z), (y, z, x), (z, x, y)] {
+                    for (normal, u, v) in [(normal, u, v), (-normal, v, u)] {
+                        let center = *center_position + normal;
+                        self.add_quad(
+                            &transform,
+                            [
+                                center - u - v,
+                                center + u - v,
+                                center + u + v,
+                                center - u + v,
+                            ],
+                            [normal.normalized(); 4],
+                            material,
+                        );
+                    }
+                }
+            }
+            Geometry3D::Sphere {
+                center_position,
+                radius,
+                segments,
+                material,
+                transformations,
+            } => {
+                let transform = transformations.combined();
+                let segments = (*segments).max(3) as usize;
+                let rings = (segments / 2).max(2);
+                let normal = |ring: usize, segment: usize| {
+                    let (sin_polar, cos_polar) =
+                        (std::f64::consts::PI * ring as f64 / rings as f64).sin_cos();
+                    let (sin_azimuth, cos_azimuth) =
+                        (2f64 * std::f64::consts::PI * segment as f64 / segments as f64).sin_cos();
+                    Vector3D::with(sin_polar * cos_azimuth, cos_polar, -sin_polar * sin_azimuth)
+                };
+                for ring in 0..rings {
+                    for segment in 0..segments {
+                        let normals = [
+                            normal(ring, segment),
+                            normal(ring + 1, segment),
+                            normal(ring + 1, segment + 1),
+                            normal(ring, segment + 1),
+                        ];
+                        self.add_quad(
+                            &transform,
+                            normals.map(|normal| *center_position + normal * *radius),
+                            normals,
+                            material,
+                        );
+                    }
+                }
+            }
+            Geometry3D::Cylinder {
+                center_position,
+                radius,
+                height,
+                segments,
+                material,
+                transformations,
+            } => {
+                let transform = transformations.combined();
+                let segments = (*segments).max(3) as usize;
+                let half_height = Vector3D::with(0f64, height / 2f64, 0f64);
+                let up = Vector3D::with(0f64, 1f64, 0f64);
+                let (top, bottom) = (
+                    *center_position + half_height,
+                    *center_position - half_height,
+                );
+                for segment in 0..segments {
+                    let (start, end) = (radial(segment, segments), radial(segment + 1, segments));
+                    self.add_quad(
+                        &transform,
+                        [
+                            bottom + start * *radius,
+                            bottom + end * *radius,
+                            top + end * *radius,
+                            top + start * *radius,
+                        ],
+                        [start, end, end, start],
+                        material,
+                    );
+                    self.add_face(
+                        &transform,
+                        [top, top + start * *radius, top + end * *radius],
+                        [up; 3],
+                        material,
+                    );
+                    self.add_face(
+                        &transform,
+                        [bottom, bottom + end * *radius, bottom + start * *radius],
+                        [-up; 3],
+                        material,
+                    );
+                }
+            }
+            Geometry3D::Cone {
+                center_position,
+                radius,
+                height,
+                segments,
+                material,
+                transformations,
+            } => {
+                let transform = transformations.combined();
+                let segments = (*segments).max(3) as usize;
+                let half_height = Vector3D::with(0f64, height / 2f64, 0f64);
+                let up = Vector3D::with(0f64, 1f64, 0f64);
+                let (apex, bottom) = (
+                    *center_position + half_height,
+                    *center_position - half_height,
+                );
+                // the slope of the surface tilts its normals upwards
+                let slanted = |segment: f64| {
+                    (radial_at(segment, segments) * *height + up * *radius).normalized()
+                };
+                for segment in 0..segments {
+                    let (start, end) = (radial(segment, segments), radial(segment + 1, segments));
+                    self.add_face(
+                        &transform,
+                        [bottom + start * *radius, bottom + end * *radius, apex],
+                        [
+                            slanted(segment as f64),
+                            slanted(segment as f64 + 1f64),
+                            slanted(segment as f64 + 0.5f64),
+                        ],
+                        material,
+                    );
+                    self.add_face(
+                        &transform,
+                        [bottom, bottom + end * *radius, bottom + start * *radius],
+                        [-up; 3],
+                        material,
+                    );
+                }
+            }
+            Geometry3D::Plane {
+                center_position,
+                size,
+                material,
+                transformations,
+            } => {
+                let x = Vector3D::with(size.width / 2f64, 0f64, 0f64);
+                let z = Vector3D::with(0f64, 0f64, size.height / 2f64);
+                self.add_quad(
+                    &transformations.combined(),
+                    [
+                        *center_position - x + z,
+                        *center_position + x + z,
+                        *center_position + x - z,
+                        *center_position - x - z,
+                    ],
+                    [Vector3D::with(0f64, 1f64, 0f64); 4],
+                    material,
+                );
+            }
+            Geometry3D::Group(geometries) => {
+                for geometry in geometries {
+                    self.add(geometry);
+                }
+            }
+        }
+    }
+
+    /// Adds the quad as two triangles, its points counterclockwise seen from its front.
+    fn add_quad(
+        &mut self,
+        transform: &Transformation3D,
+        positions: [Position3D; 4],
+        normals: [Vector3D; 4],
+        material: &Material,
+    ) {
+        for (a, b, c) in [(0, 1, 2), (0, 2, 3)] {
+            self.add_face(
+                transform,
+                [positions[a], positions[b], positions[c]],
+                [normals[a], normals[b], normals[c]],
+                material,
+            );
+        }
+    }
+
+    fn add_face(
+        &mut self,
+        transform: &Transformation3D,
+        positions: [Position3D; 3],
+        normals: [Vector3D; 3],
+        material: &Material,
+    ) {
+        if face_normal(&positions) == Vector3D::zero() {
+            return;
+        }
+        let mut face = Face {
+            positions: positions.map(|position| transform.apply(&position)),
+            normals: normals.map(|normal| transform.apply_to_normal(&normal)),
+            material: *material,
+        };
+        if transform.determinant() < 0f64 {
+            // mirroring turns the points clockwise
+            face.positions.swap(1, 2);
+            face.normals.swap(1, 2);
+        }
+        self.faces.push(face);
+    }
+}
+
+/// Returns the normal of the front of the triangle, the zero vector if it is degenerated.
+pub(crate) fn face_normal(positions: &[Position3D; 3]) -> Vector3D {
+    positions[0]
+        .vector_to(&positions[1])
+        .cross(&positions[0].vector_to(&positions[2]))
+        .normalized()
+}
+
+/// Returns the direction from the y axis outwards at the start of the segment.
+fn radial(segment: usize, segments: usize) -> Vector3D {
+    radial_at(segment as f64, segments)
+}
+
+fn radial_at(segment: f64, segments: usize) -> Vector3D {
+    let (sin, cos) = (2f64 * std::f64::consts::PI * segment / segments as f64).sin_cos();
+    Vector3D::with(cos, 0f64, -sin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &Position2D, expected: &Position2D) {
+        assert!(
+            (actual.x - expected.x).abs() < 1e-9 && (actual.y - expected.y).abs() < 1e-9,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    /// Strokes the points with a line width of two and 64 segments per round piece.
+    fn stroke(points: &[(f64, f64)], line_shape: LineShape, closed: bool) -> Vec<Contour> {
+        let points: Vec<Position2D> = points
+            .iter()
+            .map(|(x, y)| Position2D::with(*x, *y))
+            .collect();
+        stroke_contours(&points, 2f64, &line_shape, closed, 64)
+    }
+
+    fn covers(contours: &[Contour], x: f64, y: f64) -> bool {
+        contours_contain(contours, &Position2D::with(x, y))
+    }
+
+    #[test]
+    fn then_applies_self_first() {
+        let scale = Affine2D::scale_and_translate(2f64, 3f64, 1f64, 2f64);
+        let rotation = Affine2D([0f64, -1f64, 0f64, 1f64, 0f64, 0f64]);
+        let position = Position2D::with(1.5f64, -4f64);
+        assert_close(
+            &scale.then(&rotation).apply(&position),
+            &rotation.apply(&scale.apply(&position)),
+        );
+        assert_close(
+            &rotation.then(&scale).apply(&position),
+            &scale.apply(&rotation.apply(&position)),
+        );
+        assert_ne!(scale.then(&rotation), rotation.then(&scale));
+        assert_eq!(scale.then(&Affine2D::identity()), scale);
+    }
+
+    #[test]
+    fn inverse_reverts_the_transformation() {
+        let transformation = Affine2D([2f64, 1f64, -3f64, 0.5f64, 4f64, 7f64]);
+        let inverse = transformation.inverse().unwrap();
+        let position = Position2D::with(1.5f64, -4f64);
+        assert_close(&inverse.apply(&transformation.apply(&position)), &position);
+        assert_close(&transformation.apply(&inverse.apply(&position)), &position);
+        for (actual, expected) in transformation
+            .then(&inverse)
+            .0
+            .iter()
+            .zip(Affine2D::identity().0.iter())
+        {
+            assert!((actual - expected).abs() < 1e-9);
+        }
+        assert_eq!(
+            Affine2D::scale_and_translate(0f64, 1f64, 2f64, 3f64).inverse(),
+            None
+        );
+    }
+
+    #[test]
+    fn stroke_pieces_are_oriented_alike() {
+        for line_shape in [LineShape::Square, LineShape::Round, LineShape::Bevel] {
+            let contours = stroke(
+                &[(0f64, 0f64), (10f64, 0f64), (10f64, 10f64), (0f64, 3f64)],
+                line_shape,
+                false,
+            );
+            assert!(contours.iter().all(|contour| signed_area(contour) > 0f64));
+        }
+    }
+
+    #[test]
+    fn caps_follow_the_line_shape() {
+        let line = [(0f64, 0f64), (10f64, 0f64)];
+
+        let square = stroke(&line, LineShape::Square, false);
+        assert!(covers(&square, 10.9f64, 0.9f64) && covers(&square, -0.9f64, -0.9f64));
+        assert!(!covers(&square, 11.1f64, 0f64) && !covers(&square, 5f64, 1.1f64));
+
+        let round = stroke(&line, LineShape::Round, false);
+        assert!(covers(&round, 10.9f64, 0f64) && covers(&round, -0.9f64, 0f64));
+        assert!(!covers(&round, 10.8f64, 0.8f64) && !covers(&round, -0.8f64, -0.8f64));
+
+        let bevel = stroke(&line, LineShape::Bevel, false);
+        assert!(covers(&bevel, 9.9f64, 0.9f64) && covers(&bevel, 0.1f64, -0.9f64));
+        assert!(!covers(&bevel, 10.1f64, 0f64) && !covers(&bevel, -0.1f64, 0f64));
+    }
+
+    #[test]
+    fn joins_follow_the_line_shape() {
+        let corner = [(0f64, 0f64), (10f64, 0f64), (10f64, 10f64)];
+
+        let miter = stroke(&corner, LineShape::Square, false);
+        assert!(covers(&miter, 10.9f64, -0.9f64));
+
+        let round = stroke(&corner, LineShape::Round, false);
+        assert!(covers(&round, 10.6f64, -0.6f64));
+        assert!(!covers(&round, 10.9f64, -0.9f64));
+
+        let bevel = stroke(&corner, LineShape::Bevel, false);
+        assert!(covers(&bevel, 10.4f64, -0.4f64));
+        assert!(!covers(&bevel, 10.6f64, -0.6f64));
+
+        // inner sides of corners are covered by the segments alone
+        for contours in &[miter, round, bevel] {
+            assert!(covers(contours, 9.1f64, 0.9f64));
+            assert!(!covers(contours, 8.9f64, 1.1f64));
+        }
+    }
+
+    #[test]
+    fn sharp_miter_joins_are_beveled() {
+        let contours = stroke(
+            &[(0f64, 0f64), (10f64, 0f64), (0f64, 1f64)],
+            LineShape::Square,
+            false,
+        );
+        // the miter would reach about twenty units beyond the corner
+        assert!(covers(&contours, 10.04f64, 0f64));
+        assert!(!covers(&contours, 10.2f64, 0f64));
+    }
+
+    #[test]
+    fn closed_strokes_join_instead_of_cap() {
+        let square = [(0f64, 0f64), (10f64, 0f64), (10f64, 10f64), (0f64, 10f64)];
+        let closed = stroke(&square, LineShape::Square, true);
+        assert!(covers(&closed, -0.9f64, -0.9f64));
+        let open = stroke(&square, LineShape::Bevel, false);
+        assert!(!covers(&open, -0.1f64, 0f64));
+        let closed = stroke(&square, LineShape::Bevel, true);
+        assert!(covers(&closed, -0.1f64, 0f64));
+    }
+
+    #[test]
+    fn single_points_are_capped() {
+        let point = [(5f64, 5f64)];
+        assert!(covers(
+            &stroke(&point, LineShape::Square, false),
+            5.9f64,
+            5.9f64
+        ));
+        assert!(covers(
+            &stroke(&point, LineShape::Round, false),
+            5.9f64,
+            5f64
+        ));
+        assert!(stroke(&point, LineShape::Bevel, false).is_empty());
+    }
+}
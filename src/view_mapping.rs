@@ -0,0 +1,313 @@
+//! Conversion between window coordinates of input events and world coordinates of environments.
+//!
+//! Mouse positions are reported in window coordinates, with the origin in the upper left corner
+//! of the window and the y axis pointing down, touch positions normalized to `0..1` in both
+//! directions. Environments instead think in the coordinates of their preferred view, which
+//! visualisers fit into the window as given by the `Viewport2DModification`.
+
+use std::error::Error;
+
+use gymnarium_base::math::{Position2D, Size2D};
+
+use crate::input::{Input, Motion, ResizeArgs, TouchArgs};
+use crate::{
+    TwoDimensionalDrawableEnvironment, Viewport2D, Viewport2DLayout, Viewport2DModification,
+};
+
+/* --- --- --- ViewMapper --- --- --- */
+
+/// Maps positions between the window and the world shown in it.
+///
+/// The viewport is fitted into the window by `Viewport2DModification::layout` like the built-in
+/// visualisers do, including flipped axes and the borders left by keeping the aspect ratio.
+/// Without a viewport, world coordinates are window coordinates.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ViewMapper {
+    pub window_size: Size2D,
+    pub viewport: Option<(Viewport2D, Viewport2DModification)>,
+}
+
+impl ViewMapper {
+    /// Creates a new mapper for a window of the given size.
+    ///
+    /// Defaults are `viewport: None`.
+    pub fn with(window_size: Size2D) -> Self {
+        Self {
+            window_size,
+            viewport: None,
+        }
+    }
+
+    pub fn viewport(
+        self,
+        viewport: Viewport2D,
+        viewport_modification: Viewport2DModification,
+    ) -> Self {
+        Self {
+            viewport: Some((viewport, viewport_modification)),
+            ..self
+        }
+    }
+
+    /// Takes over the preferred view of the environment, which may change between frames.
+    pub fn update_from_environment<
+        EnvironmentError: Error,
+        DrawableEnvironment: TwoDimensionalDrawableEnvironment<EnvironmentError>,
+    >(
+        &mut self,
+        drawable_environment: &DrawableEnvironment,
+    ) {
+        self.viewport = drawable_environment.preferred_view();
+    }
+
+    /// Takes over the new window size of `Input::Resize` and returns whether the input was one.
+    pub fn update_from_input(&mut self, input: &Input) -> bool {
+        match input {
+            Input::Resize(resize_args) => {
+                self.resize(resize_args);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn resize(&mut self, resize_args: &ResizeArgs) {
+        self.window_size = Size2D::with(resize_args.window_size[0], resize_args.window_size[1]);
+    }
+
+    /// Returns how the viewport is fitted into the window, `None` without a viewport.
+    pub fn layout(&self) -> Option<Viewport2DLayout> {
+        self.viewport
+            .as_ref()
+            .map(|(viewport, viewport_modification)| {
+                viewport_modification.layout(viewport, &self.window_size)
+            })
+    }
+
+    /// Returns the world position shown at the window position.
+    ///
+    /// Returns `None` while the window or the viewport has no area.
+    pub fn window_to_world(&self, window_position: &Position2D) -> Option<Position2D> {
+        match self.layout() {
+            Some(layout) => layout.pixel_to_world(window_position),
+            None => Some(*window_position),
+        }
+    }
+
+    /// Returns the window position the world position is shown at.
+    ///
+    /// The position may lie outside of the window or, with
+    /// `Viewport2DModification::KeepAspectRatioAndScissorRemains`, in the cut off border.
+    pub fn world_to_window(&self, world_position: &Position2D) -> Position2D {
+        match self.layout() {
+            Some(layout) => layout.world_to_pixel(world_position),
+            None => *world_position,
+        }
+    }
+
+    /// Returns the world position of a touch, whose position is normalized to the window.
+    pub fn touch_to_world(&self, touch_args: &TouchArgs) -> Option<Position2D> {
+        self.window_to_world(&Position2D::with(
+            touch_args.position_3d[0] * self.window_size.width,
+            touch_args.position_3d[1] * self.window_size.height,
+        ))
+    }
+
+    /// Returns the world position of mouse cursor and touch inputs.
+    pub fn input_to_world(&self, input: &Input) -> Option<Position2D> {
+        match input {
+            Input::Move(Motion::MouseCursor([x, y])) => {
+                self.window_to_world(&Position2D::with(*x, *y))
+            }
+            Input::Move(Motion::Touch(touch_args)) => self.touch_to_world(touch_args),
+            _ => None,
+        }
+    }
+
+    /// Returns whether anything is drawn at the window position.
+    ///
+    /// Only the border cut off by `Viewport2DModification::KeepAspectRatioAndScissorRemains` and
+    /// positions outside of the window are not drawn.
+    pub fn is_visible(&self, window_position: &Position2D) -> bool {
+        match self.layout() {
+            Some(layout) => layout.is_visible(window_position, &self.window_size),
+            None => {
+                (0f64..=self.window_size.width).contains(&window_position.x)
+                    && (0f64..=self.window_size.height).contains(&window_position.y)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Button, ButtonArgs, ButtonState, MouseButton, Touch};
+
+    fn assert_positions_close(actual: &Position2D, expected: &Position2D) {
+        assert!(
+            (actual.x - expected.x).abs() < 1e-9 && (actual.y - expected.y).abs() < 1e-9,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    /// A viewport twice as wide as high with the world origin in its center.
+    fn mapper(viewport_modification: Viewport2DModification) -> ViewMapper {
+        ViewMapper::with(Size2D::with(200f64, 200f64)).viewport(
+            Viewport2D::with(Position2D::zero(), Size2D::with(4f64, 2f64)),
+            viewport_modification,
+        )
+    }
+
+    fn mouse(x: f64, y: f64) -> Input {
+        Input::Move(Motion::MouseCursor([x, y]))
+    }
+
+    /// Checks that the window positions map onto the world positions and back, the same way as
+    /// the layout of the mapper, given as `[window_x, window_y, world_x, world_y]`.
+    fn assert_maps(mapper: &ViewMapper, positions: &[[f64; 4]]) {
+        let layout = mapper.layout().unwrap();
+        for [window_x, window_y, world_x, world_y] in positions {
+            let window = Position2D::with(*window_x, *window_y);
+            let world = Position2D::with(*world_x, *world_y);
+            assert_positions_close(
+                &mapper.input_to_world(&mouse(window.x, window.y)).unwrap(),
+                &world,
+            );
+            assert_positions_close(&mapper.world_to_window(&world), &window);
+            assert_positions_close(&layout.pixel_to_world(&window).unwrap(), &world);
+            assert_positions_close(&layout.world_to_pixel(&world), &window);
+        }
+    }
+
+    #[test]
+    fn window_positions_are_mapped_into_the_viewport() {
+        // 50 pixels per world unit, centered in the window
+        assert_maps(
+            &mapper(Viewport2DModification::KeepAspectRatio),
+            &[
+                [100f64, 100f64, 0f64, 0f64],
+                [200f64, 150f64, 2f64, 1f64],
+                [0f64, 50f64, -2f64, -1f64],
+                [100f64, 0f64, 0f64, -2f64],
+            ],
+        );
+        assert_maps(
+            &mapper(Viewport2DModification::LooseAspectRatio),
+            &[
+                [100f64, 100f64, 0f64, 0f64],
+                [200f64, 200f64, 2f64, 1f64],
+                [0f64, 0f64, -2f64, -1f64],
+            ],
+        );
+    }
+
+    #[test]
+    fn flipped_axes_are_mirrored_around_the_center() {
+        let mut flipped = mapper(Viewport2DModification::KeepAspectRatio);
+        if let Some((viewport, _)) = &mut flipped.viewport {
+            *viewport = viewport.flipped_x_axis(true).flipped_y_axis(true);
+        }
+        assert_maps(
+            &flipped,
+            &[
+                [100f64, 100f64, 0f64, 0f64],
+                [200f64, 150f64, -2f64, -1f64],
+                [0f64, 50f64, 2f64, 1f64],
+            ],
+        );
+        let mut upwards = mapper(Viewport2DModification::KeepAspectRatio);
+        if let Some((viewport, _)) = &mut upwards.viewport {
+            *viewport = viewport.flipped_y_axis(true);
+        }
+        assert_maps(&upwards, &[[200f64, 50f64, 2f64, 1f64]]);
+    }
+
+    #[test]
+    fn letterbox_borders_are_only_cut_off_with_scissor() {
+        let kept = mapper(Viewport2DModification::KeepAspectRatio);
+        let scissored = mapper(Viewport2DModification::KeepAspectRatioAndScissorRemains);
+        let border = Position2D::with(100f64, 25f64);
+        let inside = Position2D::with(100f64, 75f64);
+
+        for mapper in [&kept, &scissored] {
+            assert_positions_close(
+                &mapper.window_to_world(&border).unwrap(),
+                &Position2D::with(0f64, -1.5f64),
+            );
+            assert!(mapper.is_visible(&inside));
+            assert!(!mapper.is_visible(&Position2D::with(201f64, 100f64)));
+        }
+        assert!(kept.is_visible(&border));
+        assert!(!scissored.is_visible(&border));
+        assert_eq!(
+            scissored.layout().unwrap().scissor,
+            Some((Position2D::with(0f64, 50f64), Size2D::with(200f64, 100f64)))
+        );
+    }
+
+    #[test]
+    fn resizes_change_the_layout() {
+        let mut mapper = mapper(Viewport2DModification::KeepAspectRatio);
+        assert!(!mapper.update_from_input(&mouse(0f64, 0f64)));
+        assert!(mapper.update_from_input(&Input::Resize(ResizeArgs {
+            window_size: [400f64, 200f64],
+            draw_size: [800, 400],
+        })));
+        assert_eq!(mapper.window_size, Size2D::with(400f64, 200f64));
+        // 100 pixels per world unit now, filling the whole window
+        assert_maps(
+            &mapper,
+            &[[200f64, 100f64, 0f64, 0f64], [400f64, 200f64, 2f64, 1f64]],
+        );
+
+        mapper.resize(&ResizeArgs {
+            window_size: [0f64, 200f64],
+            draw_size: [0, 400],
+        });
+        assert_eq!(mapper.window_to_world(&Position2D::zero()), None);
+    }
+
+    #[test]
+    fn touches_are_relative_to_the_window() {
+        let mapper = mapper(Viewport2DModification::KeepAspectRatio);
+        let touch = |x: f64, y: f64| {
+            Input::Move(Motion::Touch(TouchArgs::new(
+                0,
+                0,
+                [x, y],
+                1f64,
+                Touch::Move,
+            )))
+        };
+        assert_positions_close(
+            &mapper.input_to_world(&touch(0.5f64, 0.5f64)).unwrap(),
+            &Position2D::zero(),
+        );
+        assert_positions_close(
+            &mapper.input_to_world(&touch(1f64, 0.75f64)).unwrap(),
+            &Position2D::with(2f64, 1f64),
+        );
+        assert_eq!(
+            mapper.input_to_world(&Input::Button(ButtonArgs {
+                state: ButtonState::Press,
+                button: Button::Mouse(MouseButton::Left),
+                scancode: None,
+            })),
+            None
+        );
+    }
+
+    #[test]
+    fn without_viewport_world_positions_are_window_positions() {
+        let mapper = ViewMapper::with(Size2D::with(200f64, 100f64));
+        let position = Position2D::with(30f64, 40f64);
+        assert_eq!(mapper.layout(), None);
+        assert_eq!(mapper.window_to_world(&position), Some(position));
+        assert_eq!(mapper.world_to_window(&position), position);
+        assert!(mapper.is_visible(&position));
+        assert!(!mapper.is_visible(&Position2D::with(30f64, 101f64)));
+    }
+}